@@ -0,0 +1,649 @@
+//! Command line tool to inspect and decompress deflate64 data.
+
+use deflate64::checkpoint::MAX_CHECKPOINT_SIZE;
+use deflate64::zip::{CompressionMethod, ZipArchive};
+use deflate64::{BlockType, Deflate64Encoder, InflateError, InflaterManaged};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: deflate64 <COMMAND> [OPTIONS]
+
+Commands:
+  inflate [--deflate] [--offset N] [INPUT] [-o OUTPUT]
+      Decompresses raw deflate64 stream starting at byte offset N of INPUT
+  deflate [--level N] [INPUT] [-o OUTPUT]
+      Compresses INPUT to raw deflate64 stream with level 0-9
+  unzip [-d DIR] [-l] ARCHIVE [NAME...]
+      Extracts (or lists with -l) stored, deflate, and deflate64 entries of zip ARCHIVE
+  inspect [--deflate] [--offset N] [INPUT]
+      Prints the header of each block and statistics of the stream
+  checkpoint [--deflate] [--offset N] [--interval BYTES] --checkpoint FILE INPUT -o OUTPUT
+      Decompresses INPUT to OUTPUT, saving checkpoint to FILE every BYTES of output
+  resume [--deflate] [--offset N] [--interval BYTES] --checkpoint FILE INPUT -o OUTPUT
+      Resumes decompression from checkpoint FILE made by checkpoint command
+      with the same --deflate and --offset
+
+INPUT and OUTPUT default to stdin and stdout, or can be '-'.
+--deflate decodes plain deflate stream instead of deflate64.
+
+Exit status:
+  0  success
+  1  I/O error
+  2  invalid command line arguments
+  3  corrupt compressed data, or CRC-32 or size mismatch of zip entry
+  4  truncated input
+";
+
+const DEFAULT_CHECKPOINT_INTERVAL: u64 = 256 * 1024 * 1024;
+
+const CHECKPOINT_FILE_MAGIC: [u8; 4] = *b"D64C";
+// The magic, --deflate flag, and --offset
+const CHECKPOINT_FILE_HEADER_SIZE: usize = 13;
+
+enum CliError {
+    Usage(String),
+    Io(io::Error),
+    /// The data doesn't match CRC-32 or size of zip entry
+    Corrupt(io::Error),
+}
+
+impl From<io::Error> for CliError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+type CliResult = Result<(), CliError>;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(CliError::Usage(message)) => {
+            eprintln!("deflate64: {message}");
+            eprint!("{USAGE}");
+            ExitCode::from(2)
+        }
+        Err(CliError::Io(error)) => {
+            eprintln!("deflate64: {error}");
+            ExitCode::from(exit_code(&error))
+        }
+        Err(CliError::Corrupt(error)) => {
+            eprintln!("deflate64: {error}");
+            ExitCode::from(3)
+        }
+    }
+}
+
+fn exit_code(error: &io::Error) -> u8 {
+    match error.kind() {
+        io::ErrorKind::UnexpectedEof => 4,
+        _ if error
+            .get_ref()
+            .is_some_and(|inner| inner.is::<InflateError>()) =>
+        {
+            3
+        }
+        _ => 1,
+    }
+}
+
+fn run(args: Vec<String>) -> CliResult {
+    let mut args = Args::new(args);
+    let command = args
+        .next_positional()
+        .ok_or_else(|| CliError::Usage("no command".into()))?;
+    match command.as_str() {
+        "inflate" => inflate(args),
+        "deflate" => deflate(args),
+        "unzip" => unzip(args),
+        "inspect" => inspect(args),
+        "checkpoint" => checkpoint(args, false),
+        "resume" => checkpoint(args, true),
+        "help" | "--help" | "-h" => {
+            print!("{USAGE}");
+            Ok(())
+        }
+        _ => Err(CliError::Usage(format!("unknown command: {command}"))),
+    }
+}
+
+/// Minimal command line parser
+struct Args {
+    args: Vec<String>,
+    positional: Vec<String>,
+}
+
+impl Args {
+    fn new(mut args: Vec<String>) -> Self {
+        args.reverse();
+        Self {
+            args,
+            positional: Vec::new(),
+        }
+    }
+
+    fn next_positional(&mut self) -> Option<String> {
+        self.args.pop()
+    }
+
+    /// Parses all options with the callback which returns true if the option takes value
+    fn parse(
+        &mut self,
+        mut option: impl FnMut(&str, Option<String>) -> Result<bool, CliError>,
+    ) -> CliResult {
+        while let Some(arg) = self.args.pop() {
+            if arg == "--" {
+                self.positional.extend(self.args.drain(..).rev());
+            } else if arg.starts_with('-') && arg != "-" {
+                let value = self.args.last().cloned();
+                if option(&arg, value)? {
+                    self.args.pop();
+                }
+            } else {
+                self.positional.push(arg);
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self, max: usize) -> Result<Vec<String>, CliError> {
+        if self.positional.len() > max {
+            return Err(CliError::Usage(format!(
+                "unexpected argument: {}",
+                self.positional[max]
+            )));
+        }
+        Ok(self.positional)
+    }
+}
+
+fn required(name: &str, value: Option<String>) -> Result<String, CliError> {
+    value.ok_or_else(|| CliError::Usage(format!("{name} requires a value")))
+}
+
+fn number(name: &str, value: Option<String>) -> Result<u64, CliError> {
+    let value = required(name, value)?;
+    value
+        .parse()
+        .map_err(|_| CliError::Usage(format!("invalid number for {name}: {value}")))
+}
+
+fn unknown_option(name: &str) -> CliError {
+    CliError::Usage(format!("unknown option: {name}"))
+}
+
+/// Options common for decompressing commands
+#[derive(Default)]
+struct InflateOptions {
+    deflate: bool,
+    offset: u64,
+    output: Option<String>,
+    interval: Option<u64>,
+    checkpoint: Option<String>,
+}
+
+impl InflateOptions {
+    fn parse(args: &mut Args, extra: bool) -> Result<Self, CliError> {
+        let mut options = Self::default();
+        args.parse(|name, value| {
+            match name {
+                "--deflate" => options.deflate = true,
+                "--offset" => {
+                    options.offset = number(name, value)?;
+                    return Ok(true);
+                }
+                "-o" | "--output" => {
+                    options.output = Some(required(name, value)?);
+                    return Ok(true);
+                }
+                "--interval" if extra => {
+                    options.interval = Some(number(name, value)?);
+                    return Ok(true);
+                }
+                "--checkpoint" if extra => {
+                    options.checkpoint = Some(required(name, value)?);
+                    return Ok(true);
+                }
+                _ => return Err(unknown_option(name)),
+            }
+            Ok(false)
+        })?;
+        Ok(options)
+    }
+
+    fn new_inflater(&self) -> Box<InflaterManaged> {
+        Box::new(if self.deflate {
+            InflaterManaged::new_deflate()
+        } else {
+            InflaterManaged::new()
+        })
+    }
+}
+
+fn open_input(path: Option<&str>, offset: u64) -> io::Result<Box<dyn BufRead>> {
+    match path {
+        None | Some("-") => {
+            let mut stdin = io::stdin().lock();
+            let skipped = io::copy(&mut (&mut stdin).take(offset), &mut io::sink())?;
+            if skipped != offset {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "offset is beyond the end of input",
+                ));
+            }
+            Ok(Box::new(stdin))
+        }
+        Some(path) => {
+            let mut file = File::open(path)?;
+            file.seek(SeekFrom::Start(offset))?;
+            Ok(Box::new(BufReader::new(file)))
+        }
+    }
+}
+
+fn create_output(path: Option<&str>) -> io::Result<Box<dyn Write>> {
+    match path {
+        None | Some("-") => Ok(Box::new(io::stdout().lock())),
+        Some(path) => Ok(Box::new(BufWriter::new(File::create(path)?))),
+    }
+}
+
+/// Decompresses whole `input` to `output`.
+///
+/// `on_progress` is called with the inflater and the count of bytes written
+/// after each write to `output`.
+fn inflate_to(
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+    inflater: &mut InflaterManaged,
+    mut on_progress: impl FnMut(&InflaterManaged, &mut dyn Write, usize) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut buffer = vec![0u8; 64 * 1024];
+    while !inflater.finished() {
+        let input_buf = input.fill_buf()?;
+        let eof = input_buf.is_empty();
+
+        let result = inflater.inflate(input_buf, &mut buffer);
+        input.consume(result.bytes_consumed);
+
+        // the output before the error is written first
+        output.write_all(&buffer[..result.bytes_written])?;
+        if let Some(error) = result.error {
+            output.flush()?;
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, error));
+        }
+        on_progress(inflater, output, result.bytes_written)?;
+
+        if result.bytes_written == 0 && eof && !inflater.finished() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "unexpected end of deflate64 stream",
+            ));
+        }
+    }
+    output.flush()
+}
+
+fn inflate(mut args: Args) -> CliResult {
+    let options = InflateOptions::parse(&mut args, false)?;
+    let positional = args.finish(1)?;
+    let mut input = open_input(positional.first().map(String::as_str), options.offset)?;
+    let mut output = create_output(options.output.as_deref())?;
+    let mut inflater = options.new_inflater();
+    inflate_to(&mut input, &mut output, &mut inflater, |_, _, _| Ok(()))?;
+    Ok(())
+}
+
+fn deflate(mut args: Args) -> CliResult {
+    let mut level = deflate64::DeflaterManaged::DEFAULT_LEVEL;
+    let mut output = None;
+    args.parse(|name, value| match name {
+        "--level" => {
+            level = number(name, value)?
+                .try_into()
+                .ok()
+                .filter(|level| *level <= 9)
+                .ok_or_else(|| CliError::Usage("level must be in 0-9".into()))?;
+            Ok(true)
+        }
+        "-o" | "--output" => {
+            output = Some(required(name, value)?);
+            Ok(true)
+        }
+        _ => Err(unknown_option(name)),
+    })?;
+    let positional = args.finish(1)?;
+
+    let mut input = open_input(positional.first().map(String::as_str), 0)?;
+    let mut encoder = Deflate64Encoder::with_level(create_output(output.as_deref())?, level);
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.flush()?;
+    Ok(())
+}
+
+/// Returns the relative path to extract the entry, or None if the name is not safe
+fn entry_path(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then(|| path.to_path_buf())
+}
+
+fn unzip(mut args: Args) -> CliResult {
+    let mut directory = PathBuf::from(".");
+    let mut list = false;
+    args.parse(|name, value| match name {
+        "-d" => {
+            directory = required(name, value)?.into();
+            Ok(true)
+        }
+        "-l" => {
+            list = true;
+            Ok(false)
+        }
+        _ => Err(unknown_option(name)),
+    })?;
+    let mut positional = args.finish(usize::MAX)?.into_iter();
+    let archive = positional
+        .next()
+        .ok_or_else(|| CliError::Usage("no archive".into()))?;
+    let names: Vec<String> = positional.collect();
+
+    let mut archive = ZipArchive::new(BufReader::new(File::open(archive)?))?;
+
+    let indices = if names.is_empty() {
+        (0..archive.len()).collect::<Vec<_>>()
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                archive.index_of(name).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, format!("entry not found: {name}"))
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?
+    };
+
+    let mut stdout = io::stdout().lock();
+    for index in indices {
+        let entry = &archive.entries()[index];
+        if list {
+            let method = match entry.compression_method() {
+                CompressionMethod::Stored => "stored".to_string(),
+                CompressionMethod::Deflate => "deflate".to_string(),
+                CompressionMethod::Deflate64 => "deflate64".to_string(),
+                CompressionMethod::Unsupported(method) => format!("method {method}"),
+            };
+            writeln!(
+                stdout,
+                "{:>12} {:>12} {:<10} {}",
+                entry.size(),
+                entry.compressed_size(),
+                method,
+                entry.name()
+            )?;
+            continue;
+        }
+
+        let path = entry_path(entry.name()).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsafe entry name: {}", entry.name()),
+            )
+        })?;
+        let path = directory.join(path);
+        if entry.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut reader = archive.by_index(index)?;
+        let mut file = BufWriter::new(File::create(&path)?);
+        copy_entry(&mut reader, &mut file)?;
+        file.flush()?;
+    }
+    Ok(())
+}
+
+/// Copies the zip entry to `output`, reporting CRC-32 and size mismatches as corrupt data
+fn copy_entry(reader: &mut impl Read, output: &mut impl Write) -> CliResult {
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(read) => read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            // the entry reader returns InvalidData only for the mismatches
+            Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                return Err(CliError::Corrupt(error))
+            }
+            Err(error) => return Err(error.into()),
+        };
+        output.write_all(&buffer[..read])?;
+    }
+}
+
+#[derive(Default)]
+struct BlockStats {
+    count: u64,
+    compressed_bits: u64,
+    uncompressed_size: u64,
+}
+
+fn inspect(mut args: Args) -> CliResult {
+    let options = InflateOptions::parse(&mut args, false)?;
+    if options.output.is_some() {
+        return Err(unknown_option("-o"));
+    }
+    let positional = args.finish(1)?;
+    let mut input = open_input(positional.first().map(String::as_str), options.offset)?;
+    let mut inflater = options.new_inflater();
+    let mut stdout = io::stdout().lock();
+
+    let mut stats: [BlockStats; 3] = Default::default();
+    let mut index = 0u64;
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut result = Ok(());
+
+    writeln!(
+        stdout,
+        "{:>6} {:<12} {:<5} {:>14} {:>12} {:>12} {:>6} {:>6} {:>6}",
+        "block", "type", "final", "start bit", "bits", "size", "hlit", "hdist", "hclen"
+    )?;
+    while !inflater.finished() {
+        let input_buf = input.fill_buf()?;
+        let eof = input_buf.is_empty();
+
+        let mut output_error = Ok(());
+        let inflate_result = inflater.inflate_with_events(input_buf, &mut buffer, |info| {
+            let type_index = info.block_type() as usize;
+            stats[type_index].count += 1;
+            stats[type_index].compressed_bits += info.compressed_bits();
+            stats[type_index].uncompressed_size += info.uncompressed_size();
+            let block_type = match info.block_type() {
+                BlockType::Uncompressed => "uncompressed",
+                BlockType::Static => "static",
+                BlockType::Dynamic => "dynamic",
+            };
+            if output_error.is_ok() {
+                output_error = writeln!(
+                    stdout,
+                    "{:>6} {:<12} {:<5} {:>14} {:>12} {:>12} {:>6} {:>6} {:>6}",
+                    index,
+                    block_type,
+                    info.is_final(),
+                    info.start_bit_offset(),
+                    info.compressed_bits(),
+                    info.uncompressed_size(),
+                    info.literal_length_code_count(),
+                    info.distance_code_count(),
+                    info.code_length_code_count(),
+                );
+            }
+            index += 1;
+        });
+        input.consume(inflate_result.bytes_consumed);
+        output_error?;
+
+        if let Some(error) = inflate_result.error {
+            result = Err(io::Error::new(io::ErrorKind::InvalidInput, error));
+            break;
+        }
+        if inflate_result.bytes_written == 0 && eof && !inflater.finished() {
+            result = Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "unexpected end of deflate64 stream",
+            ));
+            break;
+        }
+    }
+
+    writeln!(stdout)?;
+    let mut total = BlockStats::default();
+    for (name, stats) in ["uncompressed", "static", "dynamic"].iter().zip(&stats) {
+        writeln!(
+            stdout,
+            "{name:<12} {:>6} blocks {:>14} bytes from {:>14} bytes ({:.1}%)",
+            stats.count,
+            stats.uncompressed_size,
+            stats.compressed_bits.div_ceil(8),
+            ratio(stats),
+        )?;
+        total.count += stats.count;
+        total.compressed_bits += stats.compressed_bits;
+        total.uncompressed_size += stats.uncompressed_size;
+    }
+    writeln!(
+        stdout,
+        "{:<12} {:>6} blocks {:>14} bytes from {:>14} bytes ({:.1}%)",
+        "total",
+        total.count,
+        total.uncompressed_size,
+        total.compressed_bits.div_ceil(8),
+        ratio(&total),
+    )?;
+    stdout.flush()?;
+
+    Ok(result?)
+}
+
+/// Returns the compressed size relative to the uncompressed size in percent
+fn ratio(stats: &BlockStats) -> f64 {
+    if stats.uncompressed_size == 0 {
+        0.0
+    } else {
+        stats.compressed_bits as f64 / 8.0 / stats.uncompressed_size as f64 * 100.0
+    }
+}
+
+/// Returns the header of checkpoint file, which records the options the checkpoint depends on
+fn checkpoint_file_header(options: &InflateOptions) -> [u8; CHECKPOINT_FILE_HEADER_SIZE] {
+    let mut header = [0u8; CHECKPOINT_FILE_HEADER_SIZE];
+    header[..4].copy_from_slice(&CHECKPOINT_FILE_MAGIC);
+    header[4] = options.deflate as u8;
+    header[5..].copy_from_slice(&options.offset.to_le_bytes());
+    header
+}
+
+/// Writes the file atomically by renaming temporary file
+fn write_atomically(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(data)?;
+    file.sync_data()?;
+    fs::rename(&temporary, path)
+}
+
+fn checkpoint(mut args: Args, resume: bool) -> CliResult {
+    let options = InflateOptions::parse(&mut args, true)?;
+    let positional = args.finish(1)?;
+    let input_path = positional
+        .first()
+        .ok_or_else(|| CliError::Usage("no input file".into()))?;
+    let output_path = options
+        .output
+        .as_deref()
+        .ok_or_else(|| CliError::Usage("no output file".into()))?;
+    let checkpoint_path = PathBuf::from(
+        options
+            .checkpoint
+            .as_deref()
+            .ok_or_else(|| CliError::Usage("no checkpoint file".into()))?,
+    );
+    let interval = options.interval.unwrap_or(DEFAULT_CHECKPOINT_INTERVAL);
+    if interval == 0 {
+        return Err(CliError::Usage("interval must not be zero".into()));
+    }
+
+    let header = checkpoint_file_header(&options);
+    let mut inflater = options.new_inflater();
+    let (input_offset, output_offset, output) = if resume {
+        let data = fs::read(&checkpoint_path)?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid checkpoint file");
+        if data.len() < header.len() || data[..4] != CHECKPOINT_FILE_MAGIC {
+            return Err(invalid().into());
+        }
+        if data[..header.len()] != header {
+            return Err(CliError::Usage(
+                "checkpoint file was made with different --deflate or --offset".into(),
+            ));
+        }
+        let positions = inflater
+            .restore_from_checkpoint(&data[header.len()..])
+            .ok_or_else(invalid)?;
+        let mut output = OpenOptions::new().write(true).open(output_path)?;
+        if output.metadata()?.len() < positions.output_bytes_already_returned {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "output file is shorter than the checkpoint",
+            )
+            .into());
+        }
+        output.set_len(positions.output_bytes_already_returned)?;
+        output.seek(SeekFrom::End(0))?;
+        (
+            positions.input_bytes_to_skip,
+            positions.output_bytes_already_returned,
+            output,
+        )
+    } else {
+        (0, 0, File::create(output_path)?)
+    };
+
+    let mut input = open_input(Some(input_path), options.offset + input_offset)?;
+    // the handle to sync the output written through BufWriter
+    let output_file = output.try_clone()?;
+    let mut output = BufWriter::new(output);
+    let mut written = output_offset;
+    let mut next_checkpoint = output_offset + interval;
+    let mut checkpoint_file = vec![0u8; header.len() + MAX_CHECKPOINT_SIZE];
+    checkpoint_file[..header.len()].copy_from_slice(&header);
+    inflate_to(
+        &mut input,
+        &mut output,
+        &mut inflater,
+        |inflater, output, bytes_written| {
+            written += bytes_written as u64;
+            if written >= next_checkpoint {
+                let data = &mut checkpoint_file[header.len()..];
+                if let Some((len, positions)) = inflater.checkpoint_into(data) {
+                    // the output must have the data the checkpoint refers to
+                    // on the disk before the checkpoint
+                    output.flush()?;
+                    output_file.sync_data()?;
+                    write_atomically(&checkpoint_path, &checkpoint_file[..header.len() + len])?;
+                    next_checkpoint = positions.output_bytes_already_returned + interval;
+                }
+            }
+            Ok(())
+        },
+    )?;
+    Ok(())
+}
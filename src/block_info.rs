@@ -0,0 +1,90 @@
+use crate::huffman_tree::{
+    MAX_DIST_TREE_ELEMENTS, MAX_LITERAL_TREE_ELEMENTS, NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS,
+};
+use crate::BlockType;
+
+/// The information of a block in deflate64 stream
+///
+/// This is reported by [`InflaterManaged::inflate_with_events`](crate::InflaterManaged::inflate_with_events)
+/// when the end of the block is decoded.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BlockInfo {
+    pub(crate) block_type: BlockType,
+    pub(crate) is_final: bool,
+    pub(crate) start_bit_offset: u64,
+    pub(crate) end_bit_offset: u64,
+    pub(crate) uncompressed_size: u64,
+    pub(crate) literal_length_code_count: u16,
+    pub(crate) distance_code_count: u8,
+    pub(crate) code_length_code_count: u8,
+    pub(crate) code_length_code_lengths: [u8; NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS],
+    pub(crate) literal_length_code_lengths: [u8; MAX_LITERAL_TREE_ELEMENTS],
+    pub(crate) distance_code_lengths: [u8; MAX_DIST_TREE_ELEMENTS],
+}
+
+impl BlockInfo {
+    /// Returns the type of the block
+    pub fn block_type(&self) -> BlockType {
+        self.block_type
+    }
+
+    /// Returns true if BFINAL is set, which means this is the last block
+    pub fn is_final(&self) -> bool {
+        self.is_final
+    }
+
+    /// Returns the offset in bits from the start of the input where the block starts
+    pub fn start_bit_offset(&self) -> u64 {
+        self.start_bit_offset
+    }
+
+    /// Returns the offset in bits from the start of the input just after the end of the block
+    pub fn end_bit_offset(&self) -> u64 {
+        self.end_bit_offset
+    }
+
+    /// Returns the size of the block in bits including the block header
+    pub fn compressed_bits(&self) -> u64 {
+        self.end_bit_offset - self.start_bit_offset
+    }
+
+    /// Returns the count of bytes decompressed from the block
+    pub fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+
+    /// Returns the count of literal/length codes (HLIT + 257), or 0 for non-dynamic blocks
+    pub fn literal_length_code_count(&self) -> usize {
+        self.literal_length_code_count as usize
+    }
+
+    /// Returns the count of distance codes (HDIST + 1), or 0 for non-dynamic blocks
+    pub fn distance_code_count(&self) -> usize {
+        self.distance_code_count as usize
+    }
+
+    /// Returns the count of code length codes (HCLEN + 4), or 0 for non-dynamic blocks
+    pub fn code_length_code_count(&self) -> usize {
+        self.code_length_code_count as usize
+    }
+
+    /// Returns the code lengths of the code length alphabet indexed by the symbol,
+    /// or empty slice for non-dynamic blocks
+    pub fn code_length_code_lengths(&self) -> &[u8] {
+        if self.code_length_code_count == 0 {
+            &[]
+        } else {
+            &self.code_length_code_lengths
+        }
+    }
+
+    /// Returns the code lengths of the literal/length alphabet, or empty slice for non-dynamic blocks
+    pub fn literal_length_code_lengths(&self) -> &[u8] {
+        &self.literal_length_code_lengths[..self.literal_length_code_count as usize]
+    }
+
+    /// Returns the code lengths of the distance alphabet, or empty slice for non-dynamic blocks
+    pub fn distance_code_lengths(&self) -> &[u8] {
+        &self.distance_code_lengths[..self.distance_code_count as usize]
+    }
+}
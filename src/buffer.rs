@@ -1,9 +1,11 @@
-use std::mem::MaybeUninit;
-use std::ops::{IndexMut, RangeBounds};
+use core::mem::MaybeUninit;
+use core::ops::{Bound, IndexMut, RangeBounds};
 
 pub(crate) enum Buffer<'a> {
     Uninit(&'a mut [MaybeUninit<u8>]),
     Init(&'a mut [u8]),
+    /// The buffer which discards written bytes
+    Discard(usize),
 }
 
 impl<'b> Buffer<'b> {
@@ -15,6 +17,7 @@ impl<'b> Buffer<'b> {
         match &mut *self {
             Self::Uninit(uninit) => Buffer::Uninit(&mut uninit[..]),
             Self::Init(init) => Buffer::Init(&mut init[..]),
+            Self::Discard(len) => Buffer::Discard(*len),
         }
     }
 
@@ -25,6 +28,20 @@ impl<'b> Buffer<'b> {
         match self {
             Buffer::Uninit(uninit) => Buffer::Uninit(uninit.index_mut(range)),
             Buffer::Init(init) => Buffer::Init(init.index_mut(range)),
+            Buffer::Discard(len) => {
+                let start = match range.0 {
+                    Bound::Included(start) => start,
+                    Bound::Excluded(start) => start + 1,
+                    Bound::Unbounded => 0,
+                };
+                let end = match range.1 {
+                    Bound::Included(end) => end + 1,
+                    Bound::Excluded(end) => end,
+                    Bound::Unbounded => len,
+                };
+                assert!(start <= end && end <= len, "range out of bounds");
+                Buffer::Discard(end - start)
+            }
         }
     }
 
@@ -41,6 +58,7 @@ impl<'b> Buffer<'b> {
                     });
             }
             Buffer::Init(init) => init.copy_from_slice(input),
+            Buffer::Discard(len) => debug_assert_eq!(*len, input.len()),
         };
     }
 
@@ -49,6 +67,7 @@ impl<'b> Buffer<'b> {
         match self {
             Buffer::Uninit(uninit) => uninit.len(),
             Buffer::Init(init) => init.len(),
+            Buffer::Discard(len) => *len,
         }
     }
 
@@ -0,0 +1,81 @@
+/// <summary>
+/// CRC-32 (IEEE 802.3) used by zip and gzip.
+/// </summary>
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Crc32 {
+    crc: u32,
+}
+
+static CRC32_TABLE: [u32; 256] = make_crc32_table();
+
+const fn make_crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+impl Crc32 {
+    pub fn new() -> Self {
+        Self { crc: !0 }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let mut crc = self.crc;
+        for &byte in data {
+            crc = CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+        }
+        self.crc = crc;
+    }
+
+    pub fn value(&self) -> u32 {
+        !self.crc
+    }
+}
+
+/// <summary>
+/// Adler-32 used by zlib.
+/// </summary>
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+const ADLER32_MOD: u32 = 65521;
+// the largest n such that 255 * n * (n + 1) / 2 + (n + 1) * (ADLER32_MOD - 1) fits in u32
+const ADLER32_NMAX: usize = 5552;
+
+impl Adler32 {
+    pub fn new() -> Self {
+        Self { a: 1, b: 0 }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for chunk in data.chunks(ADLER32_NMAX) {
+            for &byte in chunk {
+                self.a += byte as u32;
+                self.b += self.a;
+            }
+            self.a %= ADLER32_MOD;
+            self.b %= ADLER32_MOD;
+        }
+    }
+
+    pub fn value(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
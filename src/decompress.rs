@@ -0,0 +1,91 @@
+use crate::error::InflateError;
+use crate::{InflateLimits, InflaterManaged};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::{max, min};
+
+// The output buffer grows at least by this size when the size is not known
+const MIN_GROWTH: usize = 64 * 1024;
+
+// The size hint is trusted only up to about the largest output of deflate for the input,
+// the rest of the output of a deflate64 stream is decoded by growing the buffer
+const MAX_RATIO: usize = 1032;
+
+/// Decompresses the whole deflate64 stream in `input`
+///
+/// Returns the decompressed data and the count of bytes of `input` the stream used.
+/// The data after the end of the stream is ignored.
+/// If the input ends before the end of the stream, this returns the error with
+/// [`UnexpectedEndOfInput`](crate::InflateErrorKind::UnexpectedEndOfInput).
+///
+/// ```
+/// # let compressed = [0x01, 0x05, 0x00, 0xfa, 0xff, b'h', b'e', b'l', b'l', b'o', 0xAA];
+/// let (data, used) = deflate64::decompress_to_vec(&compressed)?;
+/// assert_eq!(data, b"hello");
+/// assert_eq!(used, 10);
+/// # Ok::<(), deflate64::InflateError>(())
+/// ```
+pub fn decompress_to_vec(input: &[u8]) -> Result<(Vec<u8>, usize), InflateError> {
+    decompress_to_vec_with_limit(input, None, usize::MAX)
+}
+
+/// Same as [`decompress_to_vec`] but stops with
+/// [`OutputLimitExceeded`](crate::InflateErrorKind::OutputLimitExceeded)
+/// if the output is longer than `max_output` bytes
+///
+/// If `size_hint` is the decompressed size, the output is decoded directly into the
+/// returned `Vec` without growing it. The first allocation is limited by the size of
+/// `input`, so a wrong `size_hint` doesn't allocate much more than the output needs.
+pub fn decompress_to_vec_with_limit(
+    input: &[u8],
+    size_hint: Option<usize>,
+    max_output: usize,
+) -> Result<(Vec<u8>, usize), InflateError> {
+    let mut inflater = Box::new(InflaterManaged::new());
+    inflater.set_limits(InflateLimits::new().max_output(max_output as u64));
+    // one more byte than the limit is needed to find the output exceeding the limit
+    let capacity = max_output.saturating_add(1);
+
+    let size = match size_hint {
+        Some(size) => min(
+            size,
+            input
+                .len()
+                .saturating_mul(MAX_RATIO)
+                .saturating_add(MIN_GROWTH),
+        ),
+        None => input.len().saturating_mul(2),
+    };
+    let mut output = vec![0u8; min(size, capacity)];
+    let result = inflater.inflate_to_slice(input, &mut output);
+    let mut consumed = result.bytes_consumed;
+    let mut written = result.bytes_written;
+    let mut error = result.error;
+
+    while error.is_none() && !inflater.finished() {
+        if written == output.len() {
+            let len = min(written.saturating_add(max(written, MIN_GROWTH)), capacity);
+            output.resize(len, 0);
+        }
+        let result = inflater.inflate(&input[consumed..], &mut output[written..]);
+        consumed += result.bytes_consumed;
+        written += result.bytes_written;
+        error = result.error;
+        if error.is_none()
+            && !inflater.finished()
+            && result.bytes_written == 0
+            && consumed == input.len()
+        {
+            error = Some(inflater.unexpected_end_of_input());
+        }
+    }
+
+    match error {
+        Some(error) => Err(error),
+        None => {
+            output.truncate(written);
+            Ok((output, consumed))
+        }
+    }
+}
@@ -0,0 +1,852 @@
+use crate::buffer::Buffer;
+use crate::huffman_encoder::HuffmanEncoder;
+use crate::huffman_tree::{
+    END_OF_BLOCK_CODE, MAX_DIST_TREE_ELEMENTS, MAX_LITERAL_TREE_ELEMENTS,
+    NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS,
+};
+use crate::inflater_managed::{
+    CODE_ORDER, DISTANCE_BASE_POSITION, EXTRA_LENGTH_BITS, LENGTH_BASE, TABLE_LOOKUP_DISTANCE_MAX,
+    TABLE_LOOKUP_LENGTH_MAX,
+};
+use crate::output_buffer::OutputBuffer;
+use crate::{BlockType, DeflateFlush, DeflateResult};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::min;
+use core::mem::MaybeUninit;
+
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = TABLE_LOOKUP_LENGTH_MAX;
+const MAX_DISTANCE: usize = TABLE_LOOKUP_DISTANCE_MAX;
+
+// The LZ77 search needs MAX_MATCH bytes of lookahead to find the longest match
+// so we only look for matches if there is enough lookahead unless we're flushing.
+const MIN_LOOKAHEAD: usize = MAX_MATCH + MIN_MATCH + 1;
+
+// The window holds up to MAX_DISTANCE bytes of history before the current block,
+// the current block, and the lookahead bytes.
+const WINDOW_BUFFER_SIZE: usize = 5 * 65536;
+// Slide the window when free space in the window is less than this.
+const MIN_FREE_SPACE: usize = 65536;
+
+// We end the block when the count of tokens or uncompressed bytes of the block reach these.
+// The limit for uncompressed bytes keeps the raw bytes of the block in the window
+// so we can emit the block as uncompressed if it's smaller.
+const MAX_BLOCK_TOKENS: usize = 16384;
+const MAX_BLOCK_UNCOMPRESSED: usize = 65536;
+
+const HASH_BITS: u32 = 15;
+const HASH_SIZE: usize = 1 << HASH_BITS;
+const HASH_MASK: usize = HASH_SIZE - 1;
+const CHAIN_MASK: usize = MAX_DISTANCE - 1;
+
+// For long matches, inserting every position into the hash chain costs too much.
+// We only insert the last positions of such matches.
+const MAX_INSERT_LENGTH: usize = 258;
+
+// Matches of MIN_MATCH with far distance are not worth it.
+const TOO_FAR: usize = 4096;
+
+const END_OF_BLOCK: usize = END_OF_BLOCK_CODE;
+const LENGTH_CODE_START: usize = 257;
+const NUM_LITERAL_CODES: usize = MAX_LITERAL_TREE_ELEMENTS;
+const NUM_DISTANCE_CODES: usize = MAX_DIST_TREE_ELEMENTS;
+const NUM_CODE_LENGTH_CODES: usize = NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS;
+
+const MAX_CODE_LENGTH: u8 = 15;
+const MAX_CODE_LENGTH_CODE_LENGTH: u8 = 7;
+
+// Length codes for length 3 - 258. Longer lengths use code 285 with 16 extra bits.
+static LENGTH_CODE: [u8; 256] = {
+    let mut table = [0u8; 256];
+    let mut code = 0;
+    while code < 28 {
+        let base = LENGTH_BASE[code] as usize - MIN_MATCH;
+        let count = 1 << EXTRA_LENGTH_BITS[code];
+        let mut i = 0;
+        while i < count && base + i < 256 {
+            table[base + i] = code as u8;
+            i += 1;
+        }
+        code += 1;
+    }
+    table
+};
+
+// compression parameters for each level: (good_length, max_lazy, nice_length, max_chain)
+// same as zlib.
+static CONFIGURATION_TABLE: [(usize, usize, usize, usize); 10] = [
+    (0, 0, 0, 0), // store only
+    (4, 4, 8, 4),
+    (4, 5, 16, 8),
+    (4, 6, 32, 32),
+    (4, 4, 16, 16),
+    (8, 16, 32, 32),
+    (8, 16, 128, 128),
+    (8, 32, 128, 256),
+    (32, 128, 258, 1024),
+    (32, 258, 258, 4096),
+];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum DeflaterState {
+    Compressing,
+    // The final block is written to output buffer
+    Finished,
+}
+
+/// A literal or length / distance pair.
+#[derive(Debug, Copy, Clone)]
+struct Token {
+    // zero for literal
+    distance: u32,
+    // literal byte or match length
+    value: u32,
+}
+
+/// The streaming Deflater for deflate64
+///
+/// The compressed stream uses 64 KiB window and can be decompressed with [`InflaterManaged`].
+///
+/// This struct allocates about 1 MiB of buffers for the window and the hash chains.
+///
+/// [`InflaterManaged`]: crate::InflaterManaged
+#[derive(Debug)]
+pub struct DeflaterManaged {
+    output: OutputBuffer,
+    state: DeflaterState,
+
+    // configuration
+    level: u8,
+    good_length: usize,
+    max_lazy: usize,
+    nice_length: usize,
+    max_chain: usize,
+
+    // LZ77 state
+    window: Vec<u8>,
+    head: Box<[u32]>, // position + 1 of latest string with the hash, 0 for none
+    prev: Box<[u32]>, // position + 1 of previous string with the same hash, 0 for none
+    position: usize,  // next byte to process
+    match_length: usize,
+    match_distance: usize,
+    previous_length: usize,
+    previous_distance: usize,
+    match_available: bool, // if true, the byte at position - 1 is not yet emitted
+
+    // current block
+    block_start: usize,
+    block_length: usize,
+    tokens: Vec<Token>,
+
+    // true if we have written sync point and no input is processed after that
+    synced: bool,
+}
+
+impl DeflaterManaged {
+    /// The default compression level
+    pub const DEFAULT_LEVEL: u8 = 6;
+
+    /// Initializes Deflater with default compression level
+    #[allow(clippy::new_without_default)]
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_level(Self::DEFAULT_LEVEL)
+    }
+
+    /// Initializes Deflater with compression level.
+    ///
+    /// The level is in range `0..=9` like zlib, where `0` means no compression and `9` means
+    /// best compression. Levels larger than 9 are treated as 9.
+    pub fn with_level(level: u8) -> Self {
+        let level = min(level, 9);
+        let (good_length, max_lazy, nice_length, max_chain) = CONFIGURATION_TABLE[level as usize];
+        Self {
+            output: OutputBuffer::new(),
+            state: DeflaterState::Compressing,
+            level,
+            good_length,
+            max_lazy,
+            nice_length,
+            max_chain,
+            window: Vec::with_capacity(WINDOW_BUFFER_SIZE),
+            head: vec![0; HASH_SIZE].into_boxed_slice(),
+            prev: vec![0; MAX_DISTANCE].into_boxed_slice(),
+            position: 0,
+            match_length: MIN_MATCH - 1,
+            match_distance: 0,
+            previous_length: MIN_MATCH - 1,
+            previous_distance: 0,
+            match_available: false,
+            block_start: 0,
+            block_length: 0,
+            tokens: Vec::with_capacity(MAX_BLOCK_TOKENS),
+            synced: false,
+        }
+    }
+
+    /// Sets the preset dictionary.
+    ///
+    /// The stream can refer the dictionary with back-references, so the same dictionary
+    /// must be set to the inflater with [`InflaterManaged::set_dictionary`].
+    /// Only the last 64 KiB of the dictionary is used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if compression has already started.
+    ///
+    /// [`InflaterManaged::set_dictionary`]: crate::InflaterManaged::set_dictionary
+    pub fn set_dictionary(&mut self, dictionary: &[u8]) {
+        assert!(
+            self.window.is_empty() && self.state == DeflaterState::Compressing,
+            "set_dictionary must be called before compression"
+        );
+        let dictionary = &dictionary[dictionary.len().saturating_sub(MAX_DISTANCE)..];
+        self.window.extend_from_slice(dictionary);
+        if self.level != 0 {
+            for position in 0..dictionary.len().saturating_sub(MIN_MATCH - 1) {
+                self.insert_string(position);
+            }
+        }
+        self.position = dictionary.len();
+        self.block_start = dictionary.len();
+    }
+
+    /// Returns the compression level of this deflater
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Returns true if the final block is written and no more output is available
+    pub fn finished(&self) -> bool {
+        self.state == DeflaterState::Finished && self.available_output() == 0
+    }
+
+    /// The count of bytes currently deflater has in internal output buffer
+    pub fn available_output(&self) -> usize {
+        self.output.pending_bytes()
+    }
+
+    /// Try to compress from `input` to `output`.
+    ///
+    /// This will compress data until `output` is full or `input` is consumed.
+    /// Compressed data may be kept in internal buffer until enough input is given.
+    ///
+    /// With [`DeflateFlush::Sync`], all input will be written to output followed by an empty
+    /// uncompressed block. With [`DeflateFlush::Finish`], all input will be written and the
+    /// deflate64 stream will be finished. In both cases, call this function with the same
+    /// flush mode until all input is consumed and `output` is not filled up
+    /// (or [`Self::finished`] returns true for [`DeflateFlush::Finish`]).
+    pub fn deflate(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+        flush: DeflateFlush,
+    ) -> DeflateResult {
+        self.deflate_internal(input, Buffer::Init(output), flush)
+    }
+
+    /// Same as [`Self::deflate`] but accepts uninitialized buffer
+    pub fn deflate_uninit(
+        &mut self,
+        input: &[u8],
+        output: &mut [MaybeUninit<u8>],
+        flush: DeflateFlush,
+    ) -> DeflateResult {
+        self.deflate_internal(input, Buffer::Uninit(output), flush)
+    }
+
+    fn deflate_internal(
+        &mut self,
+        mut input: &[u8],
+        mut output: Buffer<'_>,
+        flush: DeflateFlush,
+    ) -> DeflateResult {
+        let mut result = DeflateResult::new();
+
+        loop {
+            let copied = self.output.copy_to(output.reborrow());
+            if copied > 0 {
+                output = output.index_mut(copied..);
+                result.bytes_written += copied;
+            }
+
+            if self.output.pending_bytes() != 0 || self.state == DeflaterState::Finished {
+                // filled in the bytes buffer, or nothing to do
+                break;
+            }
+
+            let filled = self.fill_window(input);
+            if filled > 0 {
+                input = &input[filled..];
+                result.bytes_consumed += filled;
+                self.synced = false;
+            }
+
+            let flushing = flush != DeflateFlush::None && input.is_empty();
+            if self.compress(flushing) {
+                // a block is written. copy them to output
+                continue;
+            }
+
+            if !input.is_empty() {
+                // we need more space in window
+                debug_assert!(filled > 0, "no progress");
+                continue;
+            }
+
+            // we have processed all input which can be processed now.
+            match flush {
+                DeflateFlush::None => break,
+                DeflateFlush::Sync => {
+                    if self.synced {
+                        break;
+                    }
+                    self.flush_pending_literal();
+                    if !self.tokens.is_empty() {
+                        self.write_block(false);
+                    }
+                    // sync point: empty uncompressed block
+                    write_stored_block(&mut self.output, &[], false);
+                    self.synced = true;
+                }
+                DeflateFlush::Finish => {
+                    self.flush_pending_literal();
+                    self.write_block(true);
+                    self.state = DeflaterState::Finished;
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Copies input to the window. Returns the count of bytes copied.
+    fn fill_window(&mut self, input: &[u8]) -> usize {
+        if !input.is_empty() && WINDOW_BUFFER_SIZE - self.window.len() < MIN_FREE_SPACE {
+            self.slide_window();
+        }
+        let length = min(input.len(), WINDOW_BUFFER_SIZE - self.window.len());
+        self.window.extend_from_slice(&input[..length]);
+        length
+    }
+
+    fn slide_window(&mut self) {
+        // We need to keep MAX_DISTANCE bytes for history and the bytes of current block.
+        // The window slides by a multiple of MAX_DISTANCE so that `prev[position & CHAIN_MASK]`
+        // stays at the same index for every position.
+        let delta = min(self.block_start, self.position.saturating_sub(MAX_DISTANCE)) & !CHAIN_MASK;
+        if delta == 0 {
+            return;
+        }
+
+        self.window.copy_within(delta.., 0);
+        self.window.truncate(self.window.len() - delta);
+        self.position -= delta;
+        self.block_start -= delta;
+
+        let delta = delta as u32;
+        for entry in self.head.iter_mut().chain(self.prev.iter_mut()) {
+            *entry = entry.saturating_sub(delta);
+        }
+    }
+
+    #[inline(always)]
+    fn hash(&self, position: usize) -> usize {
+        let bytes = &self.window[position..][..MIN_MATCH];
+        ((bytes[0] as usize) << 10 ^ (bytes[1] as usize) << 5 ^ bytes[2] as usize) & HASH_MASK
+    }
+
+    /// Inserts string at position to the hash chain and returns the previous head of the chain.
+    #[inline(always)]
+    fn insert_string(&mut self, position: usize) -> usize {
+        let hash = self.hash(position);
+        let head = self.head[hash];
+        self.prev[position & CHAIN_MASK] = head;
+        self.head[hash] = position as u32 + 1;
+        head as usize
+    }
+
+    /// Runs LZ77 and collects tokens.
+    ///
+    /// Returns true if a block is written to the output buffer.
+    /// Returns false if more input is needed to continue.
+    fn compress(&mut self, flushing: bool) -> bool {
+        if self.level == 0 {
+            return self.compress_stored(flushing);
+        }
+
+        loop {
+            let lookahead = self.window.len() - self.position;
+            if lookahead < MIN_LOOKAHEAD && (!flushing || lookahead == 0) {
+                return false;
+            }
+
+            let hash_head = if lookahead >= MIN_MATCH {
+                self.insert_string(self.position)
+            } else {
+                0
+            };
+
+            self.previous_length = self.match_length;
+            self.previous_distance = self.match_distance;
+            self.match_length = MIN_MATCH - 1;
+
+            if hash_head != 0
+                && self.previous_length < self.max_lazy
+                && self.position + 1 - hash_head <= MAX_DISTANCE
+            {
+                self.longest_match(hash_head - 1);
+                if self.match_length == MIN_MATCH && self.match_distance > TOO_FAR {
+                    self.match_length = MIN_MATCH - 1;
+                }
+            }
+
+            if self.previous_length >= MIN_MATCH && self.match_length <= self.previous_length {
+                // The match at previous position is better. emit it.
+                let length = self.previous_length;
+                self.push_token(Token {
+                    distance: self.previous_distance as u32,
+                    value: length as u32,
+                });
+
+                // Insert strings of the match to the hash chain.
+                // The string at previous position and current position is already inserted.
+                let match_end = self.position - 1 + length;
+                let insert_end = min(match_end, self.window.len() - (MIN_MATCH - 1));
+                let insert_start =
+                    (self.position + 1).max(match_end.saturating_sub(MAX_INSERT_LENGTH));
+                for position in insert_start..insert_end {
+                    self.insert_string(position);
+                }
+
+                self.position = match_end;
+                self.match_available = false;
+                self.match_length = MIN_MATCH - 1;
+            } else if self.match_available {
+                // No better match at current position. Emit the previous byte as a literal.
+                self.push_token(Token {
+                    distance: 0,
+                    value: self.window[self.position - 1] as u32,
+                });
+                self.position += 1;
+            } else {
+                // Wait for the next position to decide.
+                self.match_available = true;
+                self.position += 1;
+            }
+
+            if self.tokens.len() >= MAX_BLOCK_TOKENS || self.block_length >= MAX_BLOCK_UNCOMPRESSED
+            {
+                self.write_block(false);
+                return true;
+            }
+        }
+    }
+
+    fn compress_stored(&mut self, flushing: bool) -> bool {
+        let lookahead = self.window.len() - self.position;
+        if lookahead >= MAX_BLOCK_UNCOMPRESSED || (flushing && lookahead > 0) {
+            let length = min(lookahead, MAX_BLOCK_UNCOMPRESSED);
+            self.position += length;
+            self.block_length += length;
+            self.write_block(false);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn flush_pending_literal(&mut self) {
+        if self.match_available {
+            self.push_token(Token {
+                distance: 0,
+                value: self.window[self.position - 1] as u32,
+            });
+            self.match_available = false;
+        }
+        self.match_length = MIN_MATCH - 1;
+    }
+
+    #[inline(always)]
+    fn push_token(&mut self, token: Token) {
+        self.block_length += if token.distance == 0 {
+            1
+        } else {
+            token.value as usize
+        };
+        self.tokens.push(token);
+    }
+
+    /// Finds the longest match for current position and saves it to
+    /// `match_length` and `match_distance` if it's longer than `previous_length`.
+    fn longest_match(&mut self, mut candidate: usize) {
+        let position = self.position;
+        let max_length = min(MAX_MATCH, self.window.len() - position);
+        let mut best_length = self.previous_length;
+        let mut chain_length = self.max_chain;
+        if self.previous_length >= self.good_length {
+            chain_length >>= 2;
+        }
+        let nice_length = min(self.nice_length, max_length);
+
+        if best_length >= max_length {
+            return;
+        }
+
+        loop {
+            debug_assert!(candidate < position);
+            let window = &self.window[..];
+            // check the byte at best_length first since it's most likely to differ
+            if window[candidate + best_length] == window[position + best_length] {
+                let length = match_length(window, candidate, position, max_length);
+                if length > best_length {
+                    best_length = length;
+                    self.match_length = length;
+                    self.match_distance = position - candidate;
+                    if length >= nice_length {
+                        break;
+                    }
+                }
+            }
+
+            chain_length -= 1;
+            if chain_length == 0 {
+                break;
+            }
+            let next = self.prev[candidate & CHAIN_MASK] as usize;
+            // The chain must go backwards and must be in the window.
+            if next == 0 || next > candidate || position - (next - 1) > MAX_DISTANCE {
+                break;
+            }
+            candidate = next - 1;
+        }
+    }
+
+    /// Writes current block to output buffer with the smallest block type.
+    fn write_block(&mut self, bfinal: bool) {
+        let raw = &self.window[self.block_start..][..self.block_length];
+
+        let mut literal_frequencies = [0u32; NUM_LITERAL_CODES];
+        let mut distance_frequencies = [0u32; NUM_DISTANCE_CODES];
+        let mut extra_bits = 0u64;
+        for token in &self.tokens {
+            if token.distance == 0 {
+                literal_frequencies[token.value as usize] += 1;
+            } else {
+                let length_code = length_code(token.value as usize);
+                let distance_code = distance_code(token.distance as usize);
+                literal_frequencies[LENGTH_CODE_START + length_code] += 1;
+                distance_frequencies[distance_code] += 1;
+                extra_bits += EXTRA_LENGTH_BITS[length_code] as u64
+                    + distance_extra_bits(distance_code) as u64;
+            }
+        }
+        literal_frequencies[END_OF_BLOCK] += 1;
+
+        let (static_literal, static_distance) = static_encoders();
+        let static_cost = 3
+            + static_literal.cost(&literal_frequencies)
+            + static_distance.cost(&distance_frequencies)
+            + extra_bits;
+
+        let mut dynamic = DynamicHeader::new();
+        dynamic.literal.build(&literal_frequencies, MAX_CODE_LENGTH);
+        dynamic
+            .distance
+            .build(&distance_frequencies, MAX_CODE_LENGTH);
+        dynamic.build_code_length_code();
+        let dynamic_cost = 3
+            + dynamic.cost()
+            + dynamic.literal.cost(&literal_frequencies)
+            + dynamic.distance.cost(&distance_frequencies)
+            + extra_bits;
+
+        // header (3 bits), aligning (up to 7 bits) and LEN / NLEN (32 bits) for each 65535 bytes
+        let stored_cost =
+            (raw.len().div_ceil(65535).max(1) as u64) * (3 + 7 + 32) + raw.len() as u64 * 8;
+
+        if self.level == 0 || (stored_cost <= static_cost && stored_cost <= dynamic_cost) {
+            let raw = &self.window[self.block_start..][..self.block_length];
+            write_stored_block(&mut self.output, raw, bfinal);
+        } else if static_cost <= dynamic_cost {
+            self.output.write_bits(1, bfinal as u32);
+            self.output.write_bits(2, BlockType::Static as u32);
+            write_tokens(
+                &mut self.output,
+                &self.tokens,
+                &static_literal,
+                &static_distance,
+            );
+        } else {
+            self.output.write_bits(1, bfinal as u32);
+            self.output.write_bits(2, BlockType::Dynamic as u32);
+            dynamic.write(&mut self.output);
+            write_tokens(
+                &mut self.output,
+                &self.tokens,
+                &dynamic.literal,
+                &dynamic.distance,
+            );
+        }
+
+        if bfinal {
+            self.output.flush_bits();
+        }
+
+        self.block_start += self.block_length;
+        self.block_length = 0;
+        self.tokens.clear();
+    }
+}
+
+fn write_stored_block(output: &mut OutputBuffer, data: &[u8], bfinal: bool) {
+    let mut chunks = data.chunks(65535).peekable();
+    if chunks.peek().is_none() {
+        // empty block
+        write_stored_block_chunk(output, &[], bfinal);
+    }
+    while let Some(chunk) = chunks.next() {
+        let is_last = chunks.peek().is_none();
+        write_stored_block_chunk(output, chunk, bfinal && is_last);
+    }
+}
+
+fn write_stored_block_chunk(output: &mut OutputBuffer, chunk: &[u8], bfinal: bool) {
+    debug_assert!(chunk.len() <= 65535);
+    output.write_bits(1, bfinal as u32);
+    output.write_bits(2, BlockType::Uncompressed as u32);
+    output.flush_bits();
+    let length = chunk.len() as u16;
+    output.write_bytes(&length.to_le_bytes());
+    output.write_bytes(&(!length).to_le_bytes());
+    output.write_bytes(chunk);
+}
+
+fn write_tokens(
+    output: &mut OutputBuffer,
+    tokens: &[Token],
+    literal: &HuffmanEncoder<NUM_LITERAL_CODES>,
+    distance: &HuffmanEncoder<NUM_DISTANCE_CODES>,
+) {
+    for token in tokens {
+        if token.distance == 0 {
+            literal.write_symbol(output, token.value as usize);
+        } else {
+            let length = token.value as usize;
+            let length_code = length_code(length);
+            literal.write_symbol(output, LENGTH_CODE_START + length_code);
+            output.write_bits(
+                EXTRA_LENGTH_BITS[length_code] as u32,
+                (length - LENGTH_BASE[length_code] as usize) as u32,
+            );
+
+            let distance_value = token.distance as usize;
+            let distance_code = distance_code(distance_value);
+            distance.write_symbol(output, distance_code);
+            output.write_bits(
+                distance_extra_bits(distance_code),
+                (distance_value - DISTANCE_BASE_POSITION[distance_code] as usize) as u32,
+            );
+        }
+    }
+    literal.write_symbol(output, END_OF_BLOCK);
+}
+
+#[inline(always)]
+fn length_code(length: usize) -> usize {
+    debug_assert!((MIN_MATCH..=MAX_MATCH).contains(&length));
+    if length - MIN_MATCH < LENGTH_CODE.len() {
+        LENGTH_CODE[length - MIN_MATCH] as usize
+    } else {
+        // code 285: base 3 with 16 extra bits
+        28
+    }
+}
+
+#[inline(always)]
+fn distance_code(distance: usize) -> usize {
+    debug_assert!((1..=MAX_DISTANCE).contains(&distance));
+    if distance <= 4 {
+        distance - 1
+    } else {
+        let value = distance - 1;
+        let most_significant_bit = (usize::BITS - 1 - value.leading_zeros()) as usize;
+        most_significant_bit * 2 + ((value >> (most_significant_bit - 1)) & 1)
+    }
+}
+
+#[inline(always)]
+fn distance_extra_bits(distance_code: usize) -> u32 {
+    if distance_code <= 3 {
+        0
+    } else {
+        ((distance_code - 2) >> 1) as u32
+    }
+}
+
+/// Returns the count of the same bytes at `a` and `b`, up to `max_length`.
+#[inline(always)]
+fn match_length(window: &[u8], a: usize, b: usize, max_length: usize) -> usize {
+    let mut length = 0;
+    while length + 8 <= max_length {
+        let x = u64::from_le_bytes(window[a + length..][..8].try_into().unwrap());
+        let y = u64::from_le_bytes(window[b + length..][..8].try_into().unwrap());
+        let difference = x ^ y;
+        if difference != 0 {
+            return length + (difference.trailing_zeros() / 8) as usize;
+        }
+        length += 8;
+    }
+    while length < max_length && window[a + length] == window[b + length] {
+        length += 1;
+    }
+    length
+}
+
+fn static_encoders() -> (
+    HuffmanEncoder<NUM_LITERAL_CODES>,
+    HuffmanEncoder<NUM_DISTANCE_CODES>,
+) {
+    // The data is in RFC 1951.
+    let mut literal_tree_length = [0u8; NUM_LITERAL_CODES];
+    literal_tree_length[0..][..144].fill(8);
+    literal_tree_length[144..][..112].fill(9);
+    literal_tree_length[256..][..24].fill(7);
+    literal_tree_length[280..][..8].fill(8);
+
+    (
+        HuffmanEncoder::from_code_lengths(&literal_tree_length),
+        HuffmanEncoder::from_code_lengths(&[5; NUM_DISTANCE_CODES]),
+    )
+}
+
+/// The code lengths of dynamic block and its encoding.
+struct DynamicHeader {
+    literal: HuffmanEncoder<NUM_LITERAL_CODES>,
+    distance: HuffmanEncoder<NUM_DISTANCE_CODES>,
+    code_length: HuffmanEncoder<NUM_CODE_LENGTH_CODES>,
+    literal_code_count: usize,
+    distance_code_count: usize,
+    code_length_code_count: usize,
+    // run length encoded code lengths: (code length code, extra bits value)
+    encoded: Vec<(u8, u8)>,
+}
+
+impl DynamicHeader {
+    fn new() -> Self {
+        Self {
+            literal: HuffmanEncoder::new(),
+            distance: HuffmanEncoder::new(),
+            code_length: HuffmanEncoder::new(),
+            literal_code_count: 0,
+            distance_code_count: 0,
+            code_length_code_count: 0,
+            encoded: Vec::new(),
+        }
+    }
+
+    fn build_code_length_code(&mut self) {
+        let literal = self.literal.code_lengths();
+        let distance = self.distance.code_lengths();
+        self.literal_code_count = literal
+            .iter()
+            .rposition(|&x| x != 0)
+            .map_or(0, |x| x + 1)
+            .max(257);
+        self.distance_code_count = distance
+            .iter()
+            .rposition(|&x| x != 0)
+            .map_or(0, |x| x + 1)
+            .max(1);
+
+        // The code lengths for literal and distance tree form a single sequence.
+        let mut lengths = [0u8; NUM_LITERAL_CODES + NUM_DISTANCE_CODES];
+        lengths[..self.literal_code_count].copy_from_slice(&literal[..self.literal_code_count]);
+        lengths[self.literal_code_count..][..self.distance_code_count]
+            .copy_from_slice(&distance[..self.distance_code_count]);
+        let lengths = &lengths[..self.literal_code_count + self.distance_code_count];
+
+        self.encoded.clear();
+        let mut index = 0;
+        while index < lengths.len() {
+            let length = lengths[index];
+            let run = lengths[index..]
+                .iter()
+                .take_while(|&&x| x == length)
+                .count();
+            index += run;
+
+            let mut remaining = run;
+            if length == 0 {
+                while remaining >= 11 {
+                    let count = min(remaining, 138);
+                    self.encoded.push((18, (count - 11) as u8));
+                    remaining -= count;
+                }
+                if remaining >= 3 {
+                    self.encoded.push((17, (remaining - 3) as u8));
+                    remaining = 0;
+                }
+            } else {
+                self.encoded.push((length, 0));
+                remaining -= 1;
+                while remaining >= 3 {
+                    let count = min(remaining, 6);
+                    self.encoded.push((16, (count - 3) as u8));
+                    remaining -= count;
+                }
+            }
+            for _ in 0..remaining {
+                self.encoded.push((length, 0));
+            }
+        }
+
+        let mut frequencies = [0u32; NUM_CODE_LENGTH_CODES];
+        for &(code, _) in &self.encoded {
+            frequencies[code as usize] += 1;
+        }
+        self.code_length
+            .build(&frequencies, MAX_CODE_LENGTH_CODE_LENGTH);
+
+        let code_length_lengths = self.code_length.code_lengths();
+        self.code_length_code_count = CODE_ORDER
+            .iter()
+            .rposition(|&code| code_length_lengths[code as usize] != 0)
+            .map_or(0, |x| x + 1)
+            .max(4);
+    }
+
+    /// The count of bits for this header (excluding BFINAL and BTYPE).
+    fn cost(&self) -> u64 {
+        let mut bits = 5 + 5 + 4 + 3 * self.code_length_code_count as u64;
+        for &(code, _) in &self.encoded {
+            bits += self.code_length.code_lengths()[code as usize] as u64
+                + code_length_extra_bits(code) as u64;
+        }
+        bits
+    }
+
+    fn write(&self, output: &mut OutputBuffer) {
+        output.write_bits(5, (self.literal_code_count - 257) as u32);
+        output.write_bits(5, (self.distance_code_count - 1) as u32);
+        output.write_bits(4, (self.code_length_code_count - 4) as u32);
+        for &code in &CODE_ORDER[..self.code_length_code_count] {
+            output.write_bits(3, self.code_length.code_lengths()[code as usize] as u32);
+        }
+        for &(code, extra) in &self.encoded {
+            self.code_length.write_symbol(output, code as usize);
+            output.write_bits(code_length_extra_bits(code), extra as u32);
+        }
+    }
+}
+
+fn code_length_extra_bits(code: u8) -> u32 {
+    match code {
+        16 => 2,
+        17 => 3,
+        18 => 7,
+        _ => 0,
+    }
+}
@@ -0,0 +1,114 @@
+use core::fmt;
+
+/// The error in deflate64 stream
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InflateError {
+    kind: InflateErrorKind,
+    input_bit_offset: u64,
+    output_byte_offset: u64,
+}
+
+impl InflateError {
+    pub(crate) fn new(
+        kind: InflateErrorKind,
+        input_bit_offset: u64,
+        output_byte_offset: u64,
+    ) -> Self {
+        Self {
+            kind,
+            input_bit_offset,
+            output_byte_offset,
+        }
+    }
+
+    /// Returns the kind of the error
+    pub fn kind(&self) -> InflateErrorKind {
+        self.kind
+    }
+
+    /// Returns the offset in bits from the start of the input where the error is detected.
+    ///
+    /// This is the position just after the bits which made the error are read,
+    /// so the invalid data is at or before this offset.
+    pub fn input_bit_offset(&self) -> u64 {
+        self.input_bit_offset
+    }
+
+    /// Returns the count of bytes successfully decompressed before the error.
+    pub fn output_byte_offset(&self) -> u64 {
+        self.output_byte_offset
+    }
+}
+
+impl fmt::Display for InflateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid deflate64: {} (at input bit {}, output byte {})",
+            self.kind, self.input_bit_offset, self.output_byte_offset
+        )
+    }
+}
+
+impl core::error::Error for InflateError {}
+
+/// The kind of [`InflateError`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum InflateErrorKind {
+    /// The block type is 3, which is reserved.
+    UnknownBlockType,
+    /// The length of the uncompressed block doesn't match its one's complement.
+    InvalidBlockLength,
+    /// The code lengths of huffman tree is over-subscribed, or the code is not in the tree.
+    InvalidHuffmanData,
+    /// The repeat code in the code lengths has no previous code, or repeats too many.
+    InvalidCodeLengthRepeat,
+    /// The end of block code has no huffman code.
+    MissingEndOfBlockCode,
+    /// The literal/length symbol is 286 or 287, which is not used.
+    InvalidLengthSymbol,
+    /// The distance symbol is not used. Distance symbol 30 and 31 are not used for plain deflate.
+    InvalidDistanceSymbol,
+    /// The length or distance of the match is longer than allowed.
+    DistanceTooFar,
+    /// The output exceeds [`InflateLimits::max_output`](crate::InflateLimits::max_output).
+    OutputLimitExceeded,
+    /// The output exceeds [`InflateLimits::max_ratio`](crate::InflateLimits::max_ratio).
+    RatioLimitExceeded,
+    /// The count of blocks exceeds [`InflateLimits::max_blocks`](crate::InflateLimits::max_blocks).
+    BlockLimitExceeded,
+    /// The output is longer than the expected uncompressed size in strict mode.
+    OutputTooLong,
+    /// The stream ended before the expected uncompressed size in strict mode.
+    OutputTooShort,
+    /// There is data after the end of the stream in strict mode.
+    TrailingData,
+    /// The input ended before the end of the stream.
+    ///
+    /// This is reported by [`decompress_to_vec`](crate::decompress_to_vec) since the whole
+    /// stream is given at once. [`InflaterManaged`](crate::InflaterManaged) waits for more input instead.
+    UnexpectedEndOfInput,
+}
+
+impl fmt::Display for InflateErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::UnknownBlockType => "unknown block type",
+            Self::InvalidBlockLength => "block length of uncompressed block mismatch",
+            Self::InvalidHuffmanData => "invalid huffman data",
+            Self::InvalidCodeLengthRepeat => "invalid repeat in code lengths",
+            Self::MissingEndOfBlockCode => "no code for end of block",
+            Self::InvalidLengthSymbol => "invalid literal/length symbol",
+            Self::InvalidDistanceSymbol => "invalid distance symbol",
+            Self::DistanceTooFar => "too long distance or length",
+            Self::OutputLimitExceeded => "output size limit exceeded",
+            Self::RatioLimitExceeded => "expansion ratio limit exceeded",
+            Self::BlockLimitExceeded => "block count limit exceeded",
+            Self::OutputTooLong => "output is longer than the expected uncompressed size",
+            Self::OutputTooShort => "output is shorter than the expected uncompressed size",
+            Self::TrailingData => "trailing data after the end of the stream",
+            Self::UnexpectedEndOfInput => "unexpected end of input",
+        })
+    }
+}
@@ -0,0 +1,371 @@
+use crate::checksum::Crc32;
+use crate::InflaterManaged;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const GZIP_METHOD_DEFLATE: u8 = 8;
+
+const FLAG_TEXT: u8 = 0x01;
+const FLAG_HEADER_CRC: u8 = 0x02;
+const FLAG_EXTRA: u8 = 0x04;
+const FLAG_NAME: u8 = 0x08;
+const FLAG_COMMENT: u8 = 0x10;
+const FLAG_RESERVED: u8 = 0xE0;
+
+// The maximum length of FNAME and FCOMMENT fields without zero terminator
+const MAX_HEADER_FIELD_LEN: usize = 64 * 1024;
+
+/// The error in gzip header or trailer
+///
+/// This is returned as the inner error of [`io::Error`] with [`io::ErrorKind::InvalidData`].
+/// Errors in the compressed data are returned as [`InflateError`](crate::InflateError).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum GzError {
+    /// The member doesn't start with gzip magic number.
+    InvalidMagic,
+    /// The compression method in the header is not 8.
+    UnknownCompressionMethod(u8),
+    /// Reserved flags are set.
+    ReservedFlags(u8),
+    /// The CRC-16 of the header doesn't match.
+    HeaderChecksumMismatch {
+        /// The CRC-16 in the header.
+        expected: u16,
+        /// The CRC-16 of the header.
+        actual: u16,
+    },
+    /// FNAME or FCOMMENT field is longer than 64 KiB.
+    HeaderFieldTooLong,
+    /// The CRC-32 of the decompressed data doesn't match the trailer.
+    ChecksumMismatch {
+        /// The CRC-32 in the trailer.
+        expected: u32,
+        /// The CRC-32 of the decompressed data.
+        actual: u32,
+    },
+    /// The size of the decompressed data modulo 2^32 doesn't match the trailer.
+    SizeMismatch {
+        /// The size in the trailer.
+        expected: u32,
+        /// The size of the decompressed data modulo 2^32.
+        actual: u32,
+    },
+}
+
+impl fmt::Display for GzError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMagic => f.write_str("invalid gzip magic number"),
+            Self::UnknownCompressionMethod(method) => {
+                write!(f, "unknown gzip compression method: {method}")
+            }
+            Self::ReservedFlags(flags) => write!(f, "reserved gzip flags are set: {flags:02x}"),
+            Self::HeaderChecksumMismatch { expected, actual } => write!(
+                f,
+                "gzip header checksum mismatch: expected {expected:04x}, actual {actual:04x}"
+            ),
+            Self::HeaderFieldTooLong => f.write_str("gzip header field is too long"),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "gzip checksum mismatch: expected {expected:08x}, actual {actual:08x}"
+            ),
+            Self::SizeMismatch { expected, actual } => write!(
+                f,
+                "gzip size mismatch: expected {expected}, actual {actual}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GzError {}
+
+impl From<GzError> for io::Error {
+    fn from(value: GzError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, value)
+    }
+}
+
+/// The header of gzip member
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GzHeader {
+    text: bool,
+    mtime: u32,
+    extra_flags: u8,
+    os: u8,
+    extra: Option<Vec<u8>>,
+    filename: Option<Vec<u8>>,
+    comment: Option<Vec<u8>>,
+}
+
+impl GzHeader {
+    /// Returns true if FTEXT flag is set
+    pub fn is_text(&self) -> bool {
+        self.text
+    }
+
+    /// Returns modification time in unix time. 0 means no time stamp is available
+    pub fn mtime(&self) -> u32 {
+        self.mtime
+    }
+
+    /// Returns XFL (extra flags) field
+    pub fn extra_flags(&self) -> u8 {
+        self.extra_flags
+    }
+
+    /// Returns OS field
+    pub fn os(&self) -> u8 {
+        self.os
+    }
+
+    /// Returns FEXTRA field if present
+    pub fn extra(&self) -> Option<&[u8]> {
+        self.extra.as_deref()
+    }
+
+    /// Returns FNAME field without zero terminator if present
+    pub fn filename(&self) -> Option<&[u8]> {
+        self.filename.as_deref()
+    }
+
+    /// Returns FCOMMENT field without zero terminator if present
+    pub fn comment(&self) -> Option<&[u8]> {
+        self.comment.as_deref()
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum GzState {
+    Header,
+    Body,
+    Trailer,
+    Done,
+}
+
+/// The reader the decompresses gzip (RFC 1952) framed deflate64 from another BufRead.
+///
+/// Concatenated members are decompressed as one stream and
+/// CRC-32 and size of each member are verified.
+pub struct GzDeflate64Decoder<R> {
+    inner: R,
+    inflater: Box<InflaterManaged>,
+    header: Option<GzHeader>,
+    crc32: Crc32,
+    size: u32,
+    state: GzState,
+}
+
+impl<R: Read> GzDeflate64Decoder<BufReader<R>> {
+    /// Creates GzDeflate64Decoder with Read
+    pub fn new(inner: R) -> Self {
+        Self::with_buffer(BufReader::new(inner))
+    }
+}
+
+impl<R: BufRead> GzDeflate64Decoder<R> {
+    /// Creates GzDeflate64Decoder with BufRead
+    pub fn with_buffer(inner: R) -> Self {
+        Self {
+            inner,
+            inflater: Box::new(InflaterManaged::new()),
+            header: None,
+            crc32: Crc32::new(),
+            size: 0,
+            state: GzState::Header,
+        }
+    }
+
+    fn read_header(&mut self) -> io::Result<()> {
+        let mut reader = CrcReader {
+            inner: &mut self.inner,
+            crc32: Crc32::new(),
+        };
+
+        let mut fixed = [0u8; 10];
+        reader.read_exact(&mut fixed)?;
+        if fixed[0..2] != GZIP_MAGIC {
+            return Err(GzError::InvalidMagic.into());
+        }
+        if fixed[2] != GZIP_METHOD_DEFLATE {
+            return Err(GzError::UnknownCompressionMethod(fixed[2]).into());
+        }
+        let flags = fixed[3];
+        if flags & FLAG_RESERVED != 0 {
+            return Err(GzError::ReservedFlags(flags & FLAG_RESERVED).into());
+        }
+
+        let extra = if flags & FLAG_EXTRA != 0 {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len)?;
+            let mut extra = vec![0u8; u16::from_le_bytes(len) as usize];
+            reader.read_exact(&mut extra)?;
+            Some(extra)
+        } else {
+            None
+        };
+        let filename = if flags & FLAG_NAME != 0 {
+            Some(reader.read_zero_terminated()?)
+        } else {
+            None
+        };
+        let comment = if flags & FLAG_COMMENT != 0 {
+            Some(reader.read_zero_terminated()?)
+        } else {
+            None
+        };
+        if flags & FLAG_HEADER_CRC != 0 {
+            let actual = reader.crc32.value() as u16;
+            let mut crc16 = [0u8; 2];
+            self.inner.read_exact(&mut crc16)?;
+            let expected = u16::from_le_bytes(crc16);
+            if expected != actual {
+                return Err(GzError::HeaderChecksumMismatch { expected, actual }.into());
+            }
+        }
+
+        self.header = Some(GzHeader {
+            text: flags & FLAG_TEXT != 0,
+            mtime: u32::from_le_bytes(fixed[4..8].try_into().unwrap()),
+            extra_flags: fixed[8],
+            os: fixed[9],
+            extra,
+            filename,
+            comment,
+        });
+        Ok(())
+    }
+
+    fn read_trailer(&mut self) -> io::Result<()> {
+        let mut trailer = [0u8; 8];
+        self.inner.read_exact(&mut trailer)?;
+        let expected = u32::from_le_bytes(trailer[0..4].try_into().unwrap());
+        let actual = self.crc32.value();
+        if expected != actual {
+            return Err(GzError::ChecksumMismatch { expected, actual }.into());
+        }
+        let expected = u32::from_le_bytes(trailer[4..8].try_into().unwrap());
+        let actual = self.size;
+        if expected != actual {
+            return Err(GzError::SizeMismatch { expected, actual }.into());
+        }
+        Ok(())
+    }
+}
+
+impl<R> GzDeflate64Decoder<R> {
+    /// Returns the header of the current member
+    ///
+    /// This returns `None` before the first header is read.
+    pub fn header(&self) -> Option<&GzHeader> {
+        self.header.as_ref()
+    }
+
+    /// Returns inner BufRead instance
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns reference to innner BufRead instance
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns mutable reference to innner BufRead instance
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+
+impl<R: BufRead> Read for GzDeflate64Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            // we received empty buffer, so it won't be possible to write anything
+            return Ok(0);
+        }
+
+        loop {
+            match self.state {
+                GzState::Header => {
+                    self.read_header()?;
+                    self.state = GzState::Body;
+                }
+                GzState::Body => {
+                    let input = self.inner.fill_buf()?;
+                    let eof = input.is_empty();
+
+                    let result = self.inflater.inflate(input, buf);
+
+                    self.inner.consume(result.bytes_consumed);
+
+                    if let Some(error) = result.error {
+                        // the output before the error is returned first,
+                        // and the error is returned again
+                        if result.bytes_written == 0 {
+                            return Err(io::Error::new(io::ErrorKind::InvalidInput, error));
+                        }
+                    }
+
+                    self.crc32.update(&buf[..result.bytes_written]);
+                    self.size = self.size.wrapping_add(result.bytes_written as u32);
+
+                    if self.inflater.finished() && !self.inflater.errored() {
+                        self.state = GzState::Trailer;
+                    } else if result.bytes_written == 0 && eof {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "unexpected end of gzip stream",
+                        ));
+                    }
+
+                    if result.bytes_written != 0 {
+                        return Ok(result.bytes_written);
+                    }
+                }
+                GzState::Trailer => {
+                    self.read_trailer()?;
+                    if self.inner.fill_buf()?.is_empty() {
+                        self.state = GzState::Done;
+                    } else {
+                        // next member follows
+                        self.inflater.reset();
+                        self.crc32 = Crc32::new();
+                        self.size = 0;
+                        self.state = GzState::Header;
+                    }
+                }
+                GzState::Done => return Ok(0),
+            }
+        }
+    }
+}
+
+/// Reader which calculates CRC-32 of the header for FHCRC
+struct CrcReader<'a, R> {
+    inner: &'a mut R,
+    crc32: Crc32,
+}
+
+impl<R: BufRead> CrcReader<'_, R> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.read_exact(buf)?;
+        self.crc32.update(buf);
+        Ok(())
+    }
+
+    fn read_zero_terminated(&mut self) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        let limit = MAX_HEADER_FIELD_LEN as u64 + 1;
+        (&mut *self.inner).take(limit).read_until(0, &mut buf)?;
+        self.crc32.update(&buf);
+        if buf.pop() != Some(0) {
+            return Err(if buf.len() == MAX_HEADER_FIELD_LEN {
+                GzError::HeaderFieldTooLong.into()
+            } else {
+                io::ErrorKind::UnexpectedEof.into()
+            });
+        }
+        Ok(buf)
+    }
+}
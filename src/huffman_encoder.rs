@@ -0,0 +1,181 @@
+use crate::output_buffer::OutputBuffer;
+
+/// Huffman code for the compressor side.
+///
+/// The code lengths are limited to `max_code_length` bits and codes are stored bit reversed
+/// so that they can be written to [`OutputBuffer`] directly.
+#[derive(Debug)]
+pub(crate) struct HuffmanEncoder<const N: usize> {
+    code_lengths: [u8; N],
+    codes: [u16; N],
+}
+
+impl<const N: usize> HuffmanEncoder<N> {
+    pub fn new() -> Self {
+        Self {
+            code_lengths: [0; N],
+            codes: [0; N],
+        }
+    }
+
+    /// Creates code from the code lengths. Used for static huffman trees.
+    pub fn from_code_lengths(code_lengths: &[u8; N]) -> Self {
+        let mut instance = Self {
+            code_lengths: *code_lengths,
+            codes: [0; N],
+        };
+        instance.calculate_codes();
+        instance
+    }
+
+    pub fn code_lengths(&self) -> &[u8; N] {
+        &self.code_lengths
+    }
+
+    /// Builds length-limited code optimal (or near optimal) for the frequencies.
+    pub fn build(&mut self, frequencies: &[u32; N], max_code_length: u8) {
+        build_code_lengths(frequencies, max_code_length, &mut self.code_lengths);
+        self.calculate_codes();
+    }
+
+    /// The count of bits needed to encode all symbols with the frequencies.
+    pub fn cost(&self, frequencies: &[u32; N]) -> u64 {
+        frequencies
+            .iter()
+            .zip(&self.code_lengths)
+            .map(|(&freq, &len)| freq as u64 * len as u64)
+            .sum()
+    }
+
+    #[inline(always)]
+    pub fn write_symbol(&self, output: &mut OutputBuffer, symbol: usize) {
+        debug_assert_ne!(self.code_lengths[symbol], 0, "symbol without code");
+        output.write_bits(self.code_lengths[symbol] as u32, self.codes[symbol] as u32);
+    }
+
+    // Same algorithm as HuffmanTree::calculate_huffman_code in decoder side.
+    fn calculate_codes(&mut self) {
+        let mut bit_length_count = [0u32; 17];
+        for &code_length in self.code_lengths.iter() {
+            bit_length_count[code_length as usize] += 1;
+        }
+        bit_length_count[0] = 0;
+
+        let mut next_code = [0u32; 17];
+        let mut temp_code = 0u32;
+        for bits in 1..=16 {
+            temp_code = (temp_code + bit_length_count[bits - 1]) << 1;
+            next_code[bits] = temp_code;
+        }
+
+        for (code, &len) in self.codes.iter_mut().zip(&self.code_lengths) {
+            if len > 0 {
+                *code = (next_code[len as usize].reverse_bits() >> (32 - len)) as u16;
+                next_code[len as usize] += 1;
+            } else {
+                *code = 0;
+            }
+        }
+    }
+}
+
+/// Calculates huffman code lengths limited to `max_code_length` bits.
+///
+/// If only one symbol is used, another symbol is also given a code
+/// so that the resulting code is complete.
+fn build_code_lengths(frequencies: &[u32], max_code_length: u8, code_lengths: &mut [u8]) {
+    debug_assert!(frequencies.len() >= 2 && frequencies.len() <= 288);
+    debug_assert_eq!(frequencies.len(), code_lengths.len());
+    code_lengths.fill(0);
+
+    // symbols sorted by frequency, ascending.
+    let mut symbols = [(0u32, 0u16); 288];
+    let mut count = 0;
+    for (symbol, &freq) in frequencies.iter().enumerate() {
+        if freq != 0 {
+            symbols[count] = (freq, symbol as u16);
+            count += 1;
+        }
+    }
+    let symbols = &mut symbols[..count];
+
+    match count {
+        0 => return,
+        1 => {
+            let symbol = symbols[0].1 as usize;
+            code_lengths[symbol] = 1;
+            code_lengths[if symbol == 0 { 1 } else { 0 }] = 1;
+            return;
+        }
+        _ => {}
+    }
+
+    symbols.sort_unstable();
+
+    // Build the huffman tree with two queues: one for leaves (already sorted) and
+    // one for internal nodes (created in ascending weight order).
+    // Node 0..count are leaves and count.. are internal nodes.
+    let mut weight = [0u64; 288 * 2];
+    let mut parent = [0u16; 288 * 2];
+    for (node, &(freq, _)) in symbols.iter().enumerate() {
+        weight[node] = freq as u64;
+    }
+
+    let mut next_leaf = 0;
+    let mut next_internal = count;
+    for node in count..(count * 2 - 1) {
+        let mut pick = || {
+            let use_leaf = next_leaf < count
+                && (next_internal >= node || weight[next_leaf] <= weight[next_internal]);
+            if use_leaf {
+                next_leaf += 1;
+                next_leaf - 1
+            } else {
+                next_internal += 1;
+                next_internal - 1
+            }
+        };
+        let left = pick();
+        let right = pick();
+        weight[node] = weight[left] + weight[right];
+        parent[left] = node as u16;
+        parent[right] = node as u16;
+    }
+
+    // Parents always have larger index than children so we can calculate depth from the root.
+    let root = count * 2 - 2;
+    let mut depth = [0u16; 288 * 2];
+    let mut bit_length_count = [0u32; 17];
+    for node in (0..root).rev() {
+        depth[node] = depth[parent[node] as usize] + 1;
+        if node < count {
+            bit_length_count[(depth[node] as usize).min(max_code_length as usize)] += 1;
+        }
+    }
+
+    // Limit code lengths. Same method as miniz.
+    let max = max_code_length as usize;
+    let mut total: u32 = (1..=max)
+        .map(|len| bit_length_count[len] << (max - len))
+        .sum();
+    while total != 1 << max {
+        bit_length_count[max] -= 1;
+        for len in (1..max).rev() {
+            if bit_length_count[len] != 0 {
+                bit_length_count[len] -= 1;
+                bit_length_count[len + 1] += 2;
+                break;
+            }
+        }
+        total -= 1;
+    }
+
+    // Assign the longest code to the least frequent symbol.
+    let mut symbols = symbols.iter();
+    for len in (1..=max).rev() {
+        for _ in 0..bit_length_count[len] {
+            let &(_, symbol) = symbols.next().unwrap();
+            code_lengths[symbol as usize] = len as u8;
+        }
+    }
+}
@@ -1,49 +1,111 @@
 use crate::input_buffer::InputBuffer;
-use crate::InternalErr;
+use crate::{InflateErrorKind, InternalErr};
+
+pub(crate) const MAX_LITERAL_TREE_ELEMENTS: usize = 288;
+pub(crate) const MAX_DIST_TREE_ELEMENTS: usize = 32;
+pub(crate) const END_OF_BLOCK_CODE: usize = 256;
+pub(crate) const NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS: usize = 19;
+
+const MAX_CODE_LENGTH: usize = 15;
+
+// The table sizes are from libdeflate, which are enough for any complete code.
+// Incomplete codes which need more space are rejected as invalid.
+pub(crate) type LiteralLengthTree = HuffmanTree<11, 2342>;
+pub(crate) type DistanceTree = HuffmanTree<8, 402>;
+pub(crate) type CodeLengthTree = HuffmanTree<7, 128>;
+
+/// An entry of the decoding table.
+///
+/// Layout:
+/// - bits 0-3: the code length, or the total length of the two codes for a literal pair
+/// - bits 4-6: the index bits of the subtable for a subtable pointer, otherwise 0
+/// - bit 7: set for a literal pair
+/// - bits 8-11: the code length, or the length of the first code for a literal pair
+/// - bits 12-19: the second literal of a literal pair
+/// - bits 20-31: the symbol, or the start of the subtable for a subtable pointer
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct HuffmanEntry(u32);
+
+impl HuffmanEntry {
+    const INVALID_SYMBOL: u16 = 0xFFF;
+    // Codes not assigned in incomplete trees
+    const INVALID: Self = Self((Self::INVALID_SYMBOL as u32) << 20);
+    const LITERAL_PAIR: u32 = 1 << 7;
+
+    fn symbol_entry(symbol: u16, code_length: u32) -> Self {
+        Self(((symbol as u32) << 20) | (code_length << 8) | code_length)
+    }
 
-// Packing: bits 0-8 = symbol (0-288), bits 9-13 = code length (1-16), bits 14+ = zero
-const SYMBOL_BITS: u8 = 9;
-const SYMBOL_MASK: i16 = (1 << SYMBOL_BITS) - 1; // 0x1FF
+    fn subtable_entry(start: usize, index_bits: u32) -> Self {
+        Self(((start as u32) << 20) | (index_bits << 4))
+    }
 
-fn pack(symbol: i16, code_len: u8) -> i16 {
-    symbol | ((code_len as i16) << SYMBOL_BITS)
-}
+    fn with_second_literal(self, literal: u8, pair_length: u32) -> Self {
+        Self((self.0 & !0xF) | pair_length | Self::LITERAL_PAIR | ((literal as u32) << 12))
+    }
 
-pub(crate) fn unpack(entry: i16) -> (u16, i32) {
-    ((entry & SYMBOL_MASK) as u16, (entry >> SYMBOL_BITS) as i32)
+    #[inline(always)]
+    pub fn symbol(self) -> u16 {
+        (self.0 >> 20) as u16
+    }
+
+    /// Returns the length of the code, which is 0 for invalid codes
+    #[inline(always)]
+    pub fn code_length(self) -> i32 {
+        ((self.0 >> 8) & 0xF) as i32
+    }
+
+    /// Returns the bits to skip for the literals in this entry, which has the length of the
+    /// codes at the low 4 bits and 0 at the next 2 bits so that it can be the shift amount as is
+    #[inline(always)]
+    pub fn literals_length_bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns the count of the literals in this entry, 2 for a literal pair and otherwise 1
+    #[inline(always)]
+    pub fn literal_count(self) -> usize {
+        1 + ((self.0 & Self::LITERAL_PAIR) >> 7) as usize
+    }
+
+    /// Returns the literal at the low byte and the second literal of a literal pair at the high byte
+    #[inline(always)]
+    pub fn literals(self) -> u16 {
+        (((self.0 >> 20) & 0xFF) | ((self.0 >> 4) & 0xFF00)) as u16
+    }
+
+    #[inline(always)]
+    fn subtable_bits(self) -> u32 {
+        (self.0 >> 4) & 0x7
+    }
+
+    #[inline(always)]
+    pub fn is_invalid(self) -> bool {
+        self.symbol() == Self::INVALID_SYMBOL
+    }
 }
 
-#[derive(Debug)]
-pub(crate) struct HuffmanTree {
+/// <summary>
+/// Canonical Huffman code decoder with a primary table indexed by the next `TABLE_BITS` bits.
+/// Codes longer than `TABLE_BITS` are decoded with subtables stored after the primary table.
+/// For literal/length codes, a primary entry also holds the next literal if both codes fit
+/// in the table bits so that two literals can be decoded with one lookup.
+/// </summary>
+#[derive(Debug, Clone)]
+pub(crate) struct HuffmanTree<const TABLE_BITS: u32, const TABLE_SIZE: usize> {
     code_lengths_length: u16,
-    table: [i16; 1 << Self::TABLE_BITS],
-    // Table stores positive or negative numbers. Positive numbers are packed symbols
-    // and code lengths (see pack/unpack above). Negative values are indexes into a
-    // binary tree of array nodes; consume additional bits for left/right navagation
-    // until a positive packed value is reached. Note, the original implementation had
-    // separate "left" and "right" tables, we have interleaved these tables to enable
-    // branchless left/right navigation with simple math. Left and right nodes come in
-    // pairs, where N*2 is a left node and N*2+1 is a right node.
-    nodes: [i16; Self::MAX_CODE_LENGTHS * 4],
-    code_length_array: [u8; Self::MAX_CODE_LENGTHS],
+    table: [HuffmanEntry; TABLE_SIZE],
+    code_length_array: [u8; MAX_LITERAL_TREE_ELEMENTS],
 }
 
-impl HuffmanTree {
-    pub(crate) const MAX_CODE_LENGTHS: usize = 288;
-    pub(crate) const TABLE_BITS: u8 = 9;
-    pub(crate) const TABLE_BITS_MASK: usize = (1 << Self::TABLE_BITS) - 1;
-
-    pub(crate) const MAX_LITERAL_TREE_ELEMENTS: usize = 288;
-    pub(crate) const MAX_DIST_TREE_ELEMENTS: usize = 32;
-    pub(crate) const END_OF_BLOCK_CODE: usize = 256;
-    pub(crate) const NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS: usize = 19;
+impl<const TABLE_BITS: u32, const TABLE_SIZE: usize> HuffmanTree<TABLE_BITS, TABLE_SIZE> {
+    const TABLE_BITS_MASK: usize = (1 << TABLE_BITS) - 1;
 
     pub fn invalid() -> Self {
         HuffmanTree {
             code_lengths_length: Default::default(),
-            table: [0i16; 1 << Self::TABLE_BITS],
-            nodes: [0i16; Self::MAX_CODE_LENGTHS * 4],
-            code_length_array: [0u8; Self::MAX_CODE_LENGTHS],
+            table: [HuffmanEntry::INVALID; TABLE_SIZE],
+            code_length_array: [0u8; MAX_LITERAL_TREE_ELEMENTS],
         }
     }
 
@@ -57,51 +119,34 @@ impl HuffmanTree {
 
     fn assert_code_lengths_len(len: usize) {
         debug_assert!(
-            len == Self::MAX_LITERAL_TREE_ELEMENTS
-                || len == Self::MAX_DIST_TREE_ELEMENTS
-                || len == Self::NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS,
+            len == MAX_LITERAL_TREE_ELEMENTS
+                || len == MAX_DIST_TREE_ELEMENTS
+                || len == NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS,
             "we only expect three kinds of Length here"
         );
     }
 
-    pub fn new(code_lengths: &[u8]) -> Result<HuffmanTree, InternalErr> {
-        Self::assert_code_lengths_len(code_lengths.len());
-        let code_lengths_length = code_lengths.len();
-
-        // I need to find proof that left and right array will always be
-        // enough. I think they are.
-
-        let mut instance = Self {
-            table: [0; 1 << Self::TABLE_BITS],
-            nodes: [0; Self::MAX_CODE_LENGTHS * 4],
-            code_lengths_length: code_lengths_length as u16,
-            code_length_array: {
-                let mut buffer = [0u8; Self::MAX_CODE_LENGTHS];
-                buffer[..code_lengths.len()].copy_from_slice(code_lengths);
-                buffer
-            },
-        };
-
-        instance.create_table()?;
-
+    pub fn new(code_lengths: &[u8]) -> Result<Self, InternalErr> {
+        let mut instance = Self::invalid();
+        instance.new_in_place(code_lengths)?;
         Ok(instance)
     }
 
     pub fn new_in_place(&mut self, code_lengths: &[u8]) -> Result<(), InternalErr> {
         Self::assert_code_lengths_len(code_lengths.len());
-        self.table.fill(0);
-        self.nodes.fill(0);
+        debug_assert!(1 << TABLE_BITS <= TABLE_SIZE);
         self.code_lengths_length = code_lengths.len() as u16;
         self.code_length_array[..code_lengths.len()].copy_from_slice(code_lengths);
         self.code_length_array[code_lengths.len()..].fill(0);
 
-        self.create_table()
+        self.create_table(code_lengths.len() == MAX_LITERAL_TREE_ELEMENTS)?;
+        Ok(())
     }
 
     // Generate the array contains huffman codes lengths for static huffman tree.
     // The data is in RFC 1951.
-    fn get_static_literal_tree_length() -> [u8; Self::MAX_LITERAL_TREE_ELEMENTS] {
-        let mut literal_tree_length = [0u8; Self::MAX_LITERAL_TREE_ELEMENTS];
+    fn get_static_literal_tree_length() -> [u8; MAX_LITERAL_TREE_ELEMENTS] {
+        let mut literal_tree_length = [0u8; MAX_LITERAL_TREE_ELEMENTS];
 
         literal_tree_length[0..][..144].fill(8);
         literal_tree_length[144..][..112].fill(9);
@@ -110,8 +155,8 @@ impl HuffmanTree {
         literal_tree_length
     }
 
-    const fn get_static_distance_tree_length() -> [u8; Self::MAX_DIST_TREE_ELEMENTS] {
-        [5u8; Self::MAX_DIST_TREE_ELEMENTS]
+    const fn get_static_distance_tree_length() -> [u8; MAX_DIST_TREE_ELEMENTS] {
+        [5u8; MAX_DIST_TREE_ELEMENTS]
     }
 
     fn bit_reverse(code: u32, length: usize) -> u32 {
@@ -119,123 +164,125 @@ impl HuffmanTree {
         code.reverse_bits() >> (32 - length)
     }
 
-    fn calculate_huffman_code(&self) -> [u32; Self::MAX_LITERAL_TREE_ELEMENTS] {
-        let code_lengths = &self.code_length_array[..self.code_lengths_length as usize];
-        let mut bit_length_count = [0u32; 17];
+    /// Fills the table. With `literal_pairs`, primary entries for literals also have the next
+    /// literal if both codes fit in the table bits.
+    fn create_table(&mut self, literal_pairs: bool) -> Result<(), InternalErr> {
+        let code_length_array = self.code_length_array;
+        let code_lengths = &code_length_array[..self.code_lengths_length as usize];
+
+        let mut bit_length_count = [0u16; MAX_CODE_LENGTH + 1];
         for &code_length in code_lengths.iter() {
+            if code_length as usize > MAX_CODE_LENGTH {
+                return Err(InternalErr::DataError(InflateErrorKind::InvalidHuffmanData));
+            }
             bit_length_count[code_length as usize] += 1;
         }
         bit_length_count[0] = 0; // clear count for length 0
 
-        let mut next_code = [0u32; 17];
-        let mut temp_code = 0u32;
-
-        for bits in 1..=16 {
-            temp_code = (temp_code + bit_length_count[bits - 1]) << 1;
-            next_code[bits] = temp_code;
+        // The first code of each length, and the position of the first symbol of each length
+        // in the symbols sorted by the code. Codes must not be oversubscribed.
+        let mut next_code = [0u32; MAX_CODE_LENGTH + 1];
+        let mut offsets = [0u16; MAX_CODE_LENGTH + 2];
+        let mut code = 0u32;
+        for len in 1..=MAX_CODE_LENGTH {
+            code = (code + bit_length_count[len - 1] as u32) << 1;
+            next_code[len] = code;
+            if code + bit_length_count[len] as u32 > 1 << len {
+                return Err(InternalErr::DataError(InflateErrorKind::InvalidHuffmanData));
+            }
+            offsets[len + 1] = offsets[len] + bit_length_count[len];
         }
+        if code + (bit_length_count[MAX_CODE_LENGTH] as u32) < 1 << MAX_CODE_LENGTH {
+            // Some entries are left unassigned with incomplete codes. With complete codes,
+            // all entries are overwritten.
+            self.table.fill(HuffmanEntry::INVALID);
+        }
+        let shortest = (1..=MAX_CODE_LENGTH)
+            .find(|&len| bit_length_count[len] != 0)
+            .unwrap_or(0) as u32;
 
-        let mut code = [0u32; Self::MAX_LITERAL_TREE_ELEMENTS];
-        for (i, &len) in code_lengths.iter().enumerate() {
+        let mut sorted = [0u16; MAX_LITERAL_TREE_ELEMENTS];
+        for (symbol, &len) in code_lengths.iter().enumerate() {
             if len > 0 {
-                code[i] = Self::bit_reverse(next_code[len as usize], len as usize);
-                next_code[len as usize] += 1;
+                sorted[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
             }
         }
+        let sorted = &sorted[..offsets[MAX_CODE_LENGTH + 1] as usize];
+
+        // Literals which can be the first of a literal pair
+        let mut pair_literals = [(HuffmanEntry::INVALID, 0usize); 256];
+        let mut pair_literal_count = 0;
+
+        // The primary table is filled for each length, and the filled part is doubled for the
+        // next length so that the entries of shorter codes are repeated for the following bits.
+        let mut sorted_index = 0;
+        for len in 1..=TABLE_BITS {
+            if len > 1 {
+                self.table.copy_within(..1 << (len - 1), 1 << (len - 1));
+            }
+            while let Some(&symbol) = sorted.get(sorted_index) {
+                if code_lengths[symbol as usize] as u32 != len {
+                    break;
+                }
+                sorted_index += 1;
+                // the bits in the table are reversed since the bits are read from LSB
+                let code = Self::bit_reverse(next_code[len as usize], len as usize) as usize;
+                next_code[len as usize] += 1;
+                let entry = HuffmanEntry::symbol_entry(symbol, len);
+                self.table[code] = entry;
 
-        code
-    }
+                if literal_pairs && symbol < 256 && len + shortest <= TABLE_BITS {
+                    pair_literals[pair_literal_count] = (entry, code);
+                    pair_literal_count += 1;
+                }
+            }
+        }
 
-    fn create_table(&mut self) -> Result<(), InternalErr> {
-        let code_array = self.calculate_huffman_code();
-        let code_lengths_len = self.code_lengths_length as usize;
+        // For any code which has length longer than the table bits, use subtables.
+        let mut subtable_prefix = usize::MAX;
+        let mut subtable_start = 0;
+        let mut subtable_end = 1 << TABLE_BITS;
+
+        for (i, &symbol) in sorted.iter().enumerate().skip(sorted_index) {
+            let len = code_lengths[symbol as usize] as u32;
+            let code = Self::bit_reverse(next_code[len as usize], len as usize) as usize;
+            next_code[len as usize] += 1;
+            let entry = HuffmanEntry::symbol_entry(symbol, len);
+
+            let prefix = code & Self::TABLE_BITS_MASK;
+            if prefix != subtable_prefix {
+                // Codes with the same prefix are next to each other in the sorted symbols,
+                // and the last one is the longest.
+                let max_len =
+                    Self::longest_code_with_prefix(code_lengths, &sorted[i..], next_code, len);
+                let index_bits = max_len - TABLE_BITS;
+                subtable_prefix = prefix;
+                subtable_start = subtable_end;
+                subtable_end += 1 << index_bits;
+                if subtable_end > TABLE_SIZE {
+                    return Err(InternalErr::DataError(InflateErrorKind::InvalidHuffmanData));
+                }
+                self.table[prefix] = HuffmanEntry::subtable_entry(subtable_start, index_bits);
+            }
 
-        let mut avail = 1; // skip 0 because -0 is still 0, can't distinguish by sign
+            for index in (subtable_start + (code >> TABLE_BITS)..subtable_end)
+                .step_by(1 << (len - TABLE_BITS))
+            {
+                self.table[index] = entry;
+            }
+        }
 
-        for (ch, &len) in self.code_length_array[..code_lengths_len]
-            .iter()
-            .enumerate()
-        {
-            if len > 0 {
-                // start value (bit reversed)
-                let mut start = code_array[ch] as usize;
-
-                if len <= Self::TABLE_BITS {
-                    // If a particular symbol is shorter than nine bits,
-                    // then that symbol's translation is duplicated
-                    // in all those entries that start with that symbol's bits.
-                    // For example, if the symbol is four bits, then it's duplicated
-                    // 32 times in a nine-bit table. If a symbol is nine bits long,
-                    // it appears in the table once.
-                    //
-                    // Make sure that in the loop below, code is always
-                    // less than table_size.
-                    //
-                    // On last iteration we store at array index:
-                    //    initial_start_at + (locs-1)*increment
-                    //  = initial_start_at + locs*increment - increment
-                    //  = initial_start_at + (1 << tableBits) - increment
-                    //  = initial_start_at + table_size - increment
-                    //
-                    // Therefore we must ensure:
-                    //     initial_start_at + table_size - increment < table_size
-                    // or: initial_start_at < increment
-                    //
-                    let increment = 1 << len;
-                    if start >= increment {
-                        return Err(InternalErr::DataError); // InvalidHuffmanData
-                    }
-
-                    // Note the bits in the table are reverted.
-                    let locs = 1 << (Self::TABLE_BITS - len);
-                    for _ in 0..locs {
-                        self.table[start] = pack(ch as i16, len);
-                        start += increment;
-                    }
-                } else {
-                    // For any code which has length longer than num_elements,
-                    // build a binary tree.
-
-                    let mut overflow_bits = len - Self::TABLE_BITS; // the nodes we need to represent the data.
-                    let mut code_bit_mask = 1 << Self::TABLE_BITS; // mask to get current bit (the bits can't fit in the table)
-
-                    // the left, right table is used to represent the
-                    // the rest bits. When we got the first part (number bits.) and look at
-                    // tbe table, we will need to follow the tree to find the real character.
-                    // This is in place to avoid bloating the table if there are
-                    // a few ones with long code.
-                    // As an optimization, we now store left/right together at N*2 and N*2+1.
-                    // We store (-left_index) as a pointer to newly allocated node pairs; the
-                    // get_symbol logic increments the negated left_index to get right_index.
-                    let mut index = start & Self::TABLE_BITS_MASK;
-                    let mut value: &mut i16 = &mut self.table[index];
-
-                    while {
-                        if *value == 0 {
-                            // set up next pointer if this node is not used before.
-                            // store -left_index directly (avail * 2)
-                            *value = -(avail * 2);
-                            avail += 1;
-                        }
-
-                        if *value > 0 {
-                            // prevent an IndexOutOfRangeException from array[index]
-                            return Err(InternalErr::DataError); // InvalidHuffmanData
-                        }
-
-                        // left child at -value, right child at -value+1
-                        let left_index = (-*value) as usize;
-                        index = left_index + ((start & code_bit_mask) != 0) as usize;
-
-                        value = self.nodes.get_mut(index).ok_or(InternalErr::DataError)?; // InvalidHuffmanData
-
-                        code_bit_mask <<= 1;
-                        overflow_bits -= 1;
-
-                        overflow_bits != 0
-                    } {}
-
-                    *value = pack(ch as i16, len);
+        // The entry at `index >> first_len` is for the code following the first literal.
+        // Entries updated here keep the symbol and the length of the first code.
+        for &(first, code) in &pair_literals[..pair_literal_count] {
+            let first_len = first.code_length() as u32;
+            for next_bits in 0..1 << (TABLE_BITS - first_len) {
+                let second = self.table[next_bits];
+                let pair_length = first_len + second.code_length() as u32;
+                if second.symbol() < 256 && second.code_length() != 0 && pair_length <= TABLE_BITS {
+                    self.table[code | (next_bits << first_len)] =
+                        first.with_second_literal(second.symbol() as u8, pair_length);
                 }
             }
         }
@@ -243,6 +290,40 @@ impl HuffmanTree {
         Ok(())
     }
 
+    /// Returns the length of the longest code sharing the first `TABLE_BITS` bits with the
+    /// code of `sorted[0]`, where `next_code` is the code next to `sorted[0]` for each length.
+    fn longest_code_with_prefix(
+        code_lengths: &[u8],
+        sorted: &[u16],
+        mut next_code: [u32; MAX_CODE_LENGTH + 1],
+        len: u32,
+    ) -> u32 {
+        let prefix_of = |code: u32, len: u32| code >> (len - TABLE_BITS);
+        let prefix = prefix_of(next_code[len as usize] - 1, len);
+        let mut max_len = len;
+        for &symbol in &sorted[1..] {
+            let len = code_lengths[symbol as usize] as u32;
+            if prefix_of(next_code[len as usize], len) != prefix {
+                break;
+            }
+            next_code[len as usize] += 1;
+            max_len = len;
+        }
+        max_len
+    }
+
+    /// Returns the entry for the code at the lowest bits of `bits`, which must have enough bits for the longest code
+    #[inline(always)]
+    pub fn lookup(&self, bits: u64) -> HuffmanEntry {
+        let entry = self.table[bits as usize & Self::TABLE_BITS_MASK];
+        let subtable_bits = entry.subtable_bits();
+        if subtable_bits == 0 {
+            return entry;
+        }
+        let index = (bits >> TABLE_BITS) as usize & ((1 << subtable_bits) - 1);
+        self.table[entry.symbol() as usize + index]
+    }
+
     pub fn get_next_symbol(&self, input: &mut InputBuffer<'_>) -> Result<u16, InternalErr> {
         debug_assert_ne!(self.code_lengths_length, 0, "invalid table");
         // Try to load 16 bits into input buffer if possible and get the bit_buffer value.
@@ -255,24 +336,11 @@ impl HuffmanTree {
         }
 
         // decode an element
-        let mut entry = self.table[bit_buffer as usize & Self::TABLE_BITS_MASK];
-        let mut bits = bit_buffer >> Self::TABLE_BITS;
-        while entry < 0 {
-            // navigate the tree: left child at -entry, right at -entry+1
-            let child_index = ((-entry) as usize) + (bits & 1) as usize;
-            entry = self.nodes[child_index];
-            // shift bits down and mask for branchless left/right indexing
-            bits >>= 1;
-        }
-
-        let (symbol, code_length) = unpack(entry);
-
-        if code_length <= 0 || code_length > 16 {
-            return Err(InternalErr::DataError); // InvalidHuffmanData
-        }
+        let entry = self.lookup(bit_buffer);
+        let code_length = entry.code_length();
 
         // If this code is longer than the # bits we had in the bit buffer (i.e.
-        // we read only part of the code), we can hit the entry in the table or the tree
+        // we read only part of the code), we can hit the entry in the table
         // for another symbol. However the length of another symbol will not match the
         // available bits count.
         if code_length > input.available_bits() {
@@ -281,35 +349,29 @@ impl HuffmanTree {
             return Err(InternalErr::DataNeeded);
         }
 
+        if entry.is_invalid() {
+            return Err(InternalErr::DataError(InflateErrorKind::InvalidHuffmanData));
+        }
+
         input.skip_bits(code_length);
-        Ok(symbol)
+        Ok(entry.symbol())
     }
 
     // get_next_symbol_assume_input is an optimization of get_next_symbol when the caller
-    // knows that 16 bits exist in the bit buffer or are available as input bytes. It is
-    // meant for use in an optimized decode loop that strictly verifies this precondition.
-    // If the precondition is violated, the call will assert in debug builds and is likely
-    // to produce an incorrect symbol in release builds.
+    // knows that the bit buffer has enough bits for the longest code. It is meant for use
+    // in an optimized decode loop that strictly verifies this precondition.
     #[inline(always)]
     pub fn get_next_symbol_assume_input(
         &self,
         input: &mut InputBuffer<'_>,
     ) -> Result<u16, InternalErr> {
         debug_assert_ne!(self.code_lengths_length, 0, "invalid table");
-        let bit_buffer = input.load_16bits_assume_input();
-        let mut entry = self.table[bit_buffer as usize & Self::TABLE_BITS_MASK];
-        let mut bits = bit_buffer >> Self::TABLE_BITS;
-        while entry < 0 {
-            let child_index = ((-entry) as usize) + (bits & 1) as usize;
-            entry = self.nodes[child_index];
-            bits >>= 1;
+        let entry = self.lookup(input.peek_available_bits());
+        if entry.is_invalid() {
+            return Err(InternalErr::DataError(InflateErrorKind::InvalidHuffmanData));
         }
-        let (symbol, code_length) = unpack(entry);
-        if code_length == 0 {
-            return Err(InternalErr::DataError);
-        }
-        input.skip_bits(code_length);
-        Ok(symbol)
+        input.skip_bits(entry.code_length());
+        Ok(entry.symbol())
     }
 
     #[allow(dead_code)]
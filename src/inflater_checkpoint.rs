@@ -63,9 +63,12 @@
 //! output counters), followed by the output window history, and a Fletcher-32
 //! checksum.
 
-use crate::huffman_tree::HuffmanTree;
+use crate::huffman_tree::{HuffmanTree, MAX_DIST_TREE_ELEMENTS, MAX_LITERAL_TREE_ELEMENTS};
 use crate::input_buffer::{BitsBuffer, InputBuffer};
+use crate::output_window::{WindowStorage, WINDOW_SIZE};
 use crate::{BlockType, InflaterState};
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
 
 use super::{InflaterManaged, TABLE_LOOKUP_DISTANCE_MAX};
 
@@ -82,11 +85,40 @@ use super::{InflaterManaged, TABLE_LOOKUP_DISTANCE_MAX};
 //   302     32    dist_code_lengths: [u8; 32]
 //   334     8     output_bytes_written: u64
 //   342     4     output_bytes_unread: u32
-//   346     var   window_data: [u8] (len = max(min(65538, output_bytes_written), output_bytes_unread))
+//   346     var   window_data: [u8] (len = max(min(65536, output_bytes_written + dictionary length), output_bytes_unread))
 //   END-4   4     checksum: u32 (Fletcher-32)
 
 const CHECKPOINT_HEADER_SIZE: usize = 346;
 
+/// The maximum size of the checkpoint written by
+/// [`checkpoint_into()`](super::InflaterManaged::checkpoint_into).
+pub const MAX_CHECKPOINT_SIZE: usize = CHECKPOINT_HEADER_SIZE + WINDOW_SIZE + 4;
+
+/// The checkpoint before serialization
+struct CheckpointParts<'a> {
+    header: [u8; CHECKPOINT_HEADER_SIZE],
+    window_a: &'a [u8],
+    window_b: &'a [u8],
+    positions: CheckpointStreamPositions,
+}
+
+impl CheckpointParts<'_> {
+    fn len(&self) -> usize {
+        CHECKPOINT_HEADER_SIZE + self.window_a.len() + self.window_b.len() + 4
+    }
+
+    /// Writes the checkpoint to `out`, which must be exactly `self.len()` bytes
+    fn write_to(&self, out: &mut [u8]) {
+        let (body, checksum) = out.split_at_mut(out.len() - 4);
+        let (header, window) = body.split_at_mut(CHECKPOINT_HEADER_SIZE);
+        let (window_a, window_b) = window.split_at_mut(self.window_a.len());
+        header.copy_from_slice(&self.header);
+        window_a.copy_from_slice(self.window_a);
+        window_b.copy_from_slice(self.window_b);
+        checksum.copy_from_slice(&fletcher32_checksum(body).to_le_bytes());
+    }
+}
+
 fn fletcher32_checksum(data: &[u8]) -> u32 {
     let (mut a, mut b) = (0u32, 0u32);
     for &byte in data {
@@ -100,11 +132,11 @@ fn fletcher32_checksum(data: &[u8]) -> u32 {
 /// Called from the parent module's decode functions.
 #[inline(always)]
 pub(super) fn update_checkpoint(
-    inflater: &mut InflaterManaged,
+    inflater: &mut InflaterManaged<impl WindowStorage>,
     input: &InputBuffer<'_>,
     end_of_block: bool,
 ) {
-    debug_assert!(input.available_bits() >= 0 && input.available_bits() <= 32);
+    debug_assert!(input.available_bits() >= 0 && input.available_bits() <= 64);
     // checkpoint_input_bits tracks the number of input bits consumed up to the checkpoint.
     inflater.checkpoint_input_bits =
         (inflater.total_input_loaded + input.read_bytes as u64) * 8 - input.available_bits() as u64;
@@ -132,7 +164,7 @@ pub(super) fn update_checkpoint(
     }
 }
 
-impl InflaterManaged {
+impl<W: WindowStorage> InflaterManaged<W> {
     /// Serialize the most recent inflater checkpoint for use with
     /// [`restore_from_checkpoint()`](Self::restore_from_checkpoint).
     ///
@@ -142,8 +174,29 @@ impl InflaterManaged {
     /// The returned checkpoint can contain up to 129KB of data representing the
     /// inflater state and history buffer. The `CheckpointStreamPositions` describes
     /// the input/output byte offsets corresponding to this checkpoint.
-    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "checkpoint", feature = "alloc"))))]
     pub fn checkpoint(&self) -> Option<(Vec<u8>, CheckpointStreamPositions)> {
+        let parts = self.checkpoint_parts()?;
+        let mut out = vec![0u8; parts.len()];
+        parts.write_to(&mut out);
+        Some((out, parts.positions))
+    }
+
+    /// Serialize the most recent inflater checkpoint into `buf` without allocation.
+    ///
+    /// Returns the length of the checkpoint written to the start of `buf`, or `None`
+    /// if no checkpoint is available or `buf` is too short. A buffer of
+    /// [`MAX_CHECKPOINT_SIZE`] bytes is always long enough.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn checkpoint_into(&self, buf: &mut [u8]) -> Option<(usize, CheckpointStreamPositions)> {
+        let parts = self.checkpoint_parts()?;
+        let len = parts.len();
+        parts.write_to(buf.get_mut(..len)?);
+        Some((len, parts.positions))
+    }
+
+    fn checkpoint_parts(&self) -> Option<CheckpointParts<'_>> {
         if self.checkpoint_input_bits == 0
             || self.errored()
             || (self.output.available_bytes() == 0 && self.state == InflaterState::Done)
@@ -158,8 +211,8 @@ impl InflaterManaged {
             _ => 0,
         };
 
-        let mut lit_codes = [0; HuffmanTree::MAX_LITERAL_TREE_ELEMENTS];
-        let mut dist_codes = [0; HuffmanTree::MAX_DIST_TREE_ELEMENTS];
+        let mut lit_codes = [0; MAX_LITERAL_TREE_ELEMENTS];
+        let mut dist_codes = [0; MAX_DIST_TREE_ELEMENTS];
         if checkpoint_block_type == BlockType::Dynamic {
             let lens = self.literal_length_tree.code_lengths();
             lit_codes[..lens.len()].copy_from_slice(lens);
@@ -170,7 +223,9 @@ impl InflaterManaged {
         let output_bytes_written =
             self.total_output_consumed + self.output.available_bytes() as u64;
         let bytes_unread = self.output.available_bytes() as u32;
-        let (window_a, window_b) = self.output.get_checkpoint_data(output_bytes_written);
+        let (window_a, window_b) = self
+            .output
+            .get_checkpoint_data(output_bytes_written + self.dictionary_len as u64);
 
         let bfinal_block_type = self.checkpoint_bfinal_block_type;
 
@@ -178,29 +233,32 @@ impl InflaterManaged {
         let num_buffered_bits = (8 - (self.checkpoint_input_bits & 7)) as u32 & 7;
         let buffered_value = self.checkpoint_bit_buffer & ((1 << num_buffered_bits) - 1);
 
-        let mut out = Vec::with_capacity(CHECKPOINT_HEADER_SIZE + window_a.len() + window_b.len());
-        out.extend_from_slice(&0x1001u16.to_le_bytes()); // version
-        out.extend_from_slice(&self.checkpoint_input_bits.to_le_bytes());
-        out.push(buffered_value);
-        out.push(bfinal_block_type);
-        out.extend_from_slice(&(uncompressed_remaining as u16).to_le_bytes());
-        out.extend_from_slice(&lit_codes);
-        out.extend_from_slice(&dist_codes);
-        out.extend_from_slice(&output_bytes_written.to_le_bytes());
-        out.extend_from_slice(&bytes_unread.to_le_bytes());
-        debug_assert_eq!(out.len(), CHECKPOINT_HEADER_SIZE);
-        out.extend_from_slice(window_a);
-        out.extend_from_slice(window_b);
-        let checksum = fletcher32_checksum(&out);
-        out.extend_from_slice(&checksum.to_le_bytes());
-
-        Some((
-            out,
-            CheckpointStreamPositions {
+        let mut header = [0u8; CHECKPOINT_HEADER_SIZE];
+        let mut rest = &mut header[..];
+        let mut put = |bytes: &[u8]| {
+            let (field, tail) = core::mem::take(&mut rest).split_at_mut(bytes.len());
+            field.copy_from_slice(bytes);
+            rest = tail;
+        };
+        put(&0x1001u16.to_le_bytes()); // version
+        put(&self.checkpoint_input_bits.to_le_bytes());
+        put(&[buffered_value, bfinal_block_type]);
+        put(&(uncompressed_remaining as u16).to_le_bytes());
+        put(&lit_codes);
+        put(&dist_codes);
+        put(&output_bytes_written.to_le_bytes());
+        put(&bytes_unread.to_le_bytes());
+        debug_assert!(rest.is_empty());
+
+        Some(CheckpointParts {
+            header,
+            window_a,
+            window_b,
+            positions: CheckpointStreamPositions {
                 input_bytes_to_skip: self.checkpoint_input_bits.div_ceil(8),
                 output_bytes_already_returned: output_bytes_written - bytes_unread as u64,
             },
-        ))
+        })
     }
 
     /// Restore inflater state from a previously serialized checkpoint.
@@ -246,8 +304,8 @@ impl InflaterManaged {
         let buffered_value: u8 = read(1)?[0];
         let bfinal_block_type: u8 = read(1)?[0];
         let remaining_uncompressed: u16 = u16::from_le_bytes(read(2)?.try_into().ok()?);
-        let lit_codes: &[u8] = read(HuffmanTree::MAX_LITERAL_TREE_ELEMENTS)?;
-        let dist_codes: &[u8] = read(HuffmanTree::MAX_DIST_TREE_ELEMENTS)?;
+        let lit_codes: &[u8] = read(MAX_LITERAL_TREE_ELEMENTS)?;
+        let dist_codes: &[u8] = read(MAX_DIST_TREE_ELEMENTS)?;
         let output_bytes_written: u64 = u64::from_le_bytes(read(8)?.try_into().ok()?);
         let output_bytes_unread: u32 = u32::from_le_bytes(read(4)?.try_into().ok()?);
         let window_data: &[u8] = cursor;
@@ -255,10 +313,12 @@ impl InflaterManaged {
         let num_buffered_bits = (8 - (input_bits & 7)) as i32 & 7;
         let bits = BitsBuffer::from_bits(buffered_value as u32, num_buffered_bits);
 
-        let expected_window_len = (output_bytes_written.min(TABLE_LOOKUP_DISTANCE_MAX as u64)
-            as u32)
+        // The window may have the preset dictionary before the output as history
+        let min_window_len = (output_bytes_written.min(TABLE_LOOKUP_DISTANCE_MAX as u64) as u32)
             .max(output_bytes_unread) as usize;
-        if window_data.len() != expected_window_len
+        let max_window_len = (TABLE_LOOKUP_DISTANCE_MAX as u32).max(output_bytes_unread) as usize;
+        if window_data.len() < min_window_len
+            || window_data.len() > max_window_len
             || window_data.len() > crate::output_window::WINDOW_SIZE
         {
             return None;
@@ -291,10 +351,19 @@ impl InflaterManaged {
 
         // All validation passed - modify self
         self.bits = bits;
+        self.error = None;
+        self.dictionary_len =
+            (window_data.len() as u64).saturating_sub(output_bytes_written) as usize;
         self.checkpoint_input_bits = input_bits;
         self.checkpoint_bit_buffer = buffered_value;
         self.total_output_consumed = output_bytes_written - output_bytes_unread as u64;
         self.total_input_loaded = input_bits.div_ceil(8);
+        // the header of the block in progress is not available
+        self.block_start_input_bits = input_bits;
+        self.block_start_output = output_bytes_written;
+        self.literal_length_code_count = 0;
+        self.distance_code_count = 0;
+        self.code_length_code_count = 0;
 
         self.output
             .restore_from_checkpoint(window_data, output_bytes_unread as usize);
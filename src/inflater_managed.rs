@@ -1,32 +1,40 @@
+use crate::block_info::BlockInfo;
 use crate::buffer::Buffer;
-use crate::huffman_tree::HuffmanTree;
+use crate::error::{InflateError, InflateErrorKind};
+use crate::huffman_tree::{
+    CodeLengthTree, DistanceTree, HuffmanEntry, HuffmanTree, LiteralLengthTree, END_OF_BLOCK_CODE,
+    MAX_DIST_TREE_ELEMENTS, MAX_LITERAL_TREE_ELEMENTS, NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS,
+};
 use crate::input_buffer::{BitsBuffer, InputBuffer};
-use crate::output_window::OutputWindow;
+use crate::limits::InflateLimits;
+use crate::observer::{BlockObserver, NoObserver, Observer, TokenObserver};
+use crate::output_window::{OutputWindow, WindowStorage, WINDOW_SIZE};
+use crate::token::{Token, TokenSink};
 use crate::{array_copy, array_copy1, BlockType, InflateResult, InflaterState, InternalErr};
-use std::cmp::min;
-use std::mem::MaybeUninit;
+use core::cmp::min;
+use core::mem::MaybeUninit;
 
 // Extra bits for length code 257 - 285.
-static EXTRA_LENGTH_BITS: [u8; 29] = [
+pub(crate) static EXTRA_LENGTH_BITS: [u8; 29] = [
     0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 16,
 ];
 
 // The base length for length code 257 - 285.
 // The formula to get the real length for a length code is lengthBase[code - 257] + (value stored in extraBits)
-static LENGTH_BASE: [u8; 29] = [
+pub(crate) static LENGTH_BASE: [u8; 29] = [
     3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
     163, 195, 227, 3,
 ];
 
 // The base distance for distance code 0 - 31
 // The real distance for a distance code is  distanceBasePosition[code] + (value stored in extraBits)
-static DISTANCE_BASE_POSITION: [u16; 32] = [
+pub(crate) static DISTANCE_BASE_POSITION: [u16; 32] = [
     1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
     2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577, 32769, 49153,
 ];
 
 // code lengths for code length alphabet is stored in following order
-static CODE_ORDER: [u8; 19] = [
+pub(crate) static CODE_ORDER: [u8; 19] = [
     16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
 ];
 
@@ -43,18 +51,19 @@ static STATIC_DISTANCE_TREE_TABLE: [u8; 32] = [
 //       original deflate have (2^8-1)+3 length with 2^15 distance, and
 //       65538 is (2^16-1)+3 and 65536 is 2^15.
 // [APPNOTE.TXT]: https://pkware.cachefly.net/webdocs/casestudies/APPNOTE.TXT
-const TABLE_LOOKUP_LENGTH_MAX: usize = 65538;
+pub(crate) const TABLE_LOOKUP_LENGTH_MAX: usize = 65538;
 pub(crate) const TABLE_LOOKUP_DISTANCE_MAX: usize = 65536;
 
-/// The streaming Inflater for deflate64
+/// The streaming Inflater for deflate64 (or plain deflate with [`new_deflate()`](Self::new_deflate))
 ///
-/// This struct has big buffer so It's not recommended to move this struct.
+/// By default, this struct has big buffer so It's not recommended to move this struct.
+/// The buffer can be placed outside with [`with_window()`](Self::with_window).
 #[derive(Debug)]
-pub struct InflaterManaged {
-    output: OutputWindow,
+pub struct InflaterManaged<W: WindowStorage = [u8; WINDOW_SIZE]> {
+    output: OutputWindow<W>,
     bits: BitsBuffer,
-    literal_length_tree: HuffmanTree,
-    distance_tree: HuffmanTree,
+    literal_length_tree: LiteralLengthTree,
+    distance_tree: DistanceTree,
 
     state: InflaterState,
     bfinal: bool,
@@ -76,11 +85,22 @@ pub struct InflaterManaged {
     code_array_size: u32,
     length_code: u16,
 
-    code_list: [u8; HuffmanTree::MAX_LITERAL_TREE_ELEMENTS + HuffmanTree::MAX_DIST_TREE_ELEMENTS], // temporary array to store the code length for literal/Length and distance
-    code_length_tree_code_length: [u8; HuffmanTree::NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS],
+    code_list: [u8; MAX_LITERAL_TREE_ELEMENTS + MAX_DIST_TREE_ELEMENTS], // temporary array to store the code length for literal/Length and distance
+    code_length_tree_code_length: [u8; NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS],
     deflate64: bool,
-    code_length_tree: HuffmanTree,
+    code_length_tree: CodeLengthTree,
     uncompressed_size: usize,
+    error: Option<InflateError>,
+    dictionary_len: usize, // length of the history before the stream
+    limits: InflateLimits,
+    block_count: u64,
+    strict: bool,
+
+    // Position of the block currently decoding, for BlockInfo
+    block_start_input_bits: u64,
+    block_start_output: u64,
+    // Position of the token currently decoding, for TokenSink
+    token_start_input_bits: u64,
 
     // Cumulative counters updated once per inflate call
     total_input_loaded: u64, // total bytes loaded into bit reader, only updated after decode()
@@ -105,17 +125,122 @@ impl InflaterManaged {
 
     /// Initializes Inflater with expected uncompressed size.
     pub fn with_uncompressed_size(uncompressed_size: usize) -> Self {
+        Self::with_format([0; WINDOW_SIZE], true, uncompressed_size)
+    }
+
+    /// Initializes Inflater for plain deflate (RFC 1951) stream instead of deflate64
+    ///
+    /// In this mode, length code 285 means length 258, and distance codes 30 and 31
+    /// (distances above 32 KiB) are rejected as invalid data.
+    #[inline]
+    pub fn new_deflate() -> Self {
+        Self::deflate_with_uncompressed_size(usize::MAX)
+    }
+
+    /// Initializes Inflater for plain deflate (RFC 1951) stream with expected uncompressed size.
+    pub fn deflate_with_uncompressed_size(uncompressed_size: usize) -> Self {
+        Self::with_format([0; WINDOW_SIZE], false, uncompressed_size)
+    }
+}
+
+impl<W: WindowStorage> InflaterManaged<W> {
+    /// Initializes Inflater with the window placed outside of this struct
+    ///
+    /// ```
+    /// # use deflate64::{InflaterManaged, WINDOW_SIZE};
+    /// let mut window = Box::new([0u8; WINDOW_SIZE]);
+    /// let mut inflater = InflaterManaged::with_window(&mut *window);
+    /// ```
+    pub fn with_window(window: W) -> Self {
+        Self::with_format(window, true, usize::MAX)
+    }
+
+    /// Initializes Inflater for plain deflate (RFC 1951) stream with the window placed outside of this struct
+    pub fn deflate_with_window(window: W) -> Self {
+        Self::with_format(window, false, usize::MAX)
+    }
+
+    /// Returns the window storage, dropping this inflater
+    pub fn into_window(self) -> W {
+        self.output.into_window()
+    }
+
+    /// Resets this inflater to decompress a new stream
+    ///
+    /// The format, the limits, and strict mode are kept, and the preset dictionary is removed.
+    /// The window is not zeroed, so the data of the previous stream is left in it.
+    /// It is not output since the matches reaching before the start of the stream are
+    /// rejected as [`InflateErrorKind::DistanceTooFar`]. Use [`reset_with()`](Self::reset_with)
+    /// to zero the window, for example if the previous stream had sensitive data.
+    pub fn reset(&mut self) {
+        self.reset_with(usize::MAX, false)
+    }
+
+    /// Resets this inflater to decompress a new stream with expected uncompressed size
+    ///
+    /// See [`reset()`](Self::reset).
+    pub fn reset_with_uncompressed_size(&mut self, uncompressed_size: usize) {
+        self.reset_with(uncompressed_size, false)
+    }
+
+    /// Resets this inflater to decompress a new stream with expected uncompressed size,
+    /// zeroing the window if `clear_window` is true
+    ///
+    /// Zeroing the window costs as much as creating a new inflater.
+    /// See [`reset()`](Self::reset).
+    pub fn reset_with(&mut self, uncompressed_size: usize, clear_window: bool) {
+        self.output.reset();
+        if clear_window {
+            self.output.clear();
+        }
+        self.bits = BitsBuffer::new();
+        self.uncompressed_size = uncompressed_size;
+        self.error = None;
+        self.dictionary_len = 0;
+        self.block_count = 0;
+        self.state = InflaterState::ReadingBFinal;
+        self.bfinal = false;
+        self.block_type = BlockType::Uncompressed;
+        self.block_length_buffer = [0u8; 4];
+        self.block_length = 0;
+        self.length = 0;
+        self.distance_code = 0;
+        self.extra_bits = 0;
+        self.loop_counter = 0;
+        self.literal_length_code_count = 0;
+        self.distance_code_count = 0;
+        self.code_length_code_count = 0;
+        self.code_array_size = 0;
+        self.length_code = 0;
+        self.block_start_input_bits = 0;
+        self.block_start_output = 0;
+        self.token_start_input_bits = 0;
+        self.total_input_loaded = 0;
+        self.total_output_consumed = 0;
+        #[cfg(feature = "checkpoint")]
+        {
+            self.checkpoint_input_bits = 0;
+            self.checkpoint_bit_buffer = 0;
+            self.checkpoint_bfinal_block_type = 0;
+        }
+    }
+
+    fn with_format(window: W, deflate64: bool, uncompressed_size: usize) -> Self {
         Self {
-            output: OutputWindow::new(),
+            output: OutputWindow::new(window),
             bits: BitsBuffer::new(),
 
             literal_length_tree: HuffmanTree::invalid(),
-            code_list: [0u8; HuffmanTree::MAX_LITERAL_TREE_ELEMENTS
-                + HuffmanTree::MAX_DIST_TREE_ELEMENTS],
-            code_length_tree_code_length: [0u8; HuffmanTree::NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS],
-            deflate64: true,
+            code_list: [0u8; MAX_LITERAL_TREE_ELEMENTS + MAX_DIST_TREE_ELEMENTS],
+            code_length_tree_code_length: [0u8; NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS],
+            deflate64,
             code_length_tree: HuffmanTree::invalid(),
             uncompressed_size,
+            error: None,
+            dictionary_len: 0,
+            limits: InflateLimits::new(),
+            block_count: 0,
+            strict: false,
             state: InflaterState::ReadingBFinal, // start by reading BFinal bit
             bfinal: false,
             block_type: BlockType::Uncompressed,
@@ -131,6 +256,9 @@ impl InflaterManaged {
             code_array_size: 0,
             distance_tree: HuffmanTree::invalid(),
             length_code: 0,
+            block_start_input_bits: 0,
+            block_start_output: 0,
+            token_start_input_bits: 0,
             total_input_loaded: 0,
             total_output_consumed: 0,
             #[cfg(feature = "checkpoint")]
@@ -144,10 +272,10 @@ impl InflaterManaged {
 
     /// Returns true if decompression finished and no more output is available
     ///
-    /// This also returns true if this inflater is in error state
+    /// This also returns true if this inflater is in error state and all output before the error is returned
     pub fn finished(&self) -> bool {
-        (self.state == InflaterState::Done && self.available_output() == 0)
-            || self.state == InflaterState::DataErrored
+        (self.state == InflaterState::Done || self.state == InflaterState::DataErrored)
+            && self.available_output() == 0
     }
 
     /// Returns true if decompression finished, but may still have output available in buffer
@@ -162,18 +290,138 @@ impl InflaterManaged {
         self.state == InflaterState::DataErrored
     }
 
+    /// Returns the error if this inflater is in error state
+    pub fn error(&self) -> Option<InflateError> {
+        self.error
+    }
+
+    /// Sets the preset dictionary.
+    ///
+    /// The dictionary is used as the history before the stream, so the stream can refer
+    /// the dictionary with back-references. The dictionary is not returned as output.
+    /// Since deflate64 can refer only last 64 KiB, only the last 64 KiB of the dictionary is used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if decompression has already started.
+    pub fn set_dictionary(&mut self, dictionary: &[u8]) {
+        assert!(
+            self.total_input_loaded == 0
+                && self.state == InflaterState::ReadingBFinal
+                && self.bits.bits_in_buffer == 0,
+            "set_dictionary must be called before decompression"
+        );
+        let start = dictionary.len().saturating_sub(TABLE_LOOKUP_DISTANCE_MAX);
+        self.output.preset_dictionary(&dictionary[start..]);
+        self.dictionary_len = min(
+            self.dictionary_len + dictionary.len(),
+            TABLE_LOOKUP_DISTANCE_MAX,
+        );
+    }
+
+    /// Sets the limits to stop decompression of hostile input
+    pub fn set_limits(&mut self, limits: InflateLimits) {
+        self.limits = limits;
+    }
+
+    /// Returns the limits set with [`Self::set_limits`]
+    pub fn limits(&self) -> InflateLimits {
+        self.limits
+    }
+
+    /// Enables or disables strict mode
+    ///
+    /// By default, output longer than the expected uncompressed size is silently dropped,
+    /// and shorter output or data after the end of the stream is ignored.
+    /// In strict mode, they are reported as [`InflateErrorKind::OutputTooLong`],
+    /// [`InflateErrorKind::OutputTooShort`], and [`InflateErrorKind::TrailingData`].
+    /// Any input passed after the end of the stream is trailing data in strict mode.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Returns true if strict mode is enabled
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Returns true if this inflater decodes deflate64 stream, false for plain deflate stream
+    pub fn is_deflate64(&self) -> bool {
+        self.deflate64
+    }
+
     /// The count of bytes currently inflater has in internal output buffer
     #[allow(dead_code)]
     pub fn available_output(&self) -> usize {
         self.output.available_bytes()
     }
 
+    /// Returns the error for the input ending before the end of the stream
+    #[cfg(feature = "alloc")]
+    pub(crate) fn unexpected_end_of_input(&self) -> InflateError {
+        InflateError::new(
+            InflateErrorKind::UnexpectedEndOfInput,
+            self.total_in_bits(),
+            self.total_out(),
+        )
+    }
+
+    /// Switches between deflate64 and plain deflate. This must be followed by [`Self::reset`].
+    #[cfg(feature = "std")]
+    pub(crate) fn set_deflate64(&mut self, deflate64: bool) {
+        self.deflate64 = deflate64;
+    }
+
+    /// Returns the preset dictionary. This is valid only before decompression.
+    #[cfg(all(feature = "checkpoint", feature = "std"))]
+    pub(crate) fn dictionary(&self) -> impl Iterator<Item = u8> + '_ {
+        debug_assert!(self.total_input_loaded == 0 && self.output.available_bytes() == 0);
+        self.output.last_bytes(self.dictionary_len)
+    }
+
+    /// Starts decoding at `bit` of `byte`, which is followed by the input of the next inflate.
+    /// The offsets are counted from the start of `byte`.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn start_in_byte(&mut self, byte: u8, bit: u32) {
+        debug_assert!(self.total_input_loaded == 0 && self.bits.bits_in_buffer == 0 && bit < 8);
+        self.bits = BitsBuffer {
+            bit_buffer: (byte >> bit) as u64,
+            bits_in_buffer: 8 - bit as i32,
+        };
+        self.total_input_loaded = 1;
+    }
+
+    /// The offset in bits where the block currently decoding starts
+    #[cfg(feature = "alloc")]
+    pub(crate) fn block_start_bit_offset(&self) -> u64 {
+        self.block_start_input_bits
+    }
+
+    /// Returns the total count of input bytes consumed, which is the sum of
+    /// [`InflateResult::bytes_consumed`]
+    pub fn total_in(&self) -> u64 {
+        self.total_input_loaded
+    }
+
+    /// Returns the total count of input bits decoded
+    ///
+    /// This is less than `total_in() * 8` if some bits of the last byte consumed are not decoded yet.
+    pub fn total_in_bits(&self) -> u64 {
+        self.total_input_loaded * 8 - self.bits.bits_in_buffer as u64
+    }
+
+    /// Returns the total count of output bytes returned, which is the sum of
+    /// [`InflateResult::bytes_written`]
+    pub fn total_out(&self) -> u64 {
+        self.total_output_consumed
+    }
+
     /// Try to decompress from `input` to `output`.
     ///
     /// This will decompress data until `output` is full, `input` is empty,
     /// the end if the deflate64 stream is hit, or there is error data in the deflate64 stream.
     pub fn inflate(&mut self, input: &[u8], output: &mut [u8]) -> InflateResult {
-        self.inflate_internal(input, Buffer::Init(output))
+        self.inflate_internal(input, Buffer::Init(output), &mut NoObserver)
     }
 
     /// Same as [`Self::inflate`] but accepts uninitialized buffer
@@ -182,18 +430,67 @@ impl InflaterManaged {
         input: &[u8],
         output: &mut [MaybeUninit<u8>],
     ) -> InflateResult {
-        self.inflate_internal(input, Buffer::Uninit(output))
+        self.inflate_internal(input, Buffer::Uninit(output), &mut NoObserver)
+    }
+
+    /// Same as [`Self::inflate`] but calls `on_block_end` with [`BlockInfo`] for each block
+    /// when the end of the block is decoded.
+    ///
+    /// If this inflater is restored from a checkpoint, the block in progress is reported
+    /// as if it starts at the checkpoint, without the header information.
+    pub fn inflate_with_events(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+        on_block_end: impl FnMut(&BlockInfo),
+    ) -> InflateResult {
+        self.inflate_internal(
+            input,
+            Buffer::Init(output),
+            &mut BlockObserver(on_block_end),
+        )
+    }
+
+    /// Same as [`Self::inflate`] but reports each decoded [`Token`] to `sink`
+    pub fn inflate_with_tokens<S: TokenSink + ?Sized>(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+        sink: &mut S,
+    ) -> InflateResult {
+        self.inflate_internal(input, Buffer::Init(output), &mut TokenObserver(sink))
     }
 
-    fn inflate_internal(&mut self, input: &[u8], mut output: Buffer<'_>) -> InflateResult {
+    /// Decodes `input` and reports each decoded [`Token`] to `sink` without returning output.
+    ///
+    /// This will decode until `input` is empty, the end if the deflate64 stream is hit,
+    /// or there is error data in the deflate64 stream.
+    /// [`InflateResult::bytes_written`] is the count of bytes decoded and discarded.
+    pub fn inflate_tokens<S: TokenSink + ?Sized>(
+        &mut self,
+        input: &[u8],
+        sink: &mut S,
+    ) -> InflateResult {
+        self.inflate_internal(input, Buffer::Discard(usize::MAX), &mut TokenObserver(sink))
+    }
+
+    fn inflate_internal<O: Observer>(
+        &mut self,
+        input: &[u8],
+        mut output: Buffer<'_>,
+        observer: &mut O,
+    ) -> InflateResult {
         // copy bytes from output to outputbytes if we have available bytes
         // if buffer is not filled up. keep decoding until no input are available
         // if decodeBlock returns false. Throw an exception.
         let mut result = InflateResult::new();
+        let input_len = input.len();
         let mut input = InputBuffer::new(self.bits, input);
+        let mut data_needed = false;
         while 'while_loop: {
             let mut copied = 0;
-            if self.uncompressed_size == usize::MAX {
+            if self.uncompressed_size == usize::MAX || self.strict {
+                // in strict mode, the output beyond uncompressed_size is rejected in check_limits
                 copied = self.output.copy_to(output.reborrow());
             } else if (self.uncompressed_size as u64) > self.total_output_consumed {
                 let remaining =
@@ -211,48 +508,218 @@ impl InflaterManaged {
                 self.total_output_consumed += copied as u64;
             }
 
+            if self.errored() && self.output.available_bytes() == 0 {
+                // all output decoded before the error is returned
+                result.error = self.error;
+                break 'while_loop false;
+            }
             if output.is_empty() {
                 // filled in the bytes buffer
                 break 'while_loop false;
             }
             // decode will return false when more input is needed
-            if self.errored() {
-                result.data_error = true;
-                break 'while_loop false;
-            } else if self.input_finished() {
+            if self.input_finished() {
                 break 'while_loop false;
             }
-            match self.decode(&mut input) {
+            let mut decoded = self.decode(&mut input, observer);
+            if !matches!(decoded, Err(InternalErr::DataError(_))) {
+                if let Err(kind) = self.check_limits(&input) {
+                    decoded = Err(InternalErr::DataError(kind));
+                }
+            }
+            match decoded {
                 Ok(()) => true,
-                Err(InternalErr::DataNeeded) => false,
-                Err(InternalErr::DataError) => {
-                    self.state = InflaterState::DataErrored;
-                    result.data_error = true;
+                Err(InternalErr::DataNeeded) => {
+                    data_needed = true;
                     false
                 }
+                Err(InternalErr::DataError(kind)) => {
+                    // return the output decoded before the error first
+                    self.set_data_error(kind, &input);
+                    true
+                }
             }
         } {}
 
+        if !data_needed {
+            // We may have read bytes beyond the end of the stream (or the point we stopped at)
+            // into the bit buffer. Give them back so that bytes_consumed is exact and
+            // data following the deflate64 stream can be read by the caller.
+            input.unread_whole_bytes();
+        }
+
+        if self.strict && self.state == InflaterState::Done && input.read_bytes < input_len {
+            self.set_data_error(InflateErrorKind::TrailingData, &input);
+            result.error = self.error;
+        }
+
         self.bits = input.bits;
+        // the next input may not start with the bytes read ahead
+        self.bits.clear_unused_bits();
         self.total_input_loaded += input.read_bytes as u64;
         result.bytes_consumed = input.read_bytes;
         result
     }
 
-    fn decode(&mut self, input: &mut InputBuffer<'_>) -> Result<(), InternalErr> {
+    /// The offset in bits from the start of the input which is read
+    fn input_bit_offset(&self, input: &InputBuffer<'_>) -> u64 {
+        (self.total_input_loaded + input.read_bytes as u64) * 8 - input.bits.bits_in_buffer as u64
+    }
+
+    /// The count of bytes decompressed including bytes not yet returned
+    fn output_byte_offset(&self) -> u64 {
+        self.total_output_consumed + self.output.available_bytes() as u64
+    }
+
+    fn set_data_error(&mut self, kind: InflateErrorKind, input: &InputBuffer<'_>) {
+        let input_bit_offset = self.input_bit_offset(input);
+        let output_byte_offset = self.output_byte_offset();
+        self.state = InflaterState::DataErrored;
+        self.error = Some(InflateError::new(
+            kind,
+            input_bit_offset,
+            output_byte_offset,
+        ));
+    }
+
+    /// Returns true if the match is longer than allowed or refers before the start of the history
+    #[inline(always)]
+    fn is_distance_too_far(&self, length: usize, distance: usize) -> bool {
+        length > TABLE_LOOKUP_LENGTH_MAX
+            || distance > TABLE_LOOKUP_DISTANCE_MAX
+            // the window may have data from the previous user, which must not be output
+            || (distance > self.output.end()
+                && self.output_byte_offset() + (self.dictionary_len as u64) < distance as u64)
+    }
+
+    /// The count of output bytes allowed for `input_bytes` by the limits and the size in strict mode,
+    /// and the error kind when it's exceeded
+    fn allowed_output(&self, input_bytes: u64) -> Option<(u64, InflateErrorKind)> {
+        let mut allowed = self.limits.allowed_output(input_bytes);
+        if self.strict && self.uncompressed_size != usize::MAX {
+            let expected = self.uncompressed_size as u64;
+            if allowed.is_none_or(|(limit, _)| expected < limit) {
+                allowed = Some((expected, InflateErrorKind::OutputTooLong));
+            }
+        }
+        allowed
+    }
+
+    /// Checks the output limits and the size in strict mode, discarding the output beyond the limit
+    fn check_limits(&mut self, input: &InputBuffer<'_>) -> Result<(), InflateErrorKind> {
+        let input_bytes = self.input_bit_offset(input).div_ceil(8);
+        let output = self.output_byte_offset();
+        if let Some((allowed, kind)) = self.allowed_output(input_bytes) {
+            if output > allowed {
+                let excess = min(output - allowed, self.output.available_bytes() as u64);
+                self.output.discard_last(excess as usize);
+                return Err(kind);
+            }
+        }
+        if self.strict
+            && self.state == InflaterState::Done
+            && self.uncompressed_size != usize::MAX
+            && output < self.uncompressed_size as u64
+        {
+            return Err(InflateErrorKind::OutputTooShort);
+        }
+        Ok(())
+    }
+
+    /// The count of bits the token currently decoding takes
+    #[inline(always)]
+    fn token_bits(&self, input: &InputBuffer<'_>) -> u32 {
+        (self.input_bit_offset(input) - self.token_start_input_bits) as u32
+    }
+
+    pub(crate) fn block_info(&self, input: &InputBuffer<'_>) -> BlockInfo {
+        let mut info = BlockInfo {
+            block_type: self.block_type,
+            is_final: self.bfinal,
+            start_bit_offset: self.block_start_input_bits,
+            end_bit_offset: self.input_bit_offset(input),
+            uncompressed_size: self.output_byte_offset() - self.block_start_output,
+            literal_length_code_count: 0,
+            distance_code_count: 0,
+            code_length_code_count: 0,
+            code_length_code_lengths: [0; NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS],
+            literal_length_code_lengths: [0; MAX_LITERAL_TREE_ELEMENTS],
+            distance_code_lengths: [0; MAX_DIST_TREE_ELEMENTS],
+        };
+        if self.block_type == BlockType::Dynamic {
+            let literal_length_code_count = self.literal_length_code_count as usize;
+            let distance_code_count = self.distance_code_count as usize;
+            info.literal_length_code_count = literal_length_code_count as u16;
+            info.distance_code_count = distance_code_count as u8;
+            info.code_length_code_count = self.code_length_code_count as u8;
+            info.code_length_code_lengths = self.code_length_tree_code_length;
+            array_copy(
+                &self.code_list,
+                &mut info.literal_length_code_lengths,
+                literal_length_code_count,
+            );
+            array_copy1(
+                &self.code_list,
+                literal_length_code_count,
+                &mut info.distance_code_lengths,
+                0,
+                distance_code_count,
+            );
+        }
+        info
+    }
+
+    fn decode<O: Observer>(
+        &mut self,
+        input: &mut InputBuffer<'_>,
+        observer: &mut O,
+    ) -> Result<(), InternalErr> {
         let mut eob = false;
-        let result;
 
-        if self.errored() {
-            return Err(InternalErr::DataError);
+        if let Some(error) = self.error {
+            return Err(InternalErr::DataError(error.kind()));
         } else if self.input_finished() {
             return Ok(());
         }
 
+        self.decode_block_header(input)?;
+        let result = match self.block_type {
+            // this can returns true when output is full
+            BlockType::Dynamic | BlockType::Static => self.decode_block(input, &mut eob, observer),
+            BlockType::Uncompressed => self.decode_uncompressed_block(input, &mut eob, observer),
+        };
+
+        if eob {
+            observer.end_of_block(self, input);
+        }
+
+        //
+        // If we reached the end of the block and the block we were decoding had
+        // bfinal=1 (final block)
+        //
+        if eob && self.bfinal {
+            self.state = InflaterState::Done;
+        }
+        result
+    }
+
+    /// Reads the block header until the start of the block data,
+    /// `DecodeTop` for compressed blocks or `UncompressedAligning` for uncompressed blocks
+    fn decode_block_header(&mut self, input: &mut InputBuffer<'_>) -> Result<(), InternalErr> {
         if self.state == InflaterState::ReadingBFinal {
+            if self
+                .limits
+                .get_max_blocks()
+                .is_some_and(|max| self.block_count >= max)
+            {
+                return Err(InternalErr::DataError(InflateErrorKind::BlockLimitExceeded));
+            }
+            self.block_start_input_bits = self.input_bit_offset(input);
+            self.block_start_output = self.output_byte_offset();
             // reading bfinal bit
             // Need 1 bit
             self.bfinal = input.get_bits(1)? != 0;
+            self.block_count += 1;
             self.state = InflaterState::ReadingBType;
         }
 
@@ -261,7 +728,8 @@ impl InflaterManaged {
             self.state = InflaterState::ReadingBType;
             let bits = input.get_bits(2)?;
 
-            self.block_type = BlockType::from_int(bits).ok_or(InternalErr::DataError)?;
+            self.block_type = BlockType::from_int(bits)
+                .ok_or(InternalErr::DataError(InflateErrorKind::UnknownBlockType))?;
             match self.block_type {
                 BlockType::Dynamic => {
                     self.state = InflaterState::ReadingNumLitCodes;
@@ -277,37 +745,60 @@ impl InflaterManaged {
             }
         }
 
-        if self.block_type == BlockType::Dynamic {
-            if self.state < InflaterState::DecodeTop {
-                // we are reading the header
-                result = self.decode_dynamic_block_header(input);
-            } else {
-                result = self.decode_block(input, &mut eob); // this can returns true when output is full
+        if self.block_type == BlockType::Dynamic && self.state < InflaterState::DecodeTop {
+            // we are reading the header
+            self.decode_dynamic_block_header(input)?;
+        }
+        Ok(())
+    }
+
+    fn decode_uncompressed_block<O: Observer>(
+        &mut self,
+        input: &mut InputBuffer<'_>,
+        end_of_block: &mut bool,
+        observer: &mut O,
+    ) -> Result<(), InternalErr> {
+        *end_of_block = false;
+        self.decode_uncompressed_block_header(input)?;
+
+        // Directly copy bytes from input to output.
+        let bytes_copied = self.output.copy_from(input, self.block_length);
+        self.block_length -= bytes_copied;
+
+        if O::TOKENS {
+            for byte in self.output.last_bytes(bytes_copied) {
+                observer.token(Token::Literal(byte), 8);
             }
-        } else if self.block_type == BlockType::Static {
-            result = self.decode_block(input, &mut eob);
-        } else if self.block_type == BlockType::Uncompressed {
-            result = self.decode_uncompressed_block(input, &mut eob);
-        } else {
-            result = Err(InternalErr::DataError); // UnknownBlockType
         }
 
-        //
-        // If we reached the end of the block and the block we were decoding had
-        // bfinal=1 (final block)
-        //
-        if eob && self.bfinal {
-            self.state = InflaterState::Done;
+        if self.block_length == 0 {
+            // Done with this block, need to re-init bit buffer for next block
+            if O::TOKENS {
+                observer.token(Token::EndOfBlock, 0);
+            }
+            self.state = InflaterState::ReadingBFinal;
+            *end_of_block = true;
+            self.update_checkpoint_after_write_or_eob(input, true);
+            return Ok(());
         }
-        result
+
+        self.update_checkpoint_after_write_or_eob(input, false);
+
+        // We can fail to copy all bytes for two reasons:
+        //    Running out of Input
+        //    running out of free space in output window
+        if self.output.free_bytes() == 0 {
+            return Ok(());
+        }
+
+        Err(InternalErr::DataNeeded)
     }
 
-    fn decode_uncompressed_block(
+    /// Reads LEN and NLEN of the uncompressed block until `DecodingUncompressed`
+    fn decode_uncompressed_block_header(
         &mut self,
         input: &mut InputBuffer<'_>,
-        end_of_block: &mut bool,
     ) -> Result<(), InternalErr> {
-        *end_of_block = false;
         loop {
             match self.state {
                 InflaterState::UncompressedAligning => {
@@ -330,7 +821,9 @@ impl InflaterManaged {
 
                         // make sure complement matches
                         if self.block_length as u16 != !block_length_complement as u16 {
-                            return Err(InternalErr::DataError); // InvalidBlockLength
+                            return Err(InternalErr::DataError(
+                                InflateErrorKind::InvalidBlockLength,
+                            )); // InvalidBlockLength
                         }
                     }
 
@@ -342,30 +835,7 @@ impl InflaterManaged {
                         _ => unreachable!(),
                     };
                 }
-                InflaterState::DecodingUncompressed => {
-                    // Directly copy bytes from input to output.
-                    let bytes_copied = self.output.copy_from(input, self.block_length);
-                    self.block_length -= bytes_copied;
-
-                    if self.block_length == 0 {
-                        // Done with this block, need to re-init bit buffer for next block
-                        self.state = InflaterState::ReadingBFinal;
-                        *end_of_block = true;
-                        self.update_checkpoint_after_write_or_eob(input, true);
-                        return Ok(());
-                    }
-
-                    self.update_checkpoint_after_write_or_eob(input, false);
-
-                    // We can fail to copy all bytes for two reasons:
-                    //    Running out of Input
-                    //    running out of free space in output window
-                    if self.output.free_bytes() == 0 {
-                        return Ok(());
-                    }
-
-                    return Err(InternalErr::DataNeeded);
-                }
+                InflaterState::DecodingUncompressed => return Ok(()),
                 _ => {
                     panic!("UnknownState");
                 }
@@ -373,10 +843,11 @@ impl InflaterManaged {
         }
     }
 
-    fn decode_block(
+    fn decode_block<O: Observer>(
         &mut self,
         input: &mut InputBuffer<'_>,
         end_of_block_code_seen: &mut bool,
+        observer: &mut O,
     ) -> Result<(), InternalErr> {
         *end_of_block_code_seen = false;
 
@@ -384,7 +855,11 @@ impl InflaterManaged {
             // Tight inner loop for decoding and processing deflate symbols when we know
             // that there is both enough input available and also sufficient output space.
             // State machine variables are not modified and self.state stays as DecodeTop.
-            match self.decode_block_fast_inner_loop(input) {
+            // The input is copied to the stack so that the bit buffer is kept in registers
+            let mut local_input = input.clone();
+            let result = self.decode_block_fast_inner_loop(&mut local_input, observer);
+            *input = local_input;
+            match result {
                 Ok((_, true)) => {
                     // End of block reached
                     *end_of_block_code_seen = true;
@@ -392,18 +867,26 @@ impl InflaterManaged {
                     self.update_checkpoint_after_write_or_eob(input, true);
                     return Ok(());
                 }
-                Ok((0, false)) => {
+                Ok((written, false)) => {
+                    if written > 0 {
+                        self.update_checkpoint_after_write_or_eob(input, false);
+                        // Some fast progress was made. The rest of the input is decoded with the
+                        // state machine, and running out of input is reported on the next call
+                        // so that the output can be consumed by the caller first.
+                        return match self.decode_block_state_machine(
+                            input,
+                            end_of_block_code_seen,
+                            observer,
+                        ) {
+                            Err(InternalErr::DataNeeded) => Ok(()),
+                            result => result,
+                        };
+                    }
                     // No fast progress, fall through to slower but comprehensive
                     // state machine implementation which can load partial input.
                 }
-                Ok((_, false)) => {
-                    // Some fast progress was made. Return so that output can be
-                    // consumed by the caller and/or more input can be provided.
-                    self.update_checkpoint_after_write_or_eob(input, false);
-                    return Ok(());
-                }
-                Err(InternalErr::DataError) => {
-                    return Err(InternalErr::DataError);
+                Err(InternalErr::DataError(kind)) => {
+                    return Err(InternalErr::DataError(kind));
                 }
                 Err(InternalErr::DataNeeded) => {
                     unreachable!("fast inner loop never returns DataNeeded")
@@ -411,6 +894,15 @@ impl InflaterManaged {
             }
         }
 
+        self.decode_block_state_machine(input, end_of_block_code_seen, observer)
+    }
+
+    fn decode_block_state_machine<O: Observer>(
+        &mut self,
+        input: &mut InputBuffer<'_>,
+        end_of_block_code_seen: &mut bool,
+        observer: &mut O,
+    ) -> Result<(), InternalErr> {
         // State machine path
         let mut free_bytes = self.output.free_bytes();
         while free_bytes >= TABLE_LOOKUP_LENGTH_MAX {
@@ -422,6 +914,10 @@ impl InflaterManaged {
                 InflaterState::DecodeTop => {
                     // decode an element from the literal tree
 
+                    if O::TOKENS {
+                        self.token_start_input_bits = self.input_bit_offset(input);
+                    }
+
                     // TODO: optimize this!!!
                     symbol = self.literal_length_tree.get_next_symbol(input)?;
 
@@ -429,10 +925,16 @@ impl InflaterManaged {
                     if symbol < 256 {
                         // literal
                         self.output.write(symbol as u8);
+                        if O::TOKENS {
+                            observer.token(Token::Literal(symbol as u8), self.token_bits(input));
+                        }
                         free_bytes -= 1;
                         self.update_checkpoint_after_write_or_eob(input, false);
                     } else if symbol == 256 {
                         // end of block
+                        if O::TOKENS {
+                            observer.token(Token::EndOfBlock, self.token_bits(input));
+                        }
                         *end_of_block_code_seen = true;
                         // Reset state
                         self.state = InflaterState::ReadingBFinal;
@@ -450,7 +952,9 @@ impl InflaterManaged {
                             self.extra_bits = 0;
                         } else {
                             if symbol as usize >= EXTRA_LENGTH_BITS.len() {
-                                return Err(InternalErr::DataError); // GenericInvalidData
+                                return Err(InternalErr::DataError(
+                                    InflateErrorKind::InvalidLengthSymbol,
+                                )); // GenericInvalidData
                             }
                             self.extra_bits = EXTRA_LENGTH_BITS[symbol as usize] as i32;
                             assert_ne!(self.extra_bits, 0, "We handle other cases separately!");
@@ -467,7 +971,9 @@ impl InflaterManaged {
                         let bits = input.get_bits(self.extra_bits)?;
 
                         if self.length >= LENGTH_BASE.len() {
-                            return Err(InternalErr::DataError); // GenericInvalidData
+                            return Err(InternalErr::DataError(
+                                InflateErrorKind::InvalidLengthSymbol,
+                            )); // GenericInvalidData
                         }
                         self.length = LENGTH_BASE[self.length] as usize + bits as usize;
                     }
@@ -484,6 +990,13 @@ impl InflaterManaged {
                         self.distance_code = STATIC_DISTANCE_TREE_TABLE[bits as usize] as u16;
                     }
 
+                    if !self.deflate64 && self.distance_code >= 30 {
+                        // distance codes 30 and 31 are only valid for deflate64
+                        return Err(InternalErr::DataError(
+                            InflateErrorKind::InvalidDistanceSymbol,
+                        ));
+                    }
+
                     self.state = InflaterState::HaveDistCode;
                     continue; //goto case InflaterState.HaveDistCode;
                 }
@@ -501,11 +1014,20 @@ impl InflaterManaged {
                         offset = (self.distance_code + 1) as usize;
                     }
 
-                    if self.length > TABLE_LOOKUP_LENGTH_MAX || offset > TABLE_LOOKUP_DISTANCE_MAX {
-                        return Err(InternalErr::DataError);
+                    if self.is_distance_too_far(self.length, offset) {
+                        return Err(InternalErr::DataError(InflateErrorKind::DistanceTooFar));
                     }
 
                     self.output.write_length_distance(self.length, offset);
+                    if O::TOKENS {
+                        observer.token(
+                            Token::Match {
+                                length: self.length as u32,
+                                distance: offset as u32,
+                            },
+                            self.token_bits(input),
+                        );
+                    }
                     free_bytes -= self.length;
                     self.state = InflaterState::DecodeTop;
                     self.update_checkpoint_after_write_or_eob(input, false);
@@ -524,76 +1046,153 @@ impl InflaterManaged {
     /// Fast inner loop for decoding literals and length/distance pairs. Breaks out as soon as
     /// maximum possible output cannot fit, or maximum possible input required is not available.
     /// Returns (bytes_written, end_of_block) on success or InternalErr on failure.
-    fn decode_block_fast_inner_loop(
+    fn decode_block_fast_inner_loop<O: Observer>(
         &mut self,
         input: &mut InputBuffer<'_>,
+        observer: &mut O,
     ) -> Result<(usize, bool), InternalErr> {
         let initial_free = self.output.free_bytes();
 
         loop {
             // Exit fast path if low on output space or input bits.
-            // Maximum input consumed per iteration is 64 bits, or 8 bytes:
-            //  16 bits for initial symbol value >= 257, indicating match length
+            // Maximum output per iteration is two literal entries, 4 bytes, followed by a match.
+            // Maximum input consumed per iteration is 90 bits, or 12 bytes.
+            // The bit buffer is refilled to at least 56 bits at the start and after the length code:
+            //  30 bits for two literal entries
+            //  15 bits for initial symbol value >= 257, indicating match length
             //  16 bits for match length "extra bits"
-            //  16 bits for distance symbol
-            //  16 bits for distance "extra bits"
-            if self.output.free_bytes() < TABLE_LOOKUP_LENGTH_MAX || input.available_bytes() < 8 {
+            //  15 bits for distance symbol
+            //  14 bits for distance "extra bits"
+            if self.output.free_bytes() < TABLE_LOOKUP_LENGTH_MAX + 4
+                || input.available_bytes() < 12
+            {
                 return Ok((initial_free - self.output.free_bytes(), false));
             }
+            input.refill_bits();
 
-            let symbol = self
-                .literal_length_tree
-                .get_next_symbol_assume_input(input)?;
+            if O::TOKENS {
+                self.token_start_input_bits = self.input_bit_offset(input);
+            }
+
+            let mut entry = self.literal_length_tree.lookup(input.peek_available_bits());
+            if !O::TOKENS && entry.symbol() < 256 {
+                // The bits left are enough for up to three literal entries, or a length code
+                self.write_literals(input, entry);
+                entry = self.literal_length_tree.lookup(input.peek_available_bits());
+                if entry.symbol() < 256 {
+                    self.write_literals(input, entry);
+                    entry = self.literal_length_tree.lookup(input.peek_available_bits());
+                    if entry.symbol() < 256 {
+                        self.write_literals(input, entry);
+                        continue;
+                    }
+                }
+            }
+
+            let symbol = entry.symbol();
             match symbol {
                 0..=255 => {
                     // Literal byte
+                    input.skip_bits(entry.code_length());
                     self.output.write(symbol as u8);
+                    if O::TOKENS {
+                        observer.token(Token::Literal(symbol as u8), self.token_bits(input));
+                    }
                 }
                 256 => {
                     // End of block
+                    input.skip_bits(entry.code_length());
+                    if O::TOKENS {
+                        observer.token(Token::EndOfBlock, self.token_bits(input));
+                    }
                     return Ok((initial_free - self.output.free_bytes(), true));
                 }
                 257..=285 => {
                     // Length/distance pair
-                    let length_index = (symbol - 257) as usize;
-                    let length = if length_index < 8 {
-                        length_index + 3
-                    } else {
-                        let extra_bits = EXTRA_LENGTH_BITS[length_index] as i32;
-                        let bits = input.get_bits_assume_input(extra_bits);
-                        LENGTH_BASE[length_index] as usize + bits as usize
-                    };
-
-                    let distance_code = if self.block_type == BlockType::Dynamic {
-                        self.distance_tree.get_next_symbol_assume_input(input)? as usize
-                    } else {
-                        STATIC_DISTANCE_TREE_TABLE[input.get_bits_assume_input(5) as usize] as usize
-                    };
-
-                    let offset = if distance_code <= 3 {
-                        distance_code + 1
-                    } else {
-                        let extra_bits = ((distance_code - 2) >> 1) as i32;
-                        let bits = input.get_bits_assume_input(extra_bits);
-                        *DISTANCE_BASE_POSITION
-                            .get(distance_code)
-                            .ok_or(InternalErr::DataError)? as usize
-                            + bits as usize
-                    };
-
-                    if length > TABLE_LOOKUP_LENGTH_MAX || offset > TABLE_LOOKUP_DISTANCE_MAX {
-                        return Err(InternalErr::DataError);
+                    input.skip_bits(entry.code_length());
+                    let (length, offset) = self.decode_match_assume_input(input, symbol)?;
+                    if self.is_distance_too_far(length, offset) {
+                        return Err(InternalErr::DataError(InflateErrorKind::DistanceTooFar));
                     }
                     self.output.write_length_distance(length, offset);
+                    if O::TOKENS {
+                        observer.token(
+                            Token::Match {
+                                length: length as u32,
+                                distance: offset as u32,
+                            },
+                            self.token_bits(input),
+                        );
+                    }
+                }
+                _ if entry.is_invalid() => {
+                    return Err(InternalErr::DataError(InflateErrorKind::InvalidHuffmanData));
                 }
                 _ => {
                     // Symbol out of range
-                    return Err(InternalErr::DataError);
+                    input.skip_bits(entry.code_length());
+                    return Err(InternalErr::DataError(
+                        InflateErrorKind::InvalidLengthSymbol,
+                    ));
                 }
             }
         }
     }
 
+    /// Decodes the length and the distance of the match after the length `symbol`.
+    /// The bit buffer is refilled first, which is enough for the rest of the match.
+    #[inline(always)]
+    fn decode_match_assume_input(
+        &self,
+        input: &mut InputBuffer<'_>,
+        symbol: u16,
+    ) -> Result<(usize, usize), InternalErr> {
+        input.refill_bits();
+        let length_index = (symbol - 257) as usize;
+        let length = if length_index < 8 {
+            length_index + 3
+        } else if !self.deflate64 && length_index == 28 {
+            258
+        } else {
+            let extra_bits = EXTRA_LENGTH_BITS[length_index] as i32;
+            let bits = input.get_bits_assume_input(extra_bits);
+            LENGTH_BASE[length_index] as usize + bits as usize
+        };
+
+        let distance_code = if self.block_type == BlockType::Dynamic {
+            self.distance_tree.get_next_symbol_assume_input(input)? as usize
+        } else {
+            STATIC_DISTANCE_TREE_TABLE[input.get_bits_assume_input(5) as usize] as usize
+        };
+        if !self.deflate64 && distance_code >= 30 {
+            return Err(InternalErr::DataError(
+                InflateErrorKind::InvalidDistanceSymbol,
+            ));
+        }
+
+        let offset = if distance_code <= 3 {
+            distance_code + 1
+        } else {
+            let extra_bits = ((distance_code - 2) >> 1) as i32;
+            let bits = input.get_bits_assume_input(extra_bits);
+            *DISTANCE_BASE_POSITION
+                .get(distance_code)
+                .ok_or(InternalErr::DataError(
+                    InflateErrorKind::InvalidDistanceSymbol,
+                ))? as usize
+                + bits as usize
+        };
+        Ok((length, offset))
+    }
+
+    /// Writes the literal of the entry, or two literals of the literal pair entry
+    #[inline(always)]
+    fn write_literals(&mut self, input: &mut InputBuffer<'_>, entry: HuffmanEntry) {
+        input.skip_bits_from_low_bits(entry.literals_length_bits());
+        self.output
+            .write_literals(entry.literals(), entry.literal_count());
+    }
+
     // Format of the dynamic block header:
     //      5 Bits: HLIT, # of Literal/Length codes - 257 (257 - 286)
     //      5 Bits: HDIST, # of Distance codes - 1        (1 - 32)
@@ -692,7 +1291,9 @@ impl InflaterManaged {
 
                                 if self.loop_counter == 0 {
                                     // can't have "prev code" on first code
-                                    return Err(InternalErr::DataError);
+                                    return Err(InternalErr::DataError(
+                                        InflateErrorKind::InvalidCodeLengthRepeat,
+                                    ));
                                 }
 
                                 let bits = input.get_bits(2)?;
@@ -702,7 +1303,9 @@ impl InflaterManaged {
 
                                 if self.loop_counter + repeat_count > self.code_array_size {
                                     //throw new InvalidDataException();
-                                    return Err(InternalErr::DataError);
+                                    return Err(InternalErr::DataError(
+                                        InflateErrorKind::InvalidCodeLengthRepeat,
+                                    ));
                                 }
 
                                 for _ in 0..repeat_count {
@@ -717,7 +1320,9 @@ impl InflaterManaged {
 
                                 if self.loop_counter + repeat_count > self.code_array_size {
                                     //throw new InvalidDataException();
-                                    return Err(InternalErr::DataError);
+                                    return Err(InternalErr::DataError(
+                                        InflateErrorKind::InvalidCodeLengthRepeat,
+                                    ));
                                 }
 
                                 for _ in 0..repeat_count {
@@ -733,7 +1338,9 @@ impl InflaterManaged {
 
                                 if self.loop_counter + repeat_count > self.code_array_size {
                                     //throw new InvalidDataException();
-                                    return Err(InternalErr::DataError);
+                                    return Err(InternalErr::DataError(
+                                        InflateErrorKind::InvalidCodeLengthRepeat,
+                                    ));
                                 }
 
                                 for _ in 0..repeat_count {
@@ -752,8 +1359,8 @@ impl InflaterManaged {
             }
         }
 
-        let mut literal_tree_code_length = [0u8; HuffmanTree::MAX_LITERAL_TREE_ELEMENTS];
-        let mut distance_tree_code_length = [0u8; HuffmanTree::MAX_DIST_TREE_ELEMENTS];
+        let mut literal_tree_code_length = [0u8; MAX_LITERAL_TREE_ELEMENTS];
+        let mut distance_tree_code_length = [0u8; MAX_DIST_TREE_ELEMENTS];
 
         // Create literal and distance tables
         array_copy(
@@ -770,8 +1377,10 @@ impl InflaterManaged {
         );
 
         // Make sure there is an end-of-block code, otherwise how could we ever end?
-        if literal_tree_code_length[HuffmanTree::END_OF_BLOCK_CODE] == 0 {
-            return Err(InternalErr::DataError); // InvalidDataException
+        if literal_tree_code_length[END_OF_BLOCK_CODE] == 0 {
+            return Err(InternalErr::DataError(
+                InflateErrorKind::MissingEndOfBlockCode,
+            )); // InvalidDataException
         }
 
         self.literal_length_tree
@@ -797,3 +1406,6 @@ impl InflaterManaged {
 #[cfg(feature = "checkpoint")]
 #[path = "inflater_checkpoint.rs"]
 pub mod checkpoint;
+
+#[path = "inflater_slice.rs"]
+mod slice;
@@ -0,0 +1,343 @@
+//! Decoding directly into the caller's output slice.
+//!
+//! This module provides [`inflate_to_slice()`](super::InflaterManaged::inflate_to_slice).
+//! The output slice is used as the history of the matches instead of the window,
+//! so the decompressed data is written once without copying through the window.
+//! When the stream does not end in the slice, the last 64 KiB of the history is
+//! copied into the window and decoding continues the same as
+//! [`inflate()`](super::InflaterManaged::inflate).
+
+use super::{
+    InflaterManaged, DISTANCE_BASE_POSITION, EXTRA_LENGTH_BITS, LENGTH_BASE,
+    STATIC_DISTANCE_TREE_TABLE, TABLE_LOOKUP_DISTANCE_MAX,
+};
+use crate::error::InflateErrorKind;
+use crate::huffman_tree::HuffmanEntry;
+use crate::input_buffer::InputBuffer;
+use crate::output_window::{copy_match, WindowStorage};
+use crate::token::Token;
+use crate::{BlockType, InflateResult, InflaterState, InternalErr};
+use core::cmp::min;
+
+impl<W: WindowStorage> InflaterManaged<W> {
+    /// Same as [`Self::inflate`] but decodes directly into `output`, using it as the history.
+    ///
+    /// This is intended for decompressing the whole stream at once, for example a zip entry
+    /// with the known size. The output is not copied through the window if the stream ends
+    /// within `output`. Otherwise, the rest of the stream is decoded with the window, so this
+    /// can be followed by [`Self::inflate`] calls with more input or output.
+    ///
+    /// The output is decoded directly only at the start of the stream without dictionary.
+    /// After that, this is the same as [`Self::inflate`].
+    pub fn inflate_to_slice(&mut self, input: &[u8], output: &mut [u8]) -> InflateResult {
+        if self.output_byte_offset() != 0
+            || self.dictionary_len != 0
+            || matches!(
+                self.state,
+                InflaterState::HaveInitialLength
+                    | InflaterState::HaveFullLength
+                    | InflaterState::HaveDistCode
+                    | InflaterState::Done
+                    | InflaterState::DataErrored
+            )
+        {
+            return self.inflate(input, output);
+        }
+
+        // The output beyond the size or the limits is decoded with the window
+        let mut len = output.len();
+        if self.uncompressed_size != usize::MAX {
+            len = min(len, self.uncompressed_size);
+        }
+        let input_bytes = self.total_input_loaded + input.len() as u64;
+        if let Some((allowed, _)) = self.allowed_output(input_bytes) {
+            len = min(len as u64, allowed) as usize;
+        }
+
+        let mut input_buffer = InputBuffer::new(self.bits, input);
+        let mut written = 0;
+        let mut decoded = self.decode_to_slice(&mut input_buffer, &mut output[..len], &mut written);
+
+        // The output is returned only up to the limit for the input actually read
+        let input_bytes = self.input_bit_offset(&input_buffer).div_ceil(8);
+        if let Some((allowed, kind)) = self.allowed_output(input_bytes) {
+            if written as u64 > allowed && !matches!(decoded, Err(InternalErr::DataError(_))) {
+                written = allowed as usize;
+                decoded = Err(InternalErr::DataError(kind));
+            }
+        }
+        self.total_output_consumed = written as u64;
+        if !matches!(decoded, Err(InternalErr::DataError(_))) {
+            if let Err(kind) = self.check_limits(&input_buffer) {
+                decoded = Err(InternalErr::DataError(kind));
+            }
+        }
+
+        match decoded {
+            Err(InternalErr::DataError(kind)) => self.set_data_error(kind, &input_buffer),
+            _ if self.state != InflaterState::Done => {
+                // The rest of the stream refers to the history in the window
+                let history = written.saturating_sub(TABLE_LOOKUP_DISTANCE_MAX);
+                self.output.preset_dictionary(&output[history..written]);
+                if matches!(
+                    self.state,
+                    InflaterState::DecodeTop | InflaterState::DecodingUncompressed
+                ) {
+                    self.update_checkpoint_after_write_or_eob(&input_buffer, false);
+                }
+            }
+            _ => {}
+        }
+
+        // The bytes read ahead are given back to decode them with the window
+        input_buffer.unread_whole_bytes();
+        self.bits = input_buffer.bits;
+        self.bits.clear_unused_bits();
+        let read_bytes = input_buffer.read_bytes;
+        self.total_input_loaded += read_bytes as u64;
+
+        let mut result = self.inflate(&input[read_bytes..], &mut output[written..]);
+        result.bytes_consumed += read_bytes;
+        result.bytes_written += written;
+        result
+    }
+
+    /// Decodes blocks into `output[*written..]` until the end of the stream, the output is full,
+    /// or more input is needed.
+    fn decode_to_slice(
+        &mut self,
+        input: &mut InputBuffer<'_>,
+        output: &mut [u8],
+        written: &mut usize,
+    ) -> Result<(), InternalErr> {
+        loop {
+            match self.state {
+                InflaterState::Done => return Ok(()),
+                InflaterState::DecodeTop => {
+                    if !self.decode_block_to_slice(input, output, written)? {
+                        return Ok(());
+                    }
+                }
+                InflaterState::DecodingUncompressed => {
+                    let length = min(self.block_length, output.len() - *written);
+                    let copied = input.copy_to(&mut output[*written..][..length]);
+                    *written += copied;
+                    self.block_length -= copied;
+                    if self.block_length != 0 {
+                        return if copied == length {
+                            Ok(())
+                        } else {
+                            Err(InternalErr::DataNeeded)
+                        };
+                    }
+                }
+                _ => {
+                    self.decode_block_header(input)?;
+                    if self.block_type == BlockType::Uncompressed {
+                        self.decode_uncompressed_block_header(input)?;
+                    }
+                    continue;
+                }
+            }
+
+            // end of block
+            self.state = if self.bfinal {
+                InflaterState::Done
+            } else {
+                InflaterState::ReadingBFinal
+            };
+            self.update_checkpoint_after_write_or_eob(input, true);
+        }
+    }
+
+    /// Decodes the compressed block into `output[*written..]`.
+    /// Returns true at the end of the block, or false if the next token doesn't fit in `output`.
+    fn decode_block_to_slice(
+        &mut self,
+        input: &mut InputBuffer<'_>,
+        output: &mut [u8],
+        written: &mut usize,
+    ) -> Result<bool, InternalErr> {
+        loop {
+            // The input is copied to the stack so that the bit buffer is kept in registers
+            let mut local_input = input.clone();
+            let result = self.decode_block_to_slice_fast(&mut local_input, output, written);
+            *input = local_input;
+            if result? {
+                return Ok(true);
+            }
+
+            // Near the end of the input or the output, decode a token with partial input
+            let saved = input.clone();
+            let token = match self.decode_token(input) {
+                Err(InternalErr::DataNeeded) => {
+                    *input = saved;
+                    return Err(InternalErr::DataNeeded);
+                }
+                token => token?,
+            };
+            let pos = *written;
+            match token {
+                Token::Literal(_) | Token::Match { .. } if pos == output.len() => {
+                    *input = saved;
+                    return Ok(false);
+                }
+                Token::Literal(byte) => {
+                    output[pos] = byte;
+                    *written += 1;
+                }
+                Token::EndOfBlock => return Ok(true),
+                Token::Match { length, distance } => {
+                    let (length, distance) = (length as usize, distance as usize);
+                    if distance > pos {
+                        return Err(InternalErr::DataError(InflateErrorKind::DistanceTooFar));
+                    }
+                    if length > output.len() - pos {
+                        *input = saved;
+                        return Ok(false);
+                    }
+                    copy_match(output, pos - distance, pos, length);
+                    *written += length;
+                }
+            }
+        }
+    }
+
+    /// Fast inner loop for decoding into `output[*written..]`, the same as the fast inner loop
+    /// for the window except that the matches are checked against the output left.
+    /// Returns true at the end of the block, or false when the input or the output is low
+    /// or the match doesn't fit in `output`.
+    fn decode_block_to_slice_fast(
+        &mut self,
+        input: &mut InputBuffer<'_>,
+        output: &mut [u8],
+        written: &mut usize,
+    ) -> Result<bool, InternalErr> {
+        let mut pos = *written;
+        let result = loop {
+            // At most 6 bytes are written by three literal entries
+            if input.available_bytes() < 12 || output.len() - pos < 8 {
+                break Ok(false);
+            }
+            input.refill_bits();
+
+            let mut entry = self.literal_length_tree.lookup(input.peek_available_bits());
+            if entry.symbol() < 256 {
+                // The bits left are enough for up to three literal entries, or a length code
+                Self::write_literals_to_slice(input, entry, output, &mut pos);
+                entry = self.literal_length_tree.lookup(input.peek_available_bits());
+                if entry.symbol() < 256 {
+                    Self::write_literals_to_slice(input, entry, output, &mut pos);
+                    entry = self.literal_length_tree.lookup(input.peek_available_bits());
+                    if entry.symbol() < 256 {
+                        Self::write_literals_to_slice(input, entry, output, &mut pos);
+                        continue;
+                    }
+                }
+            }
+
+            let symbol = entry.symbol();
+            match symbol {
+                256 => {
+                    // End of block
+                    input.skip_bits(entry.code_length());
+                    break Ok(true);
+                }
+                257..=285 => {
+                    // Length/distance pair
+                    let saved = input.clone();
+                    input.skip_bits(entry.code_length());
+                    let (length, distance) = match self.decode_match_assume_input(input, symbol) {
+                        Ok(length_distance) => length_distance,
+                        Err(err) => break Err(err),
+                    };
+                    if distance > pos {
+                        break Err(InternalErr::DataError(InflateErrorKind::DistanceTooFar));
+                    }
+                    if length > output.len() - pos {
+                        *input = saved;
+                        break Ok(false);
+                    }
+                    copy_match(output, pos - distance, pos, length);
+                    pos += length;
+                }
+                _ if entry.is_invalid() => {
+                    break Err(InternalErr::DataError(InflateErrorKind::InvalidHuffmanData));
+                }
+                _ => {
+                    // Symbol out of range
+                    input.skip_bits(entry.code_length());
+                    break Err(InternalErr::DataError(
+                        InflateErrorKind::InvalidLengthSymbol,
+                    ));
+                }
+            }
+        };
+        *written = pos;
+        result
+    }
+
+    /// Writes the literal of the entry, or two literals of the literal pair entry, to `output`
+    #[inline(always)]
+    fn write_literals_to_slice(
+        input: &mut InputBuffer<'_>,
+        entry: HuffmanEntry,
+        output: &mut [u8],
+        pos: &mut usize,
+    ) {
+        input.skip_bits_from_low_bits(entry.literals_length_bits());
+        output[*pos..*pos + 2].copy_from_slice(&entry.literals().to_le_bytes());
+        *pos += entry.literal_count();
+    }
+
+    /// Decodes a token with partial input, returning `DataNeeded` without restoring the input
+    fn decode_token(&self, input: &mut InputBuffer<'_>) -> Result<Token, InternalErr> {
+        let symbol = self.literal_length_tree.get_next_symbol(input)?;
+        let length_index = match symbol {
+            0..=255 => return Ok(Token::Literal(symbol as u8)),
+            256 => return Ok(Token::EndOfBlock),
+            _ => (symbol - 257) as usize,
+        };
+
+        let length = if length_index < 8 {
+            length_index + 3
+        } else if !self.deflate64 && length_index == 28 {
+            258
+        } else {
+            let extra_bits = *EXTRA_LENGTH_BITS
+                .get(length_index)
+                .ok_or(InternalErr::DataError(
+                    InflateErrorKind::InvalidLengthSymbol,
+                ))? as i32;
+            LENGTH_BASE[length_index] as usize + input.get_bits(extra_bits)? as usize
+        };
+
+        let distance_code = if self.block_type == BlockType::Dynamic {
+            self.distance_tree.get_next_symbol(input)? as usize
+        } else {
+            STATIC_DISTANCE_TREE_TABLE[input.get_bits(5)? as usize] as usize
+        };
+        if !self.deflate64 && distance_code >= 30 {
+            return Err(InternalErr::DataError(
+                InflateErrorKind::InvalidDistanceSymbol,
+            ));
+        }
+
+        let distance = if distance_code <= 3 {
+            distance_code + 1
+        } else {
+            let extra_bits = ((distance_code - 2) >> 1) as i32;
+            let base = *DISTANCE_BASE_POSITION
+                .get(distance_code)
+                .ok_or(InternalErr::DataError(
+                    InflateErrorKind::InvalidDistanceSymbol,
+                ))? as usize;
+            base + input.get_bits(extra_bits)? as usize
+        };
+
+        Ok(Token::Match {
+            length: length as u32,
+            distance: distance as u32,
+        })
+    }
+}
@@ -1,9 +1,14 @@
 use crate::InternalErr;
-use std::cmp::min;
+use core::cmp::min;
 
+/// <summary>
+/// The bits read from the input and not yet consumed.
+/// The bits above `bits_in_buffer` may have the bits of the next input bytes loaded by
+/// `refill_bits`, which are the same as the bits loaded later.
+/// </summary>
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct BitsBuffer {
-    pub(crate) bit_buffer: u32,
+    pub(crate) bit_buffer: u64,
     pub(crate) bits_in_buffer: i32,
 }
 
@@ -18,14 +23,20 @@ impl BitsBuffer {
     #[allow(dead_code)]
     pub(crate) fn from_bits(bits: u32, num_bits: i32) -> BitsBuffer {
         debug_assert!((0..=32).contains(&num_bits));
-        let mask = (!0u32).unbounded_shr(num_bits as u32);
+        let mask = (!0u32).unbounded_shr(32 - num_bits as u32);
         Self {
-            bit_buffer: bits & mask,
+            bit_buffer: (bits & mask) as u64,
             bits_in_buffer: num_bits,
         }
     }
+
+    /// Clears the bits above `bits_in_buffer`
+    pub(crate) fn clear_unused_bits(&mut self) {
+        self.bit_buffer &= (!0u64).unbounded_shr(64 - self.bits_in_buffer as u32);
+    }
 }
 
+#[derive(Clone)]
 pub(crate) struct InputBuffer<'a> {
     pub bits: BitsBuffer,
     pub buffer: &'a [u8],
@@ -45,8 +56,8 @@ impl<'a> InputBuffer<'a> {
         self.bits.bits_in_buffer
     }
 
-    #[allow(dead_code)]
-    pub fn peek_available_bits(&self) -> u32 {
+    #[inline(always)]
+    pub fn peek_available_bits(&self) -> u64 {
         self.bits.bit_buffer
     }
 
@@ -64,7 +75,7 @@ impl<'a> InputBuffer<'a> {
             }
 
             // insert a byte to bitbuffer
-            self.bits.bit_buffer |= (self.buffer[0] as u32) << self.bits.bits_in_buffer;
+            self.bits.bit_buffer |= (self.buffer[0] as u64) << self.bits.bits_in_buffer;
             self.advance(1);
             self.bits.bits_in_buffer += 8;
 
@@ -73,7 +84,7 @@ impl<'a> InputBuffer<'a> {
                     return false;
                 }
                 // insert a byte to bitbuffer
-                self.bits.bit_buffer |= (self.buffer[0] as u32) << self.bits.bits_in_buffer;
+                self.bits.bit_buffer |= (self.buffer[0] as u64) << self.bits.bits_in_buffer;
                 self.advance(1);
                 self.bits.bits_in_buffer += 8;
             }
@@ -82,20 +93,20 @@ impl<'a> InputBuffer<'a> {
         true
     }
 
-    pub fn try_load_16bits(&mut self) -> u32 {
+    pub fn try_load_16bits(&mut self) -> u64 {
         if self.bits.bits_in_buffer < 8 {
             if self.buffer.len() > 1 {
-                self.bits.bit_buffer |= (self.buffer[0] as u32) << self.bits.bits_in_buffer;
-                self.bits.bit_buffer |= (self.buffer[1] as u32) << (self.bits.bits_in_buffer + 8);
+                self.bits.bit_buffer |= (self.buffer[0] as u64) << self.bits.bits_in_buffer;
+                self.bits.bit_buffer |= (self.buffer[1] as u64) << (self.bits.bits_in_buffer + 8);
                 self.advance(2);
                 self.bits.bits_in_buffer += 16;
             } else if !self.buffer.is_empty() {
-                self.bits.bit_buffer |= (self.buffer[0] as u32) << self.bits.bits_in_buffer;
+                self.bits.bit_buffer |= (self.buffer[0] as u64) << self.bits.bits_in_buffer;
                 self.advance(1);
                 self.bits.bits_in_buffer += 8;
             }
         } else if self.bits.bits_in_buffer < 16 && !self.buffer.is_empty() {
-            self.bits.bit_buffer |= (self.buffer[0] as u32) << self.bits.bits_in_buffer;
+            self.bits.bit_buffer |= (self.buffer[0] as u64) << self.bits.bits_in_buffer;
             self.advance(1);
             self.bits.bits_in_buffer += 8;
         }
@@ -103,7 +114,7 @@ impl<'a> InputBuffer<'a> {
         self.bits.bit_buffer
     }
 
-    fn get_bit_mask(&self, count: i32) -> u32 {
+    fn get_bit_mask(&self, count: i32) -> u64 {
         (1 << count) - 1
     }
 
@@ -120,34 +131,38 @@ impl<'a> InputBuffer<'a> {
         Ok(result)
     }
 
-    // load_16bits_assume_input is an optimization of try_load_16bits when the caller knows
-    // that 16 bits are in the bit buffer or available as remaining input bytes. If this
-    // precondition is false, the call will assert in debug builds and fill with zeros in
-    // release builds (likely resulting in data error).
+    // refill_bits loads at least 56 bits into the bit buffer, or all the remaining input.
+    // If 8 input bytes are available, they are loaded at once and only the whole bytes fit in
+    // the bit buffer are consumed. The rest of the word is left above bits_in_buffer.
     #[inline(always)]
-    pub fn load_16bits_assume_input(&mut self) -> u32 {
-        if self.bits.bits_in_buffer < 16 {
-            if let Some((head, tail)) = self.buffer.split_at_checked(2) {
-                let word = u16::from_le_bytes(head.try_into().unwrap());
-                self.bits.bit_buffer |= (word as u32) << self.bits.bits_in_buffer;
-                self.buffer = tail;
-                self.read_bytes += 2;
-            } else {
-                debug_assert!(false, "Not enough input available");
-            }
-            self.bits.bits_in_buffer += 16;
+    pub fn refill_bits(&mut self) {
+        if let Some(word) = self.buffer.first_chunk::<8>() {
+            self.bits.bit_buffer |= u64::from_le_bytes(*word) << self.bits.bits_in_buffer;
+            self.advance((63 - self.bits.bits_in_buffer as usize) / 8);
+            self.bits.bits_in_buffer |= 56;
+        } else {
+            self.refill_bits_bytewise();
         }
-        self.bits.bit_buffer
     }
 
-    // get_bits_assume_input is an optimization of get_bits using load_16bits_assume_input
-    // when the caller knows that 16 bits are in the bit buffer or available as remaining
-    // input bytes. If this precondition is false, the call will assert in debug builds and
-    // fill with zeros in release builds (likely resulting in data error).
+    #[cold]
+    fn refill_bits_bytewise(&mut self) {
+        while self.bits.bits_in_buffer <= 56 && !self.buffer.is_empty() {
+            self.bits.bit_buffer |= (self.buffer[0] as u64) << self.bits.bits_in_buffer;
+            self.advance(1);
+            self.bits.bits_in_buffer += 8;
+        }
+    }
+
+    // get_bits_assume_input is an optimization of get_bits when the caller knows that
+    // count bits are in the bit buffer after refill_bits.
     #[inline(always)]
     pub fn get_bits_assume_input(&mut self, count: i32) -> u32 {
-        debug_assert!(0 < count && count <= 16, "count is invalid.");
-        let result = self.load_16bits_assume_input() & self.get_bit_mask(count);
+        debug_assert!(
+            0 < count && count <= self.bits.bits_in_buffer,
+            "count is invalid."
+        );
+        let result = (self.bits.bit_buffer & self.get_bit_mask(count)) as u32;
         self.bits.bit_buffer >>= count;
         self.bits.bits_in_buffer -= count;
         result
@@ -170,12 +185,25 @@ impl<'a> InputBuffer<'a> {
             return bytes_from_bit_buffer;
         }
 
+        // the bytes read ahead into the bit buffer are consumed from the input directly
+        self.bits.clear_unused_bits();
         let length = min(output.len(), self.buffer.len());
         output[..length].copy_from_slice(&self.buffer[..length]);
         self.advance(length);
         bytes_from_bit_buffer + length
     }
 
+    /// <summary>
+    /// Returns whole bytes in the bit buffer to the input as if they were not read.
+    /// Only bytes read from this buffer can be returned.
+    /// </summary>
+    pub fn unread_whole_bytes(&mut self) {
+        let bytes = min((self.bits.bits_in_buffer / 8) as usize, self.read_bytes);
+        self.bits.bits_in_buffer -= bytes as i32 * 8;
+        self.bits.clear_unused_bits();
+        self.read_bytes -= bytes;
+    }
+
     pub fn needs_input(&self) -> bool {
         self.buffer.is_empty()
     }
@@ -190,12 +218,29 @@ impl<'a> InputBuffer<'a> {
         self.bits.bits_in_buffer -= n;
     }
 
+    /// <summary>
+    /// Skips the count of bits at the low 4 bits of `n`. The next 2 bits of `n` must be 0.
+    /// This saves masking the shift amount on the critical path of decoding literals.
+    /// </summary>
+    #[inline(always)]
+    pub fn skip_bits_from_low_bits(&mut self, n: u32) {
+        debug_assert!(n & 0x30 == 0, "shift amount has extra bits");
+        let count = (n & 0xF) as i32;
+        debug_assert!(
+            self.bits.bits_in_buffer >= count,
+            "No enough bits in the buffer, Did you call ensure_bits_available?"
+        );
+        self.bits.bit_buffer = self.bits.bit_buffer.wrapping_shr(n);
+        self.bits.bits_in_buffer -= count;
+    }
+
     /// <summary>Skips to the next byte boundary.</summary>
     pub fn skip_to_byte_boundary(&mut self) {
         self.bits.bit_buffer >>= self.bits.bits_in_buffer % 8;
         self.bits.bits_in_buffer -= self.bits.bits_in_buffer % 8;
     }
 
+    #[inline(always)]
     fn advance(&mut self, buf: usize) {
         self.buffer = &self.buffer[buf..];
         self.read_bytes += buf;
@@ -1,34 +1,110 @@
 //! Deflate64 implementation based on [.NET System.IO.Compression][dotnet].
 //!
 //! This is made to unzip zip file with deflate64 made with windows 11.
+//! This crate also provides [`DeflaterManaged`] to create deflate64 stream.
+//!
+//! # Features
+//!
+//! - `std` (default): `Read` and `Write` adapters like [`Deflate64Decoder`].
+//!   Without this feature, this crate is `no_std` and [`InflaterManaged`] is still available.
+//! - `alloc`: [`DeflaterManaged`], [`decompress_to_vec`], and checkpoints in `Vec`.
+//!   This is enabled by `std`.
+//! - `checkpoint`: saving and restoring the progress of [`InflaterManaged`].
+//! - `zip`, `tokio`, `futures`: see the modules with the same name.
 //!
 //! [dotnet]: https://github.com/dotnet/runtime/tree/e5efd8010e19593298dc2c3ee15106d5aec5a924/src/libraries/System.IO.Compression/src/System/IO/Compression/DeflateManaged
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(unsafe_code)]
 #![deny(rust_2018_idioms, nonstandard_style, future_incompatible)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod block_info;
 mod buffer;
+#[cfg(feature = "std")]
+mod checksum;
+#[cfg(feature = "alloc")]
+mod decompress;
+#[cfg(feature = "alloc")]
+mod deflater_managed;
+mod error;
+#[cfg(feature = "futures")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures")))]
+pub mod futures;
+#[cfg(feature = "std")]
+mod gzip;
+#[cfg(feature = "alloc")]
+mod huffman_encoder;
 mod huffman_tree;
 mod inflater_managed;
 mod input_buffer;
+mod limits;
+mod observer;
+#[cfg(feature = "alloc")]
+mod output_buffer;
 mod output_window;
+#[cfg(feature = "alloc")]
+mod recovery;
+#[cfg(all(feature = "checkpoint", feature = "std"))]
+mod seek;
+#[cfg(feature = "std")]
 mod stream;
-
+mod token;
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub mod tokio;
+#[cfg(feature = "zip")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+pub mod zip;
+#[cfg(feature = "std")]
+mod zlib;
+
+pub use block_info::BlockInfo;
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub use decompress::{decompress_to_vec, decompress_to_vec_with_limit};
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub use deflater_managed::DeflaterManaged;
+pub use error::{InflateError, InflateErrorKind};
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use gzip::{GzDeflate64Decoder, GzError, GzHeader};
 #[cfg(feature = "checkpoint")]
 pub use inflater_managed::checkpoint;
 pub use inflater_managed::InflaterManaged;
-pub use stream::Deflate64Decoder;
-
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum BlockType {
+pub use limits::InflateLimits;
+pub use output_window::{WindowStorage, WINDOW_SIZE};
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub use recovery::{InflateRecovery, RecoveredStream, SkippedRange};
+#[cfg(all(feature = "checkpoint", feature = "std"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "checkpoint", feature = "std"))))]
+pub use seek::{SeekIndex, SeekableDeflate64Decoder};
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use stream::{Deflate64Decoder, Deflate64DecoderWriter, Deflate64Encoder};
+pub use token::{Token, TokenSink};
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use zlib::{ZlibDeflate64Decoder, ZlibError};
+
+/// The type of the block in deflate64 stream
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum BlockType {
+    /// The block with stored (uncompressed) data
     Uncompressed = 0,
+    /// The block compressed with fixed huffman codes
     Static = 1,
+    /// The block compressed with dynamic huffman codes in the block header
     Dynamic = 2,
 }
 
 impl BlockType {
-    pub fn from_int(int: u16) -> Option<BlockType> {
+    pub(crate) fn from_int(int: u16) -> Option<BlockType> {
         match int {
             0 => Some(Self::Uncompressed),
             1 => Some(Self::Static),
@@ -40,7 +116,6 @@ impl BlockType {
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 enum InflaterState {
-    //ReadingHeader = 0,           // Only applies to GZIP
     ReadingBFinal = 2,
     // About to read bfinal bit
     ReadingBType = 3, // About to read blockType bits
@@ -73,17 +148,12 @@ enum InflaterState {
     UncompressedByte4 = 19,
     DecodingUncompressed = 20,
 
-    // These three apply only to GZIP
-    //StartReadingFooter = 21,
-    // (Initialisation for reading footer)
-    //ReadingFooter = 22,
-    //VerifyingFooter = 23,
     Done = 24, // Finished
 
     DataErrored = 100,
 }
 
-impl std::ops::Sub for InflaterState {
+impl core::ops::Sub for InflaterState {
     type Output = u8;
 
     fn sub(self, rhs: Self) -> Self::Output {
@@ -112,8 +182,8 @@ pub struct InflateResult {
     pub bytes_consumed: usize,
     /// The number of bytes written to the output slice.
     pub bytes_written: usize,
-    /// true if there is error in input buffer
-    pub data_error: bool,
+    /// The error in input buffer if any
+    pub error: Option<InflateError>,
 }
 
 impl InflateResult {
@@ -123,13 +193,50 @@ impl InflateResult {
         Self {
             bytes_consumed: 0,
             bytes_written: 0,
-            data_error: false,
+            error: None,
         }
     }
+
+    /// Returns true if there is error in input buffer
+    pub fn data_error(&self) -> bool {
+        self.error.is_some()
+    }
+}
+
+/// A structure containing result of streaming deflate.
+#[derive(Debug)]
+pub struct DeflateResult {
+    /// The number of bytes consumed from the input slice.
+    pub bytes_consumed: usize,
+    /// The number of bytes written to the output slice.
+    pub bytes_written: usize,
+}
+
+impl DeflateResult {
+    /// Creates `DeflateResult` with zero bytes consumed and written.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            bytes_consumed: 0,
+            bytes_written: 0,
+        }
+    }
+}
+
+/// How [`DeflaterManaged::deflate`] flushes the compressed data.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DeflateFlush {
+    /// Compressed data may be kept in internal buffer to get better compression.
+    None,
+    /// All input is compressed and written followed by an empty uncompressed block,
+    /// so the reader can decompress all data written so far.
+    Sync,
+    /// All input is compressed and the deflate64 stream is finished.
+    Finish,
 }
 
 #[derive(Debug)]
 enum InternalErr {
     DataNeeded,
-    DataError,
+    DataError(InflateErrorKind),
 }
@@ -0,0 +1,87 @@
+/// The limits to stop decompression of hostile input
+///
+/// Exceeding the limit results in [`InflateError`](crate::InflateError) with
+/// [`OutputLimitExceeded`](crate::InflateErrorKind::OutputLimitExceeded),
+/// [`RatioLimitExceeded`](crate::InflateErrorKind::RatioLimitExceeded), or
+/// [`BlockLimitExceeded`](crate::InflateErrorKind::BlockLimitExceeded).
+/// The output up to the limit is returned before the error.
+///
+/// ```
+/// # use deflate64::{InflateLimits, InflaterManaged};
+/// let mut inflater = InflaterManaged::new();
+/// inflater.set_limits(InflateLimits::new().max_output(1 << 30).max_ratio(1000));
+/// ```
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct InflateLimits {
+    max_output: Option<u64>,
+    max_ratio: Option<u64>,
+    max_blocks: Option<u64>,
+}
+
+impl InflateLimits {
+    /// The output allowed in addition to the ratio limit, so that short streams are not rejected.
+    pub const RATIO_ALLOWANCE: u64 = 64 * 1024;
+
+    /// Creates InflateLimits without any limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the total count of decompressed bytes
+    pub fn max_output(mut self, max_output: u64) -> Self {
+        self.max_output = Some(max_output);
+        self
+    }
+
+    /// Limits the count of decompressed bytes to `ratio` times the count of consumed input bytes
+    /// plus [`RATIO_ALLOWANCE`](Self::RATIO_ALLOWANCE)
+    pub fn max_ratio(mut self, ratio: u64) -> Self {
+        self.max_ratio = Some(ratio);
+        self
+    }
+
+    /// Limits the count of blocks in the stream
+    pub fn max_blocks(mut self, max_blocks: u64) -> Self {
+        self.max_blocks = Some(max_blocks);
+        self
+    }
+
+    /// Returns the limit of the total count of decompressed bytes
+    pub fn get_max_output(&self) -> Option<u64> {
+        self.max_output
+    }
+
+    /// Returns the limit of the expansion ratio
+    pub fn get_max_ratio(&self) -> Option<u64> {
+        self.max_ratio
+    }
+
+    /// Returns the limit of the count of blocks
+    pub fn get_max_blocks(&self) -> Option<u64> {
+        self.max_blocks
+    }
+
+    /// Returns the count of bytes allowed to be decompressed from `input_bytes`,
+    /// and the error kind when it's exceeded.
+    pub(crate) fn allowed_output(
+        &self,
+        input_bytes: u64,
+    ) -> Option<(u64, crate::InflateErrorKind)> {
+        use crate::InflateErrorKind;
+        let by_output = self
+            .max_output
+            .map(|max| (max, InflateErrorKind::OutputLimitExceeded));
+        let by_ratio = self.max_ratio.map(|ratio| {
+            (
+                ratio
+                    .saturating_mul(input_bytes)
+                    .saturating_add(Self::RATIO_ALLOWANCE),
+                InflateErrorKind::RatioLimitExceeded,
+            )
+        });
+        match (by_output, by_ratio) {
+            (Some(a), Some(b)) => Some(if a.0 <= b.0 { a } else { b }),
+            (a, b) => a.or(b),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::block_info::BlockInfo;
+use crate::input_buffer::InputBuffer;
+use crate::output_window::WindowStorage;
+use crate::token::{Token, TokenSink};
+use crate::InflaterManaged;
+
+/// <summary>
+/// Receives the events while decoding. The methods are called from the decoding loop
+/// so implementations for the normal decoding must be no-op to be optimized out.
+/// </summary>
+pub(crate) trait Observer {
+    /// <summary>
+    /// If false, the decoder doesn't track the positions of tokens and never calls token.
+    /// </summary>
+    const TOKENS: bool = false;
+
+    fn end_of_block<W: WindowStorage>(
+        &mut self,
+        inflater: &InflaterManaged<W>,
+        input: &InputBuffer<'_>,
+    );
+
+    #[inline(always)]
+    fn token(&mut self, _token: Token, _bits: u32) {}
+}
+
+/// <summary>
+/// The observer used for normal decoding
+/// </summary>
+pub(crate) struct NoObserver;
+
+impl Observer for NoObserver {
+    #[inline(always)]
+    fn end_of_block<W: WindowStorage>(&mut self, _: &InflaterManaged<W>, _: &InputBuffer<'_>) {}
+}
+
+/// <summary>
+/// The observer which reports BlockInfo to the callback
+/// </summary>
+pub(crate) struct BlockObserver<F>(pub(crate) F);
+
+impl<F: FnMut(&BlockInfo)> Observer for BlockObserver<F> {
+    fn end_of_block<W: WindowStorage>(
+        &mut self,
+        inflater: &InflaterManaged<W>,
+        input: &InputBuffer<'_>,
+    ) {
+        (self.0)(&inflater.block_info(input))
+    }
+}
+
+/// <summary>
+/// The observer which reports tokens to the TokenSink
+/// </summary>
+pub(crate) struct TokenObserver<'a, S: ?Sized>(pub(crate) &'a mut S);
+
+impl<S: TokenSink + ?Sized> Observer for TokenObserver<'_, S> {
+    const TOKENS: bool = true;
+
+    #[inline(always)]
+    fn end_of_block<W: WindowStorage>(&mut self, _: &InflaterManaged<W>, _: &InputBuffer<'_>) {}
+
+    #[inline(always)]
+    fn token(&mut self, token: Token, bits: u32) {
+        self.0.token(token, bits)
+    }
+}
@@ -0,0 +1,78 @@
+use crate::buffer::Buffer;
+use alloc::vec::Vec;
+use core::cmp::min;
+
+/// <summary>
+/// This class maintains the compressed bytes which are not yet returned to the caller.
+/// Bits are packed from the least significant bit of each byte, as deflate requires.
+/// </summary>
+#[derive(Debug)]
+pub(crate) struct OutputBuffer {
+    buffer: Vec<u8>,
+    read_pos: usize,
+    bit_buffer: u64,
+    bits_in_buffer: u32,
+}
+
+impl OutputBuffer {
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            read_pos: 0,
+            bit_buffer: 0,
+            bits_in_buffer: 0,
+        }
+    }
+
+    /// <summary>Write `count` low bits of `bits` to the output.</summary>
+    #[inline(always)]
+    pub fn write_bits(&mut self, count: u32, bits: u32) {
+        debug_assert!(count <= 32, "count is invalid.");
+        debug_assert!(count == 32 || bits >> count == 0, "bits out of range");
+        self.bit_buffer |= (bits as u64) << self.bits_in_buffer;
+        self.bits_in_buffer += count;
+        if self.bits_in_buffer >= 32 {
+            self.buffer
+                .extend_from_slice(&(self.bit_buffer as u32).to_le_bytes());
+            self.bit_buffer >>= 32;
+            self.bits_in_buffer -= 32;
+        }
+    }
+
+    /// <summary>Pad the pending bits with zeros up to the next byte boundary.</summary>
+    pub fn flush_bits(&mut self) {
+        while self.bits_in_buffer > 0 {
+            self.buffer.push(self.bit_buffer as u8);
+            self.bit_buffer >>= 8;
+            self.bits_in_buffer = self.bits_in_buffer.saturating_sub(8);
+        }
+        self.bit_buffer = 0;
+    }
+
+    /// <summary>Write bytes to the output. The output must be at a byte boundary.</summary>
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        debug_assert_eq!(self.bits_in_buffer, 0, "not at byte boundary");
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// <summary>The count of bytes which are complete but not copied to the caller.</summary>
+    pub fn pending_bytes(&self) -> usize {
+        self.buffer.len() - self.read_pos
+    }
+
+    /// <summary>Copy the complete bytes to output buffer.</summary>
+    pub fn copy_to(&mut self, mut output: Buffer<'_>) -> usize {
+        let length = min(output.len(), self.pending_bytes());
+        output
+            .reborrow()
+            .index_mut(..length)
+            .copy_from_slice(&self.buffer[self.read_pos..][..length]);
+        self.read_pos += length;
+        if self.read_pos == self.buffer.len() {
+            // everything is drained, so we can reuse the allocation from the start
+            self.buffer.clear();
+            self.read_pos = 0;
+        }
+        length
+    }
+}
@@ -1,14 +1,65 @@
 use crate::{buffer::Buffer, input_buffer::InputBuffer};
-use std::cmp::min;
+use core::cmp::min;
 
 // With Deflate64 we can have up to a 65536 length as well as up to a 65538 distance. We need a power-of-two
 // window size that goes back at least 65538 bytes, and we can only write into it when there are at least
 // 65536 "free" bytes available for the maximum possible write length. However, it is OK if the free bytes
 // overlap the history window; we process length-distance match copies in the forward direction. It is fine
 // to wrap around and overwrite bytes that we have already copied forward.
-pub(crate) const WINDOW_SIZE: usize = 131072;
+/// The size of the window of [`InflaterManaged`](crate::InflaterManaged)
+pub const WINDOW_SIZE: usize = 131072;
 const WINDOW_MASK: usize = 131071;
 
+/// The storage of the window of [`InflaterManaged`](crate::InflaterManaged)
+///
+/// This is implemented for `[u8; WINDOW_SIZE]` stored in the inflater,
+/// `&mut [u8; WINDOW_SIZE]`, and `Box<[u8; WINDOW_SIZE]>` so that the window memory can be
+/// borrowed from a pool, an arena, or static memory.
+/// The initial content of the window is never output.
+pub trait WindowStorage {
+    /// Returns the window
+    fn window(&self) -> &[u8; WINDOW_SIZE];
+    /// Returns the mutable window
+    fn window_mut(&mut self) -> &mut [u8; WINDOW_SIZE];
+}
+
+impl WindowStorage for [u8; WINDOW_SIZE] {
+    #[inline(always)]
+    fn window(&self) -> &[u8; WINDOW_SIZE] {
+        self
+    }
+
+    #[inline(always)]
+    fn window_mut(&mut self) -> &mut [u8; WINDOW_SIZE] {
+        self
+    }
+}
+
+impl<W: WindowStorage + ?Sized> WindowStorage for &mut W {
+    #[inline(always)]
+    fn window(&self) -> &[u8; WINDOW_SIZE] {
+        (**self).window()
+    }
+
+    #[inline(always)]
+    fn window_mut(&mut self) -> &mut [u8; WINDOW_SIZE] {
+        (**self).window_mut()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<W: WindowStorage + ?Sized> WindowStorage for alloc::boxed::Box<W> {
+    #[inline(always)]
+    fn window(&self) -> &[u8; WINDOW_SIZE] {
+        (**self).window()
+    }
+
+    #[inline(always)]
+    fn window_mut(&mut self) -> &mut [u8; WINDOW_SIZE] {
+        (**self).window_mut()
+    }
+}
+
 /// <summary>
 /// This class maintains a window for decompressed output.
 /// We need to keep this because the decompressed information can be
@@ -17,21 +68,44 @@ const WINDOW_MASK: usize = 131071;
 /// We use a byte array of WINDOW_SIZE circularly.
 /// </summary>
 #[derive(Debug)]
-pub(crate) struct OutputWindow {
-    window: [u8; WINDOW_SIZE],
+pub(crate) struct OutputWindow<W> {
+    window: W,
     end: usize,
     bytes_used: usize,
 }
 
-impl OutputWindow {
-    pub fn new() -> Self {
+impl<W: WindowStorage> OutputWindow<W> {
+    pub fn new(window: W) -> Self {
         Self {
-            window: [0; WINDOW_SIZE],
+            window,
             end: 0,
             bytes_used: 0,
         }
     }
 
+    pub fn into_window(self) -> W {
+        self.window
+    }
+
+    pub fn reset(&mut self) {
+        self.end = 0;
+        self.bytes_used = 0;
+    }
+
+    /// Zeroes the window so that no data of the previous stream is left in it
+    pub(crate) fn clear(&mut self) {
+        self.window.window_mut().fill(0);
+    }
+
+    /// <summary>
+    /// The count of bytes written to the window since the start, if it is less than the window size.
+    /// The whole window is valid history once `end` wraps around.
+    /// </summary>
+    #[inline(always)]
+    pub(crate) fn end(&self) -> usize {
+        self.end
+    }
+
     pub(crate) fn clear_bytes_used(&mut self) {
         self.bytes_used = 0;
     }
@@ -43,34 +117,58 @@ impl OutputWindow {
             self.bytes_used < WINDOW_SIZE,
             "Can't add byte when window is full!"
         );
-        self.window[self.end] = b;
-        self.end += 1;
-        self.end &= WINDOW_MASK;
+        let end = self.end;
+        self.window.window_mut()[end] = b;
+        self.end = (end + 1) & WINDOW_MASK;
         self.bytes_used += 1;
     }
 
+    /// <summary>
+    /// Add `count` bytes, one or two, from the low bytes of `bytes` to output window.
+    /// Two bytes are always stored so that this has no branch. The second byte is overwritten
+    /// later if `count` is 1.
+    /// </summary>
+    #[inline(always)]
+    pub fn write_literals(&mut self, bytes: u16, count: usize) {
+        debug_assert!(
+            (1..=2).contains(&count) && self.bytes_used + 2 <= WINDOW_SIZE,
+            "Can't add bytes when window is full!"
+        );
+        let end = self.end;
+        let window = self.window.window_mut();
+        window[end] = bytes as u8;
+        window[(end + 1) & WINDOW_MASK] = (bytes >> 8) as u8;
+        self.end = (end + count) & WINDOW_MASK;
+        self.bytes_used += count;
+    }
+
     #[inline(always)]
     pub fn write_length_distance(&mut self, length: usize, distance: usize) {
         debug_assert!((self.bytes_used + length) <= WINDOW_SIZE, "No Enough space");
 
         // move backwards distance bytes in the output stream,
         // and copy length bytes from this position to the output stream.
-
-        // This function *could* have lots of special-case optimizations for long
-        // non-overlapping copies, repeated bytes / patterns for long fills with
-        // short distances, separate paths for wrapping/non-wrapping writes, etc.
-        // but simpler ends up faster due to inlining and avoiding misprediction.
         self.bytes_used += length;
         let mut from = self.end.wrapping_sub(distance) & WINDOW_MASK;
         let mut to = self.end;
+        self.end = (to + length) & WINDOW_MASK;
 
-        for _ in 0..length {
-            self.window[to] = self.window[from];
-            to = (to + 1) & WINDOW_MASK;
-            from = (from + 1) & WINDOW_MASK;
+        let window = self.window.window_mut();
+        if length <= distance && from + length <= WINDOW_SIZE && to + length <= WINDOW_SIZE {
+            // the common case: non-overlapping match without wrap around
+            window.copy_within(from..from + length, to);
+            return;
         }
 
-        self.end = to;
+        // The match is split into contiguous segments at the end of the window.
+        let mut remaining = length;
+        while remaining > 0 {
+            let segment = min(remaining, min(WINDOW_SIZE - from, WINDOW_SIZE - to));
+            copy_match(window, from, to, segment);
+            from = (from + segment) & WINDOW_MASK;
+            to = (to + segment) & WINDOW_MASK;
+            remaining -= segment;
+        }
     }
 
     /// <summary>
@@ -88,14 +186,14 @@ impl OutputWindow {
         let tail_len = WINDOW_SIZE - self.end;
         if length > tail_len {
             // copy the first part
-            copied = input.copy_to(&mut self.window[self.end..][..tail_len]);
+            copied = input.copy_to(&mut self.window.window_mut()[self.end..][..tail_len]);
             if copied == tail_len {
                 // only try to copy the second part if we have enough bytes in input
-                copied += input.copy_to(&mut self.window[..length - tail_len]);
+                copied += input.copy_to(&mut self.window.window_mut()[..length - tail_len]);
             }
         } else {
             // only one copy is needed if there is no wrap around.
-            copied = input.copy_to(&mut self.window[self.end..][..length]);
+            copied = input.copy_to(&mut self.window.window_mut()[self.end..][..length]);
         }
 
         self.end = (self.end + copied) & WINDOW_MASK;
@@ -103,6 +201,36 @@ impl OutputWindow {
         copied
     }
 
+    /// <summary>Removes last `count` bytes written to the window and not yet copied out.</summary>
+    pub(crate) fn discard_last(&mut self, count: usize) {
+        debug_assert!(count <= self.bytes_used);
+        self.end = self.end.wrapping_sub(count) & WINDOW_MASK;
+        self.bytes_used -= count;
+    }
+
+    /// <summary>Returns last `count` bytes written to the window.</summary>
+    pub(crate) fn last_bytes(&self, count: usize) -> impl Iterator<Item = u8> + '_ {
+        let start = self.end.wrapping_sub(count);
+        let window = self.window.window();
+        (0..count).map(move |i| window[start.wrapping_add(i) & WINDOW_MASK])
+    }
+
+    /// <summary>
+    /// Put the preset dictionary into the history of the window.
+    /// The dictionary is not returned as decompressed output.
+    /// </summary>
+    pub(crate) fn preset_dictionary(&mut self, dictionary: &[u8]) {
+        debug_assert_eq!(
+            self.bytes_used, 0,
+            "dictionary must be set before decompression"
+        );
+        let window = self.window.window_mut();
+        for &b in dictionary {
+            window[self.end] = b;
+            self.end = (self.end + 1) & WINDOW_MASK;
+        }
+    }
+
     /// <summary>Free space in output window.</summary>
     pub fn free_bytes(&self) -> usize {
         WINDOW_SIZE - self.bytes_used
@@ -141,36 +269,65 @@ impl OutputWindow {
             output
                 .reborrow()
                 .index_mut(..tail_len)
-                .copy_from_slice(&self.window[WINDOW_SIZE - tail_len..][..tail_len]);
+                .copy_from_slice(&self.window.window()[WINDOW_SIZE - tail_len..][..tail_len]);
             output.index_mut(tail_len..).index_mut(..copy_end)
         } else {
             output
         };
-        output.copy_from_slice(&self.window[copy_end - output.len()..][..output.len()]);
+        output.copy_from_slice(&self.window.window()[copy_end - output.len()..][..output.len()]);
         self.bytes_used -= copied;
         //debug_assert!(self.bytes_used >= 0, "check this function and find why we copied more bytes than we have");
         copied
     }
 
     #[cfg(feature = "checkpoint")]
-    pub(crate) fn get_checkpoint_data(&self, total_output_written: u64) -> (&[u8], &[u8]) {
+    pub(crate) fn get_checkpoint_data(&self, history_len: u64) -> (&[u8], &[u8]) {
         use crate::inflater_managed::TABLE_LOOKUP_DISTANCE_MAX;
-        let history_needed = min(TABLE_LOOKUP_DISTANCE_MAX, total_output_written as usize);
+        let history_needed = min(TABLE_LOOKUP_DISTANCE_MAX as u64, history_len) as usize;
         let data_len = history_needed.max(self.bytes_used);
         let start = (self.end + WINDOW_SIZE - data_len) & WINDOW_MASK;
         if data_len <= WINDOW_SIZE - start {
             // one contiguous range
-            (&self.window[start..start + data_len], &[])
+            (&self.window.window()[start..start + data_len], &[])
         } else {
             // wrap around, two ranges
-            (&self.window[start..], &self.window[..self.end])
+            (
+                &self.window.window()[start..],
+                &self.window.window()[..self.end],
+            )
         }
     }
 
     #[cfg(feature = "checkpoint")]
     pub(crate) fn restore_from_checkpoint(&mut self, data: &[u8], bytes_used: usize) {
-        self.window[..data.len()].copy_from_slice(data);
+        self.window.window_mut()[..data.len()].copy_from_slice(data);
         self.end = data.len();
         self.bytes_used = bytes_used;
     }
 }
+
+/// <summary>
+/// Copies `length` bytes from `from` to `to` forward as if they were copied one byte at a time.
+/// This is used for the window without wrap around, and for the output slice.
+/// </summary>
+#[inline]
+pub(crate) fn copy_match(buffer: &mut [u8], from: usize, to: usize, length: usize) {
+    if from > to || from + length <= to {
+        // If the source is after the destination, the bytes are read before overwritten.
+        buffer.copy_within(from..from + length, to);
+    } else if to - from == 1 {
+        // a run of one byte
+        let b = buffer[from];
+        buffer[to..to + length].fill(b);
+    } else {
+        // The source overlaps with the destination, so the pattern of the distance bytes
+        // is repeated. The copied bytes are doubled each time since they are also the
+        // repetitions of the pattern.
+        let mut copied = 0;
+        while copied < length {
+            let count = min(to - from + copied, length - copied);
+            buffer.copy_within(from..from + count, to + copied);
+            copied += count;
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use crate::error::InflateError;
+use crate::inflater_managed::{CODE_ORDER, TABLE_LOOKUP_DISTANCE_MAX};
+use crate::{BlockType, InflaterManaged};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Decompresses corrupted deflate64 stream as much as possible
+///
+/// When an error is found, the stream is scanned bit by bit from the start of the broken block
+/// for a stored block with valid LEN/NLEN or a dynamic block with complete Huffman codes,
+/// and decoding resumes there. The bytes referred by the matches before the resumed position
+/// are unknown, so they are replaced with the placeholder byte.
+///
+/// ```
+/// # use deflate64::InflateRecovery;
+/// # let compressed = [0x01, 0x05, 0x00, 0xfa, 0xff, b'h', b'e', b'l', b'l', b'o'];
+/// let recovered = InflateRecovery::new().placeholder(b'?').recover(&compressed);
+/// for range in recovered.skipped_ranges() {
+///     println!("lost data at bit {}: {}", range.start_bit_offset(), range.error());
+/// }
+/// let data = recovered.into_output();
+/// # assert_eq!(data, b"hello");
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct InflateRecovery {
+    deflate64: bool,
+    placeholder: u8,
+}
+
+impl InflateRecovery {
+    /// Creates InflateRecovery for deflate64 stream with placeholder `0`
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            deflate64: true,
+            placeholder: 0,
+        }
+    }
+
+    /// Creates InflateRecovery for plain deflate (RFC 1951) stream with placeholder `0`
+    pub fn new_deflate() -> Self {
+        Self {
+            deflate64: false,
+            placeholder: 0,
+        }
+    }
+
+    /// Sets the byte to output for unknown bytes referred after resuming
+    pub fn placeholder(mut self, placeholder: u8) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    /// Decompresses `input`, skipping the broken parts
+    pub fn recover(&self, input: &[u8]) -> RecoveredStream {
+        let mut inflater = Box::new(self.new_inflater());
+        let mut trial = Box::new(self.new_inflater());
+        let placeholder = vec![self.placeholder; TABLE_LOOKUP_DISTANCE_MAX];
+        let mut scratch = vec![0u8; CHUNK_SIZE];
+        let mut output = Vec::new();
+        let mut skipped_ranges = Vec::new();
+        let mut start = 0;
+
+        loop {
+            let (error, block_start) =
+                match decode_from(&mut inflater, input, start, &placeholder, &mut output) {
+                    Outcome::Finished => break,
+                    Outcome::Truncated => {
+                        return RecoveredStream {
+                            output,
+                            skipped_ranges,
+                            complete: false,
+                        }
+                    }
+                    Outcome::Error { error, block_start } => (error, block_start),
+                };
+
+            // scan from the start of the broken block, since the error may be found
+            // after reading the corrupted data as the next block
+            let resume_bit_offset = (block_start + 1..input.len() as u64 * 8).find(|&bit| {
+                is_plausible_header(input, bit, self.deflate64)
+                    && is_resumable(&mut trial, input, bit, &placeholder, &mut scratch)
+            });
+            skipped_ranges.push(SkippedRange {
+                error,
+                start_bit_offset: block_start,
+                resume_bit_offset,
+                output_offset: output.len() as u64,
+            });
+            match resume_bit_offset {
+                Some(bit) => start = bit,
+                None => {
+                    return RecoveredStream {
+                        output,
+                        skipped_ranges,
+                        complete: false,
+                    }
+                }
+            }
+        }
+
+        RecoveredStream {
+            output,
+            skipped_ranges,
+            complete: true,
+        }
+    }
+
+    fn new_inflater(&self) -> InflaterManaged {
+        if self.deflate64 {
+            InflaterManaged::new()
+        } else {
+            InflaterManaged::new_deflate()
+        }
+    }
+}
+
+/// The result of [`InflateRecovery::recover`]
+#[derive(Debug, Clone)]
+pub struct RecoveredStream {
+    output: Vec<u8>,
+    skipped_ranges: Vec<SkippedRange>,
+    complete: bool,
+}
+
+impl RecoveredStream {
+    /// Returns the decompressed data including the placeholder bytes
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Returns the decompressed data including the placeholder bytes
+    pub fn into_output(self) -> Vec<u8> {
+        self.output
+    }
+
+    /// Returns the broken parts of the stream skipped
+    pub fn skipped_ranges(&self) -> &[SkippedRange] {
+        &self.skipped_ranges
+    }
+
+    /// Returns true if the end of the stream is decoded
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+}
+
+/// The broken part of the stream skipped by [`InflateRecovery`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SkippedRange {
+    error: InflateError,
+    start_bit_offset: u64,
+    resume_bit_offset: Option<u64>,
+    output_offset: u64,
+}
+
+impl SkippedRange {
+    /// Returns the error which caused the skip. The offsets are from the start of the input and the output
+    pub fn error(&self) -> InflateError {
+        self.error
+    }
+
+    /// Returns the offset in bits where the broken block starts
+    ///
+    /// The output decoded from the broken block before the error is kept,
+    /// but it may have garbage decoded from the corrupted data.
+    pub fn start_bit_offset(&self) -> u64 {
+        self.start_bit_offset
+    }
+
+    /// Returns the offset in bits where decoding resumed, or `None` if no block is found after the error
+    pub fn resume_bit_offset(&self) -> Option<u64> {
+        self.resume_bit_offset
+    }
+
+    /// Returns the offset in the output where the data decoded after resuming starts
+    pub fn output_offset(&self) -> u64 {
+        self.output_offset
+    }
+}
+
+enum Outcome {
+    Finished,
+    Truncated,
+    Error {
+        error: InflateError,
+        block_start: u64,
+    },
+}
+
+/// Starts the inflater at `bit` of the input. The history before the stream start is the placeholder.
+fn start_at(inflater: &mut InflaterManaged, input: &[u8], bit: u64, placeholder: &[u8]) -> bool {
+    inflater.reset();
+    if bit != 0 {
+        inflater.set_dictionary(placeholder);
+    }
+    match input.get((bit / 8) as usize) {
+        Some(&byte) => {
+            inflater.start_in_byte(byte, (bit % 8) as u32);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Decodes from `bit` until the end of the stream, the end of the input, or an error
+fn decode_from(
+    inflater: &mut InflaterManaged,
+    input: &[u8],
+    bit: u64,
+    placeholder: &[u8],
+    output: &mut Vec<u8>,
+) -> Outcome {
+    if !start_at(inflater, input, bit, placeholder) {
+        return Outcome::Truncated;
+    }
+    let base_bit = bit & !7;
+    let base_output = output.len() as u64;
+    let mut rest = &input[(bit / 8) as usize + 1..];
+
+    loop {
+        let len = output.len();
+        output.resize(len + CHUNK_SIZE, 0);
+        let result = inflater.inflate(rest, &mut output[len..]);
+        output.truncate(len + result.bytes_written);
+        rest = &rest[result.bytes_consumed..];
+
+        if let Some(error) = result.error {
+            return Outcome::Error {
+                error: InflateError::new(
+                    error.kind(),
+                    base_bit + error.input_bit_offset(),
+                    base_output + error.output_byte_offset(),
+                ),
+                block_start: base_bit + inflater.block_start_bit_offset(),
+            };
+        }
+        if inflater.finished() {
+            return Outcome::Finished;
+        }
+        if result.bytes_consumed == 0 && result.bytes_written == 0 {
+            return Outcome::Truncated;
+        }
+    }
+}
+
+/// Returns true if the block at `bit` can be decoded, using `scratch` as the output.
+/// A stored or static block must be followed by another block since its header is easy to appear
+/// by chance, unless it ends the stream at the end of the input.
+fn is_resumable(
+    trial: &mut InflaterManaged,
+    input: &[u8],
+    bit: u64,
+    placeholder: &[u8],
+    scratch: &mut [u8],
+) -> bool {
+    if !start_at(trial, input, bit, placeholder) {
+        return false;
+    }
+    let mut rest = &input[(bit / 8) as usize + 1..];
+    let mut blocks = 0;
+    let mut first_dynamic = false;
+
+    loop {
+        let result = trial.inflate_with_events(rest, scratch, |info| {
+            if blocks == 0 {
+                first_dynamic = info.block_type() == BlockType::Dynamic;
+            }
+            blocks += 1;
+        });
+        rest = &rest[result.bytes_consumed..];
+
+        if result.error.is_some() {
+            return false;
+        }
+        let enough_blocks = (blocks >= 1 && first_dynamic) || blocks >= 2;
+        if trial.finished() {
+            // the input after the stream may be other data, such as the next zip entry
+            return enough_blocks || rest.is_empty();
+        }
+        if enough_blocks {
+            return true;
+        }
+        if result.bytes_consumed == 0 && result.bytes_written == 0 {
+            // the input ends in the block
+            return true;
+        }
+    }
+}
+
+/// Reads bits from the input at any bit offset
+struct BitReader<'a> {
+    input: &'a [u8],
+    position: u64,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: u32) -> Option<u32> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = *self.input.get((self.position / 8) as usize)?;
+            value |= (((byte >> (self.position % 8)) & 1) as u32) << i;
+            self.position += 1;
+        }
+        Some(value)
+    }
+}
+
+/// Returns true if a stored block with valid LEN/NLEN or a dynamic block with complete codes starts at `bit`
+fn is_plausible_header(input: &[u8], bit: u64, deflate64: bool) -> bool {
+    let mut reader = BitReader {
+        input,
+        position: bit,
+    };
+    match reader.bits(3).map(|x| x >> 1) {
+        Some(0) => {
+            reader.position = reader.position.next_multiple_of(8);
+            match (reader.bits(16), reader.bits(16)) {
+                (Some(len), Some(nlen)) => len == !nlen & 0xFFFF,
+                _ => false,
+            }
+        }
+        Some(2) => is_plausible_dynamic_header(&mut reader, deflate64).is_some(),
+        _ => false,
+    }
+}
+
+fn is_plausible_dynamic_header(reader: &mut BitReader<'_>, deflate64: bool) -> Option<()> {
+    let literal_length_code_count = reader.bits(5)? as usize + 257;
+    let distance_code_count = reader.bits(5)? as usize + 1;
+    let code_length_code_count = reader.bits(4)? as usize + 4;
+    if literal_length_code_count > 286 || (!deflate64 && distance_code_count > 30) {
+        return None;
+    }
+
+    let mut code_length_code_lengths = [0u8; 19];
+    for &index in &CODE_ORDER[..code_length_code_count] {
+        code_length_code_lengths[index as usize] = reader.bits(3)? as u8;
+    }
+    let code_length_code = CanonicalCode::new(&code_length_code_lengths)?;
+
+    let mut lengths = [0u8; 286 + 32];
+    let lengths = &mut lengths[..literal_length_code_count + distance_code_count];
+    let mut index = 0;
+    while index < lengths.len() {
+        let (value, repeat) = match code_length_code.decode(reader)? {
+            symbol @ 0..=15 => (symbol, 1),
+            16 => (
+                *lengths.get(index.checked_sub(1)?)?,
+                reader.bits(2)? as usize + 3,
+            ),
+            17 => (0, reader.bits(3)? as usize + 3),
+            _ => (0, reader.bits(7)? as usize + 11),
+        };
+        lengths.get_mut(index..index + repeat)?.fill(value);
+        index += repeat;
+    }
+
+    let (literal_length, distance) = lengths.split_at(literal_length_code_count);
+    let distance_codes = distance.iter().filter(|&&x| x != 0).count();
+    let complete = literal_length[256] != 0
+        && kraft_sum(literal_length) == 1 << 15
+        && (kraft_sum(distance) == 1 << 15 || distance_codes <= 1);
+    complete.then_some(())
+}
+
+/// Returns the sum of 2^(15 - length) for the code lengths, which is 2^15 for a complete code
+fn kraft_sum(lengths: &[u8]) -> u32 {
+    lengths
+        .iter()
+        .filter(|&&x| x != 0)
+        .map(|&x| 1 << (15 - x))
+        .sum()
+}
+
+/// The canonical Huffman code for the code length alphabet, decoded bit by bit
+struct CanonicalCode {
+    counts: [u16; 8],
+    symbols: [u8; 19],
+}
+
+impl CanonicalCode {
+    /// Returns None if the code is not complete
+    fn new(lengths: &[u8; 19]) -> Option<Self> {
+        if kraft_sum(lengths) != 1 << 15 {
+            return None;
+        }
+        let mut counts = [0u16; 8];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        let mut symbols = [0u8; 19];
+        let mut index = 0;
+        for length in 1..8 {
+            for (symbol, _) in lengths.iter().enumerate().filter(|(_, &x)| x == length) {
+                symbols[index] = symbol as u8;
+                index += 1;
+            }
+        }
+        Some(Self { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader<'_>) -> Option<u8> {
+        let mut code = 0u32;
+        let mut first = 0u32;
+        let mut index = 0u32;
+        for &count in &self.counts[1..] {
+            code |= reader.bits(1)?;
+            let count = count as u32;
+            if code - first < count {
+                return Some(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        None
+    }
+}
@@ -0,0 +1,433 @@
+use crate::checkpoint::MAX_CHECKPOINT_SIZE;
+use crate::inflater_managed::TABLE_LOOKUP_DISTANCE_MAX;
+use crate::{InflateLimits, InflaterManaged};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+
+const SEEK_BUFFER_SIZE: usize = 32 * 1024;
+
+const INDEX_MAGIC: [u8; 4] = *b"D64X";
+const INDEX_VERSION: u8 = 1;
+
+const INDEX_FLAG_DEFLATE64: u8 = 0x01;
+const INDEX_FLAG_STRICT: u8 = 0x02;
+const INDEX_FLAG_MAX_OUTPUT: u8 = 0x04;
+const INDEX_FLAG_MAX_RATIO: u8 = 0x08;
+const INDEX_FLAG_MAX_BLOCKS: u8 = 0x10;
+
+/// The access point in [`SeekIndex`]
+struct AccessPoint {
+    /// The offset in the decompressed data where decompression can be resumed.
+    output_offset: u64,
+    /// The checkpoint of the inflater at `output_offset`.
+    checkpoint: Vec<u8>,
+}
+
+/// The index of access points in a deflate64 stream for random access
+///
+/// The index is built by decompressing the whole stream once with [`SeekIndex::build`],
+/// and used by [`SeekableDeflate64Decoder`] to seek without decompressing from the start.
+/// Each access point holds a checkpoint of the inflater, which is about 64 KiB,
+/// so the span between access points is the trade-off between memory and seek speed.
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+pub struct SeekIndex {
+    points: Vec<AccessPoint>,
+    deflate64: bool,
+    limits: InflateLimits,
+    strict: bool,
+    dictionary: Vec<u8>,
+    compressed_size: u64,
+    uncompressed_size: u64,
+}
+
+impl SeekIndex {
+    /// Builds the index of deflate64 stream with access points every `span` bytes of output
+    ///
+    /// The whole stream is read from `inner`.
+    pub fn build<R: Read>(inner: R, span: u64) -> io::Result<Self> {
+        Self::build_with_inflater(inner, span, Box::new(InflaterManaged::new()))
+    }
+
+    /// Builds the index with configured inflater
+    ///
+    /// This can be used to build the index of plain deflate stream with [`InflaterManaged::new_deflate()`].
+    /// The format, the limits, strict mode, and the preset dictionary of `inflater` are kept
+    /// in the index and used by [`SeekableDeflate64Decoder`].
+    pub fn build_with_inflater<R: Read>(
+        inner: R,
+        span: u64,
+        mut inflater: Box<InflaterManaged>,
+    ) -> io::Result<Self> {
+        assert!(span > 0, "span must not be zero");
+
+        let dictionary = inflater.dictionary().collect();
+        let mut inner = BufReader::new(inner);
+        let mut buffer = vec![0u8; SEEK_BUFFER_SIZE];
+        let mut points = Vec::new();
+        let mut compressed_size = 0u64;
+        let mut uncompressed_size = 0u64;
+        let mut next_point = span;
+
+        while !inflater.finished() {
+            let input = inner.fill_buf()?;
+            let eof = input.is_empty();
+
+            let result = inflater.inflate(input, &mut buffer);
+
+            inner.consume(result.bytes_consumed);
+            compressed_size += result.bytes_consumed as u64;
+            uncompressed_size += result.bytes_written as u64;
+
+            if let Some(error) = result.error {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, error));
+            }
+
+            if uncompressed_size >= next_point {
+                if let Some((checkpoint, positions)) = inflater.checkpoint() {
+                    points.push(AccessPoint {
+                        output_offset: positions.output_bytes_already_returned,
+                        checkpoint,
+                    });
+                    next_point = positions.output_bytes_already_returned + span;
+                }
+            }
+
+            if result.bytes_written == 0 && eof && !inflater.finished() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "unexpected end of deflate64 stream",
+                ));
+            }
+        }
+
+        Ok(Self {
+            points,
+            deflate64: inflater.is_deflate64(),
+            limits: inflater.limits(),
+            strict: inflater.is_strict(),
+            dictionary,
+            compressed_size,
+            uncompressed_size,
+        })
+    }
+
+    /// Returns the count of access points
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns true if there are no access points
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Returns the size of the compressed stream
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+
+    /// Returns the size of the decompressed data
+    pub fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+
+    /// Returns the last access point at or before `offset`
+    fn point_before(&self, offset: u64) -> Option<&AccessPoint> {
+        let index = self.points.partition_point(|x| x.output_offset <= offset);
+        index.checked_sub(1).map(|i| &self.points[i])
+    }
+
+    /// Writes the index to `writer`, to be read with [`Self::read_from`]
+    ///
+    /// The format is specific to this crate and may change between versions.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut flags = 0;
+        if self.deflate64 {
+            flags |= INDEX_FLAG_DEFLATE64;
+        }
+        if self.strict {
+            flags |= INDEX_FLAG_STRICT;
+        }
+        let limits = [
+            (self.limits.get_max_output(), INDEX_FLAG_MAX_OUTPUT),
+            (self.limits.get_max_ratio(), INDEX_FLAG_MAX_RATIO),
+            (self.limits.get_max_blocks(), INDEX_FLAG_MAX_BLOCKS),
+        ];
+        for (limit, flag) in limits {
+            if limit.is_some() {
+                flags |= flag;
+            }
+        }
+
+        writer.write_all(&INDEX_MAGIC)?;
+        writer.write_all(&[INDEX_VERSION, flags])?;
+        for (limit, _) in limits {
+            writer.write_all(&limit.unwrap_or(0).to_le_bytes())?;
+        }
+        writer.write_all(&self.compressed_size.to_le_bytes())?;
+        writer.write_all(&self.uncompressed_size.to_le_bytes())?;
+        writer.write_all(&(self.dictionary.len() as u32).to_le_bytes())?;
+        writer.write_all(&self.dictionary)?;
+        writer.write_all(&(self.points.len() as u64).to_le_bytes())?;
+        for point in &self.points {
+            writer.write_all(&point.output_offset.to_le_bytes())?;
+            writer.write_all(&(point.checkpoint.len() as u32).to_le_bytes())?;
+            writer.write_all(&point.checkpoint)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the index written with [`Self::write_to`]
+    ///
+    /// This returns [`io::ErrorKind::InvalidData`] if the data is not a valid index.
+    /// The checkpoints in the index are verified when they are used.
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 6];
+        reader.read_exact(&mut header)?;
+        if header[..4] != INDEX_MAGIC || header[4] != INDEX_VERSION {
+            return Err(invalid_index());
+        }
+        let flags = header[5];
+
+        let mut limits = InflateLimits::new();
+        let max_output = read_u64(&mut reader)?;
+        if flags & INDEX_FLAG_MAX_OUTPUT != 0 {
+            limits = limits.max_output(max_output);
+        }
+        let max_ratio = read_u64(&mut reader)?;
+        if flags & INDEX_FLAG_MAX_RATIO != 0 {
+            limits = limits.max_ratio(max_ratio);
+        }
+        let max_blocks = read_u64(&mut reader)?;
+        if flags & INDEX_FLAG_MAX_BLOCKS != 0 {
+            limits = limits.max_blocks(max_blocks);
+        }
+        let compressed_size = read_u64(&mut reader)?;
+        let uncompressed_size = read_u64(&mut reader)?;
+        let dictionary = read_bytes(&mut reader, TABLE_LOOKUP_DISTANCE_MAX)?;
+
+        let count = read_u64(&mut reader)?;
+        let mut points = Vec::new();
+        for _ in 0..count {
+            let output_offset = read_u64(&mut reader)?;
+            let checkpoint = read_bytes(&mut reader, MAX_CHECKPOINT_SIZE)?;
+            if points
+                .last()
+                .is_some_and(|x: &AccessPoint| x.output_offset >= output_offset)
+                || output_offset > uncompressed_size
+            {
+                return Err(invalid_index());
+            }
+            points.push(AccessPoint {
+                output_offset,
+                checkpoint,
+            });
+        }
+
+        Ok(Self {
+            points,
+            deflate64: flags & INDEX_FLAG_DEFLATE64 != 0,
+            limits,
+            strict: flags & INDEX_FLAG_STRICT != 0,
+            dictionary,
+            compressed_size,
+            uncompressed_size,
+        })
+    }
+
+    fn new_inflater(&self) -> Box<InflaterManaged> {
+        let mut inflater = Box::new(if self.deflate64 {
+            InflaterManaged::new()
+        } else {
+            InflaterManaged::new_deflate()
+        });
+        inflater.set_limits(self.limits);
+        inflater.set_strict(self.strict);
+        if !self.dictionary.is_empty() {
+            inflater.set_dictionary(&self.dictionary);
+        }
+        inflater
+    }
+}
+
+fn invalid_index() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid seek index")
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Reads the bytes with the length prefix of up to `max_len`
+fn read_bytes<R: Read>(reader: &mut R, max_len: usize) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > max_len {
+        return Err(invalid_index());
+    }
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// The reader the decompresses deflate64 with random access using [`SeekIndex`].
+///
+/// Seeking restores the nearest access point before the target and
+/// decompresses from there.
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+pub struct SeekableDeflate64Decoder<R> {
+    inner: BufReader<R>,
+    index: SeekIndex,
+    inflater: Box<InflaterManaged>,
+    /// The position of the start of the stream in the inner reader
+    start: u64,
+    /// The offset in the decompressed data the inflater will output next
+    decoded: u64,
+    /// The offset in the decompressed data the reader will return next
+    position: u64,
+}
+
+impl<R: Read + Seek> SeekableDeflate64Decoder<R> {
+    /// Creates SeekableDeflate64Decoder with Read + Seek and the index of the stream
+    ///
+    /// `inner` must be positioned at the start of the stream the index is built from.
+    pub fn new(mut inner: R, index: SeekIndex) -> io::Result<Self> {
+        let start = inner.stream_position()?;
+        Ok(Self {
+            inner: BufReader::new(inner),
+            inflater: index.new_inflater(),
+            index,
+            start,
+            decoded: 0,
+            position: 0,
+        })
+    }
+
+    /// Moves the inflater to the nearest access point before `position` if it makes progress
+    fn prepare_seek(&mut self) -> io::Result<()> {
+        let point = self.index.point_before(self.position);
+        let point_offset = point.map_or(0, |x| x.output_offset);
+        if self.decoded <= self.position && point_offset <= self.decoded {
+            // decompressing from current position is not slower
+            return Ok(());
+        }
+
+        self.inflater = self.index.new_inflater();
+        match point {
+            Some(point) => {
+                let positions = self
+                    .inflater
+                    .restore_from_checkpoint(&point.checkpoint)
+                    .ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::InvalidData, "invalid access point")
+                    })?;
+                self.inner
+                    .seek(SeekFrom::Start(self.start + positions.input_bytes_to_skip))?;
+                self.decoded = positions.output_bytes_already_returned;
+            }
+            None => {
+                self.inner.seek(SeekFrom::Start(self.start))?;
+                self.decoded = 0;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_decoded(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let input = self.inner.fill_buf()?;
+            let eof = input.is_empty();
+
+            let result = self.inflater.inflate(input, buf);
+
+            self.inner.consume(result.bytes_consumed);
+            self.decoded += result.bytes_written as u64;
+
+            if let Some(error) = result.error {
+                // the output before the error is returned first, and the error is returned again
+                if result.bytes_written == 0 {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, error));
+                }
+            }
+
+            if result.bytes_written == 0 && !eof && !self.inflater.finished() {
+                // if we haven't ready any data and we haven't hit EOF yet,
+                // ask again. We must not return 0 in such case
+                continue;
+            }
+
+            return Ok(result.bytes_written);
+        }
+    }
+}
+
+impl<R> SeekableDeflate64Decoder<R> {
+    /// Returns the index used by this reader
+    pub fn index(&self) -> &SeekIndex {
+        &self.index
+    }
+
+    /// Returns inner Read + Seek instance
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+
+    /// Returns reference to innner Read + Seek instance
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+
+    /// Returns mutable reference to innner Read + Seek instance
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+}
+
+impl<R: Read + Seek> Read for SeekableDeflate64Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.position >= self.index.uncompressed_size {
+            return Ok(0);
+        }
+
+        self.prepare_seek()?;
+
+        // skip data before the position
+        let mut skip_buffer = [0u8; 4096];
+        while self.decoded < self.position {
+            let len = (self.position - self.decoded).min(skip_buffer.len() as u64) as usize;
+            if self.read_decoded(&mut skip_buffer[..len])? == 0 {
+                return Ok(0);
+            }
+        }
+
+        let read = self.read_decoded(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: Read + Seek> Seek for SeekableDeflate64Decoder<R> {
+    /// Seeks to the offset in the decompressed data.
+    ///
+    /// The actual seek is deferred until the next read.
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.index.uncompressed_size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.position)
+    }
+}
@@ -1,7 +1,7 @@
 // TODO: move this module to deflate64 crate
 
-use crate::InflaterManaged;
-use std::io::{self, BufRead, BufReader, Read};
+use crate::{DeflateFlush, DeflaterManaged, InflateError, InflateLimits, InflaterManaged};
+use std::io::{self, BufRead, BufReader, Read, Write};
 
 /// The reader the decompresses deflate64 from another BufRead.
 pub struct Deflate64Decoder<R> {
@@ -24,9 +24,58 @@ impl<R: BufRead> Deflate64Decoder<R> {
             inflater: Box::new(InflaterManaged::new()),
         }
     }
+
+    /// Creates Deflate64Decoder with BufRead and configured inflater
+    ///
+    /// This can be used to decode plain deflate stream with [`InflaterManaged::new_deflate()`].
+    pub fn with_inflater(inner: R, inflater: Box<InflaterManaged>) -> Self {
+        Self { inner, inflater }
+    }
 }
 
 impl<R> Deflate64Decoder<R> {
+    /// Sets the limits to stop decompression of hostile input
+    ///
+    /// See [`InflaterManaged::set_limits`].
+    pub fn set_limits(&mut self, limits: InflateLimits) {
+        self.inflater.set_limits(limits);
+    }
+
+    /// Enables or disables strict mode, which also reports data after the end of the stream
+    ///
+    /// In strict mode, the input ending before the end of the stream is reported as
+    /// [`io::ErrorKind::UnexpectedEof`] with [`InflateError`] of
+    /// [`UnexpectedEndOfInput`](crate::InflateErrorKind::UnexpectedEndOfInput).
+    /// See [`InflaterManaged::set_strict`].
+    pub fn set_strict(&mut self, strict: bool) {
+        self.inflater.set_strict(strict);
+    }
+
+    /// Returns the total count of input bytes consumed from the inner reader
+    pub fn total_in(&self) -> u64 {
+        self.inflater.total_in()
+    }
+
+    /// Returns the total count of input bits decoded
+    ///
+    /// See [`InflaterManaged::total_in_bits`].
+    pub fn total_in_bits(&self) -> u64 {
+        self.inflater.total_in_bits()
+    }
+
+    /// Returns the total count of decompressed bytes returned
+    pub fn total_out(&self) -> u64 {
+        self.inflater.total_out()
+    }
+
+    /// Resets this decoder to decompress a new stream from `inner`, returning the previous inner reader
+    ///
+    /// The inflater is reused with [`InflaterManaged::reset`].
+    pub fn reset(&mut self, inner: R) -> R {
+        self.inflater.reset();
+        std::mem::replace(&mut self.inner, inner)
+    }
+
     /// Returns inner BufRead instance
     pub fn into_inner(self) -> R {
         self.inner
@@ -45,33 +94,366 @@ impl<R> Deflate64Decoder<R> {
 
 impl<R: BufRead> Read for Deflate64Decoder<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        read_decompressed(&mut self.inner, &mut self.inflater, buf)
+    }
+}
+
+/// Decompresses the data from `inner` with `inflater` into `buf`, for [`Read::read`]
+/// of the decoders
+pub(crate) fn read_decompressed<R: BufRead>(
+    inner: &mut R,
+    inflater: &mut InflaterManaged,
+    buf: &mut [u8],
+) -> io::Result<usize> {
+    if buf.is_empty() {
+        // we received empty buffer, so it won't be possible to write anything
+        return Ok(0);
+    }
+
+    loop {
+        let input = inner.fill_buf()?;
+        let (consumed, read) = inflate_read(inflater, input, buf);
+        inner.consume(consumed);
+        if let Some(read) = read {
+            return read;
+        }
+    }
+}
+
+/// Decompresses the buffered `input` into `buf`, for the sync and async decoders
+///
+/// Returns the count of input bytes consumed, and the result of the read,
+/// or `None` if more input is needed.
+pub(crate) fn inflate_read(
+    inflater: &mut InflaterManaged,
+    input: &[u8],
+    buf: &mut [u8],
+) -> (usize, Option<io::Result<usize>>) {
+    let eof = input.is_empty();
+
+    let result = inflater.inflate(input, buf);
+    let consumed = result.bytes_consumed;
+
+    if let Some(error) = result.error {
+        // the output before the error is returned first, and the error is returned again
+        if result.bytes_written == 0 {
+            let error = io::Error::new(io::ErrorKind::InvalidInput, error);
+            return (consumed, Some(Err(error)));
+        }
+    }
+
+    if result.bytes_written == 0 && !inflater.finished() {
+        if !eof {
+            // if we haven't ready any data and we haven't hit EOF yet,
+            // ask again. We must not return 0 in such case
+            return (consumed, None);
+        }
+        if inflater.is_strict() {
+            // the stream is truncated
+            let error = io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                inflater.unexpected_end_of_input(),
+            );
+            return (consumed, Some(Err(error)));
+        }
+    }
+
+    (consumed, Some(Ok(result.bytes_written)))
+}
+
+const ENCODER_BUFFER_SIZE: usize = 32 * 1024;
+
+/// The writer that compresses deflate64 to another Write.
+///
+/// [`finish()`](Self::finish) must be called to write the end of the deflate64 stream.
+/// Dropping this writer without calling it results in truncated stream.
+pub struct Deflate64Encoder<W> {
+    inner: W,
+    deflater: DeflaterManaged,
+    buffer: Vec<u8>,
+    pending_start: usize,
+    pending_end: usize,
+}
+
+impl<W: Write> Deflate64Encoder<W> {
+    /// Creates Deflate64Encoder with default compression level
+    pub fn new(inner: W) -> Self {
+        Self::with_level(inner, DeflaterManaged::DEFAULT_LEVEL)
+    }
+
+    /// Creates Deflate64Encoder with compression level.
+    ///
+    /// See [`DeflaterManaged::with_level`] for the levels.
+    pub fn with_level(inner: W, level: u8) -> Self {
+        Self {
+            inner,
+            deflater: DeflaterManaged::with_level(level),
+            buffer: vec![0; ENCODER_BUFFER_SIZE],
+            pending_start: 0,
+            pending_end: 0,
+        }
+    }
+
+    /// Writes the end of the deflate64 stream and returns inner Write instance
+    pub fn finish(mut self) -> io::Result<W> {
+        loop {
+            self.dump()?;
+            if self.deflater.finished() {
+                break;
+            }
+            self.deflate(&[], DeflateFlush::Finish);
+        }
+        Ok(self.inner)
+    }
+
+    /// Writes compressed bytes not yet written to inner Write instance
+    fn dump(&mut self) -> io::Result<()> {
+        while self.pending_start < self.pending_end {
+            let written = self
+                .inner
+                .write(&self.buffer[self.pending_start..self.pending_end])?;
+            if written == 0 {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            self.pending_start += written;
+        }
+        Ok(())
+    }
+
+    fn deflate(&mut self, input: &[u8], flush: DeflateFlush) -> usize {
+        debug_assert_eq!(self.pending_start, self.pending_end);
+        let result = self.deflater.deflate(input, &mut self.buffer, flush);
+        self.pending_start = 0;
+        self.pending_end = result.bytes_written;
+        result.bytes_consumed
+    }
+}
+
+impl<W> Deflate64Encoder<W> {
+    /// Returns inner Write instance without finishing the deflate64 stream
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Returns reference to innner Write instance
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns mutable reference to innner Write instance
+    ///
+    /// Writing to the inner Write instance will corrupt the deflate64 stream.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write> Write for Deflate64Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         if buf.is_empty() {
-            // we received empty buffer, so it won't be possible to write anything
             return Ok(0);
         }
 
         loop {
-            let input = self.inner.fill_buf()?;
-            let eof = input.is_empty();
+            self.dump()?;
+            let consumed = self.deflate(buf, DeflateFlush::None);
+            if consumed != 0 {
+                return Ok(consumed);
+            }
+            // deflater had output to write and no input is consumed.
+            // write the output and try again since we must not return 0.
+        }
+    }
 
-            let result = self.inflater.inflate(input, buf);
+    /// Writes all data written so far to inner Write instance followed by a sync point
+    /// (an empty uncompressed block) and flushes inner Write instance.
+    fn flush(&mut self) -> io::Result<()> {
+        loop {
+            self.dump()?;
+            self.deflate(&[], DeflateFlush::Sync);
+            if self.pending_end == 0 {
+                break;
+            }
+        }
+        self.inner.flush()
+    }
+}
 
-            self.inner.consume(result.bytes_consumed);
+const DECODER_WRITER_BUFFER_SIZE: usize = 32 * 1024;
 
-            if result.data_error {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    "invalid deflate64",
-                ));
+/// The writer that decompresses deflate64 written to it and writes the result to another Write.
+///
+/// [`try_finish()`](Self::try_finish) or [`finish()`](Self::finish) should be called
+/// to check the deflate64 stream is not truncated.
+///
+/// The same as [`Deflate64Decoder`], invalid deflate64 data, including data written after
+/// the end of the stream, is reported as [`io::ErrorKind::InvalidInput`].
+/// The output decoded before the error is written to inner Write instance first, and
+/// the error is returned by the next call to `write`, `flush`, or `try_finish`.
+pub struct Deflate64DecoderWriter<W> {
+    inner: W,
+    inflater: Box<InflaterManaged>,
+    buffer: Vec<u8>,
+    pending_start: usize,
+    pending_end: usize,
+    error: Option<InflateError>,
+}
+
+impl<W: Write> Deflate64DecoderWriter<W> {
+    /// Creates Deflate64DecoderWriter with Write
+    pub fn new(inner: W) -> Self {
+        Self::with_inflater(inner, Box::new(InflaterManaged::new()))
+    }
+
+    /// Creates Deflate64DecoderWriter with Write and configured inflater
+    pub fn with_inflater(inner: W, inflater: Box<InflaterManaged>) -> Self {
+        Self {
+            inner,
+            inflater,
+            buffer: vec![0; DECODER_WRITER_BUFFER_SIZE],
+            pending_start: 0,
+            pending_end: 0,
+            error: None,
+        }
+    }
+
+    /// Writes all decompressed data to inner Write instance and
+    /// checks the end of the deflate64 stream is reached.
+    ///
+    /// This returns [`io::ErrorKind::UnexpectedEof`] with [`InflateError`]
+    /// of [`UnexpectedEndOfInput`](crate::InflateErrorKind::UnexpectedEndOfInput)
+    /// if the deflate64 stream is truncated.
+    pub fn try_finish(&mut self) -> io::Result<()> {
+        self.drain()?;
+        if !self.inflater.finished() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                self.inflater.unexpected_end_of_input(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Same as [`try_finish()`](Self::try_finish) but returns inner Write instance
+    pub fn finish(mut self) -> io::Result<W> {
+        self.try_finish()?;
+        Ok(self.inner)
+    }
+
+    /// Writes decompressed bytes not yet written to inner Write instance
+    fn dump(&mut self) -> io::Result<()> {
+        while self.pending_start < self.pending_end {
+            let written = self
+                .inner
+                .write(&self.buffer[self.pending_start..self.pending_end])?;
+            if written == 0 {
+                return Err(io::ErrorKind::WriteZero.into());
             }
+            self.pending_start += written;
+        }
+        Ok(())
+    }
 
-            if result.bytes_written == 0 && !eof && !self.inflater.finished() {
-                // if we haven't ready any data and we haven't hit EOF yet,
-                // ask again. We must not return 0 in such case
-                continue;
+    /// Writes all data decompressable from the input written so far to inner Write instance
+    fn drain(&mut self) -> io::Result<()> {
+        loop {
+            self.dump()?;
+            self.check_error()?;
+            self.inflate(&[]);
+            if self.pending_end == 0 {
+                return self.check_error();
             }
+        }
+    }
 
-            return Ok(result.bytes_written);
+    /// Returns the error of the deflate64 stream, which is returned after the output
+    /// decoded before it is written
+    fn check_error(&self) -> io::Result<()> {
+        match self.error {
+            Some(error) => Err(io::Error::new(io::ErrorKind::InvalidInput, error)),
+            None => Ok(()),
         }
     }
+
+    fn inflate(&mut self, input: &[u8]) -> usize {
+        debug_assert_eq!(self.pending_start, self.pending_end);
+        let result = self.inflater.inflate(input, &mut self.buffer);
+        self.pending_start = 0;
+        self.pending_end = result.bytes_written;
+        if result.error.is_some() {
+            self.error = result.error;
+        }
+        result.bytes_consumed
+    }
+}
+
+impl<W> Deflate64DecoderWriter<W> {
+    /// Sets the limits to stop decompression of hostile input
+    ///
+    /// See [`InflaterManaged::set_limits`].
+    pub fn set_limits(&mut self, limits: InflateLimits) {
+        self.inflater.set_limits(limits);
+    }
+
+    /// Enables or disables strict mode, which also reports data after the end of the stream
+    /// written in the same call as the end of the stream
+    ///
+    /// See [`InflaterManaged::set_strict`].
+    pub fn set_strict(&mut self, strict: bool) {
+        self.inflater.set_strict(strict);
+    }
+
+    /// Returns inner Write instance without checking the end of the deflate64 stream
+    ///
+    /// Decompressed data not yet written to inner Write instance will be lost.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Returns reference to innner Write instance
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns mutable reference to innner Write instance
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write> Write for Deflate64DecoderWriter<W> {
+    /// Decompresses `buf` and writes the result to inner Write instance.
+    ///
+    /// This returns [`io::ErrorKind::InvalidInput`] if `buf` is written after the end of
+    /// the deflate64 stream.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            self.dump()?;
+            self.check_error()?;
+            let consumed = self.inflate(buf);
+            if consumed != 0 {
+                return Ok(consumed);
+            }
+            if self.pending_end == 0 && self.error.is_none() && self.inflater.finished() {
+                // no more input will be consumed
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "trailing data after end of deflate64 stream",
+                ));
+            }
+            // inflater had output to write or failed, and no input is consumed.
+            // write the output and try again since we must not return 0.
+        }
+    }
+
+    /// Writes all data decompressable from the input written so far to inner Write instance
+    /// and flushes inner Write instance.
+    fn flush(&mut self) -> io::Result<()> {
+        self.drain()?;
+        self.inner.flush()
+    }
 }
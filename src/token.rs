@@ -0,0 +1,35 @@
+/// The LZ77 token in deflate64 stream
+///
+/// This is reported to [`TokenSink`] by [`InflaterManaged::inflate_with_tokens`](crate::InflaterManaged::inflate_with_tokens)
+/// and [`InflaterManaged::inflate_tokens`](crate::InflaterManaged::inflate_tokens).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Token {
+    /// The literal byte. Each byte in uncompressed blocks is also reported as literal.
+    Literal(u8),
+    /// The back-reference to the previous output
+    Match {
+        /// The count of bytes to copy
+        length: u32,
+        /// The distance from the current position to copy from
+        distance: u32,
+    },
+    /// The end of the block
+    EndOfBlock,
+}
+
+/// The receiver of [`Token`]s
+///
+/// This is implemented for closures with `FnMut(Token, u32)`.
+pub trait TokenSink {
+    /// Receives the token and the count of bits it takes in the input.
+    ///
+    /// The bits include extra bits of length and distance, but don't include block headers.
+    /// Literals in uncompressed blocks take 8 bits and their end of block takes 0 bits.
+    fn token(&mut self, token: Token, bits: u32);
+}
+
+impl<F: FnMut(Token, u32)> TokenSink for F {
+    fn token(&mut self, token: Token, bits: u32) {
+        self(token, bits)
+    }
+}
@@ -0,0 +1,80 @@
+//! Async decoder for `tokio::io::AsyncBufRead`
+
+use crate::stream::inflate_read;
+use crate::InflaterManaged;
+use ::tokio::io::{AsyncBufRead, AsyncRead, BufReader, ReadBuf};
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// The async reader the decompresses deflate64 from another [`AsyncBufRead`].
+///
+/// This is async version of [`Deflate64Decoder`](crate::Deflate64Decoder).
+pub struct AsyncDeflate64Decoder<R> {
+    inner: R,
+    inflater: Box<InflaterManaged>,
+}
+
+impl<R: AsyncRead> AsyncDeflate64Decoder<BufReader<R>> {
+    /// Creates AsyncDeflate64Decoder with AsyncRead
+    pub fn new(inner: R) -> Self {
+        Self::with_buffer(BufReader::new(inner))
+    }
+}
+
+impl<R: AsyncBufRead> AsyncDeflate64Decoder<R> {
+    /// Creates AsyncDeflate64Decoder with AsyncBufRead
+    pub fn with_buffer(inner: R) -> Self {
+        Self {
+            inner,
+            inflater: Box::new(InflaterManaged::new()),
+        }
+    }
+
+    /// Creates AsyncDeflate64Decoder with AsyncBufRead and configured inflater
+    pub fn with_inflater(inner: R, inflater: Box<InflaterManaged>) -> Self {
+        Self { inner, inflater }
+    }
+}
+
+impl<R> AsyncDeflate64Decoder<R> {
+    /// Returns inner AsyncBufRead instance
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns reference to innner AsyncBufRead instance
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns mutable reference to innner AsyncBufRead instance
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncRead for AsyncDeflate64Decoder<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        if buf.remaining() == 0 {
+            // we received empty buffer, so it won't be possible to write anything
+            return Poll::Ready(Ok(()));
+        }
+
+        loop {
+            let input = ready!(Pin::new(&mut this.inner).poll_fill_buf(cx))?;
+            let (consumed, read) =
+                inflate_read(&mut this.inflater, input, buf.initialize_unfilled());
+            Pin::new(&mut this.inner).consume(consumed);
+            if let Some(read) = read {
+                return Poll::Ready(read.map(|written| buf.advance(written)));
+            }
+        }
+    }
+}
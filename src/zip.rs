@@ -0,0 +1,449 @@
+//! Minimal zip archive reader to extract deflate64 entries.
+//!
+//! This module provides [`ZipArchive`], which reads the central directory of zip archive
+//! (including ZIP64 archives) and hands out readers for each entry.
+//! Stored, deflate and deflate64 entries are supported and CRC-32 of each entry is verified
+//! when the end of the entry is reached.
+//!
+//! Encrypted entries, multi-disk archives, and other compression methods are not supported.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use deflate64::zip::ZipArchive;
+//! use std::fs::File;
+//! use std::io::Read;
+//!
+//! let mut archive = ZipArchive::new(File::open("archive.zip")?)?;
+//! for index in 0..archive.len() {
+//!     let mut entry = archive.by_index(index)?;
+//!     let mut data = Vec::new();
+//!     entry.read_to_end(&mut data)?;
+//!     println!("{}: {} bytes", entry.entry().name(), data.len());
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use crate::checksum::Crc32;
+use crate::stream::read_decompressed;
+use crate::{InflateError, InflateErrorKind, InflaterManaged};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Take};
+
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06064b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+
+const LOCAL_FILE_HEADER_SIZE: usize = 30;
+const CENTRAL_DIRECTORY_HEADER_SIZE: usize = 46;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIZE: usize = 56;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIZE: usize = 20;
+const MAX_COMMENT_LENGTH: usize = 0xFFFF;
+
+const ZIP64_EXTRA_FIELD_ID: u16 = 0x0001;
+
+const FLAG_ENCRYPTED: u16 = 1 << 0;
+
+/// Compression method of zip entry
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CompressionMethod {
+    /// Method 0: the entry is not compressed
+    Stored,
+    /// Method 8: plain deflate
+    Deflate,
+    /// Method 9: deflate64
+    Deflate64,
+    /// Other methods, which are not supported by this module
+    Unsupported(u16),
+}
+
+impl CompressionMethod {
+    fn from_u16(method: u16) -> Self {
+        match method {
+            0 => Self::Stored,
+            8 => Self::Deflate,
+            9 => Self::Deflate64,
+            other => Self::Unsupported(other),
+        }
+    }
+}
+
+/// Information about zip entry read from the central directory
+#[derive(Debug, Clone)]
+pub struct ZipEntry {
+    name: String,
+    flags: u16,
+    compression_method: CompressionMethod,
+    crc32: u32,
+    compressed_size: u64,
+    size: u64,
+    local_header_offset: u64,
+}
+
+impl ZipEntry {
+    /// Returns name of the entry
+    ///
+    /// Names which are not marked as UTF-8 are also decoded as UTF-8, and invalid sequences
+    /// are replaced with U+FFFD.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns true if the entry is a directory
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    /// Returns true if the entry is encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.flags & FLAG_ENCRYPTED != 0
+    }
+
+    /// Returns compression method of the entry
+    pub fn compression_method(&self) -> CompressionMethod {
+        self.compression_method
+    }
+
+    /// Returns CRC-32 of the uncompressed data
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    /// Returns size of the compressed data
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+
+    /// Returns size of the uncompressed data
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// The zip archive reader
+pub struct ZipArchive<R> {
+    reader: R,
+    entries: Vec<ZipEntry>,
+    // reused for all compressed entries
+    inflater: Option<Box<InflaterManaged>>,
+}
+
+impl<R: Read + Seek> ZipArchive<R> {
+    /// Reads the central directory of zip archive
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let file_len = reader.seek(SeekFrom::End(0))?;
+
+        // the end of central directory record is at the end of file followed by comment
+        let tail_len = file_len.min(
+            (END_OF_CENTRAL_DIRECTORY_SIZE
+                + MAX_COMMENT_LENGTH
+                + ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIZE) as u64,
+        );
+        reader.seek(SeekFrom::Start(file_len - tail_len))?;
+        let mut tail = vec![0u8; tail_len as usize];
+        reader.read_exact(&mut tail)?;
+
+        let eocd_pos = (0..=tail.len().saturating_sub(END_OF_CENTRAL_DIRECTORY_SIZE))
+            .rev()
+            .find(|&pos| {
+                pos + END_OF_CENTRAL_DIRECTORY_SIZE <= tail.len()
+                    && u32_at(&tail, pos) == END_OF_CENTRAL_DIRECTORY_SIGNATURE
+            })
+            .ok_or_else(|| invalid("end of central directory not found"))?;
+        let eocd = &tail[eocd_pos..];
+
+        let mut entry_count = u16_at(eocd, 10) as u64;
+        let mut central_directory_size = u32_at(eocd, 12) as u64;
+        let mut central_directory_offset = u32_at(eocd, 16) as u64;
+
+        if eocd_pos >= ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIZE {
+            let locator = &tail[eocd_pos - ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIZE..];
+            if u32_at(locator, 0) == ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE {
+                let zip64_eocd_offset = u64_at(locator, 8);
+                reader.seek(SeekFrom::Start(zip64_eocd_offset))?;
+                let mut zip64_eocd = [0u8; ZIP64_END_OF_CENTRAL_DIRECTORY_SIZE];
+                reader.read_exact(&mut zip64_eocd)?;
+                if u32_at(&zip64_eocd, 0) != ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE {
+                    return Err(invalid("invalid zip64 end of central directory"));
+                }
+                entry_count = u64_at(&zip64_eocd, 32);
+                central_directory_size = u64_at(&zip64_eocd, 40);
+                central_directory_offset = u64_at(&zip64_eocd, 48);
+            }
+        }
+
+        match central_directory_offset.checked_add(central_directory_size) {
+            Some(end) if end <= file_len => {}
+            _ => return Err(invalid("central directory out of file")),
+        }
+
+        reader.seek(SeekFrom::Start(central_directory_offset))?;
+        let mut central_directory = vec![0u8; central_directory_size as usize];
+        reader.read_exact(&mut central_directory)?;
+
+        let mut entries = Vec::new();
+        let mut rest = &central_directory[..];
+        while !rest.is_empty() {
+            let (entry, len) = parse_central_directory_header(rest)?;
+            entries.push(entry);
+            rest = &rest[len..];
+        }
+
+        if entries.len() as u64 != entry_count {
+            return Err(invalid("entry count mismatch"));
+        }
+
+        Ok(Self {
+            reader,
+            entries,
+            inflater: None,
+        })
+    }
+
+    /// Returns reader for the entry at `index`
+    pub fn by_index(&mut self, index: usize) -> io::Result<ZipEntryReader<'_, R>> {
+        let entry = self
+            .entries
+            .get(index)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "zip entry not found"))?;
+
+        if entry.is_encrypted() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "encrypted zip entry is not supported",
+            ));
+        }
+
+        self.reader
+            .seek(SeekFrom::Start(entry.local_header_offset))?;
+        let mut header = [0u8; LOCAL_FILE_HEADER_SIZE];
+        self.reader.read_exact(&mut header)?;
+        if u32_at(&header, 0) != LOCAL_FILE_HEADER_SIGNATURE {
+            return Err(invalid("invalid local file header"));
+        }
+        // sizes in local file header may be zero if data descriptor is used
+        // so we use sizes in central directory instead.
+        let name_len = u16_at(&header, 26) as i64;
+        let extra_len = u16_at(&header, 28) as i64;
+        self.reader.seek(SeekFrom::Current(name_len + extra_len))?;
+
+        let data = (&mut self.reader).take(entry.compressed_size);
+        let size = usize::try_from(entry.size).unwrap_or(usize::MAX);
+        let data = match entry.compression_method {
+            CompressionMethod::Stored => {
+                if entry.compressed_size != entry.size {
+                    return Err(invalid("size mismatch for stored entry"));
+                }
+                EntryData::Stored(data)
+            }
+            CompressionMethod::Deflate | CompressionMethod::Deflate64 => {
+                let inflater = self
+                    .inflater
+                    .get_or_insert_with(|| Box::new(InflaterManaged::new()));
+                inflater.set_deflate64(entry.compression_method == CompressionMethod::Deflate64);
+                inflater.reset_with_uncompressed_size(size);
+                // checks the size and the end of the compressed data
+                inflater.set_strict(true);
+                EntryData::Compressed(BufReader::new(data), inflater)
+            }
+            CompressionMethod::Unsupported(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "unsupported compression method",
+                ));
+            }
+        };
+
+        Ok(ZipEntryReader {
+            entry,
+            data,
+            crc32: Crc32::new(),
+            bytes_read: 0,
+        })
+    }
+
+    /// Returns reader for the entry with `name`
+    pub fn by_name(&mut self, name: &str) -> io::Result<ZipEntryReader<'_, R>> {
+        let index = self
+            .index_of(name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "zip entry not found"))?;
+        self.by_index(index)
+    }
+}
+
+impl<R> ZipArchive<R> {
+    /// Returns count of entries in the archive
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the archive has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns entries in the central directory order
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    /// Returns index of the entry with `name`
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.name == name)
+    }
+
+    /// Returns inner Read instance
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+enum EntryData<'a, R> {
+    Stored(Take<&'a mut R>),
+    Compressed(BufReader<Take<&'a mut R>>, &'a mut InflaterManaged),
+}
+
+/// The reader for zip entry, which decompresses and verifies the data.
+///
+/// If the data doesn't match the size or CRC-32 in the central directory,
+/// error with [`io::ErrorKind::InvalidData`] is returned at the end of the entry.
+pub struct ZipEntryReader<'a, R> {
+    entry: &'a ZipEntry,
+    data: EntryData<'a, R>,
+    crc32: Crc32,
+    bytes_read: u64,
+}
+
+impl<R> ZipEntryReader<'_, R> {
+    /// Returns information about the entry
+    pub fn entry(&self) -> &ZipEntry {
+        self.entry
+    }
+}
+
+impl<R: Read> Read for ZipEntryReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let read = match &mut self.data {
+            EntryData::Stored(reader) => reader.read(buf)?,
+            EntryData::Compressed(reader, inflater) => {
+                read_decompressed(reader, inflater, buf).map_err(strict_error)?
+            }
+        };
+        self.crc32.update(&buf[..read]);
+        self.bytes_read += read as u64;
+
+        if self.bytes_read > self.entry.size {
+            return Err(invalid("zip entry is larger than expected"));
+        }
+        if read == 0 {
+            if self.bytes_read != self.entry.size {
+                return Err(invalid("zip entry is smaller than expected"));
+            }
+            if self.crc32.value() != self.entry.crc32 {
+                return Err(invalid("zip entry CRC-32 mismatch"));
+            }
+        }
+
+        Ok(read)
+    }
+}
+
+fn parse_central_directory_header(data: &[u8]) -> io::Result<(ZipEntry, usize)> {
+    if data.len() < CENTRAL_DIRECTORY_HEADER_SIZE || u32_at(data, 0) != CENTRAL_DIRECTORY_SIGNATURE
+    {
+        return Err(invalid("invalid central directory header"));
+    }
+
+    let flags = u16_at(data, 8);
+    let compression_method = CompressionMethod::from_u16(u16_at(data, 10));
+    let crc32 = u32_at(data, 16);
+    let mut compressed_size = u32_at(data, 20) as u64;
+    let mut size = u32_at(data, 24) as u64;
+    let name_len = u16_at(data, 28) as usize;
+    let extra_len = u16_at(data, 30) as usize;
+    let comment_len = u16_at(data, 32) as usize;
+    let mut local_header_offset = u32_at(data, 42) as u64;
+
+    let header_len = CENTRAL_DIRECTORY_HEADER_SIZE + name_len + extra_len + comment_len;
+    if data.len() < header_len {
+        return Err(invalid("invalid central directory header"));
+    }
+    let name = &data[CENTRAL_DIRECTORY_HEADER_SIZE..][..name_len];
+    let mut extra = &data[CENTRAL_DIRECTORY_HEADER_SIZE + name_len..][..extra_len];
+
+    // values in zip64 extra field are present only if the value in the header is 0xFFFFFFFF
+    while extra.len() >= 4 {
+        let id = u16_at(extra, 0);
+        let len = u16_at(extra, 2) as usize;
+        if extra.len() < 4 + len {
+            return Err(invalid("invalid extra field"));
+        }
+        let mut field = &extra[4..][..len];
+        if id == ZIP64_EXTRA_FIELD_ID {
+            for value in [&mut size, &mut compressed_size, &mut local_header_offset] {
+                if *value == 0xFFFFFFFF {
+                    if field.len() < 8 {
+                        return Err(invalid("invalid zip64 extra field"));
+                    }
+                    *value = u64_at(field, 0);
+                    field = &field[8..];
+                }
+            }
+        }
+        extra = &extra[4 + len..];
+    }
+
+    // code page 437 is not supported so decode as UTF-8 regardless of the UTF-8 flag
+    let name = String::from_utf8_lossy(name).into_owned();
+
+    Ok((
+        ZipEntry {
+            name,
+            flags,
+            compression_method,
+            crc32,
+            compressed_size,
+            size,
+            local_header_offset,
+        },
+        header_len,
+    ))
+}
+
+/// Reports the mismatch found by strict inflater as the other mismatches
+fn strict_error(error: io::Error) -> io::Error {
+    let kind = error
+        .get_ref()
+        .and_then(|x| x.downcast_ref::<InflateError>())
+        .map(|x| x.kind());
+    match kind {
+        Some(InflateErrorKind::OutputTooLong) => invalid("zip entry is larger than expected"),
+        Some(InflateErrorKind::OutputTooShort) => invalid("zip entry is smaller than expected"),
+        Some(InflateErrorKind::TrailingData) => {
+            invalid("zip entry has data after the end of the compressed stream")
+        }
+        _ => error,
+    }
+}
+
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..][..4].try_into().unwrap())
+}
+
+fn u64_at(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..][..8].try_into().unwrap())
+}
@@ -0,0 +1,245 @@
+use crate::checksum::Adler32;
+use crate::InflaterManaged;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read};
+
+// Compression method in CMF. Deflate64 streams also use method 8 with CINFO = 8.
+const ZLIB_METHOD_DEFLATE: u8 = 8;
+// CINFO for 64 KiB window, which means deflate64
+const ZLIB_CINFO_DEFLATE64: u8 = 8;
+const ZLIB_FLAG_DICTIONARY: u8 = 0x20;
+
+/// The error in zlib header or trailer
+///
+/// This is returned as the inner error of [`io::Error`] with [`io::ErrorKind::InvalidData`].
+/// Errors in the compressed data are returned as [`InflateError`](crate::InflateError).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ZlibError {
+    /// The compression method in the header is not 8.
+    UnknownCompressionMethod(u8),
+    /// CINFO in the header is larger than 8.
+    InvalidWindowSize(u8),
+    /// The header doesn't pass FCHECK.
+    InvalidHeaderCheck,
+    /// The stream uses the preset dictionary but no dictionary is given with
+    /// [`ZlibDeflate64Decoder::with_dictionary`].
+    DictionaryRequired(u32),
+    /// The stream uses the preset dictionary which is different from the dictionary set.
+    DictionaryMismatch {
+        /// The Adler-32 of the dictionary in the header.
+        expected: u32,
+        /// The Adler-32 of the dictionary set.
+        actual: u32,
+    },
+    /// The Adler-32 of the decompressed data doesn't match the trailer.
+    ChecksumMismatch {
+        /// The Adler-32 in the trailer.
+        expected: u32,
+        /// The Adler-32 of the decompressed data.
+        actual: u32,
+    },
+}
+
+impl fmt::Display for ZlibError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownCompressionMethod(method) => {
+                write!(f, "unknown zlib compression method: {method}")
+            }
+            Self::InvalidWindowSize(cinfo) => write!(f, "invalid zlib window size: {cinfo}"),
+            Self::InvalidHeaderCheck => f.write_str("invalid zlib header check"),
+            Self::DictionaryRequired(id) => {
+                write!(f, "zlib stream requires dictionary {id:08x}")
+            }
+            Self::DictionaryMismatch { expected, actual } => write!(
+                f,
+                "zlib dictionary mismatch: expected {expected:08x}, actual {actual:08x}"
+            ),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "zlib checksum mismatch: expected {expected:08x}, actual {actual:08x}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ZlibError {}
+
+impl From<ZlibError> for io::Error {
+    fn from(value: ZlibError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, value)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum ZlibState {
+    Header,
+    Body,
+    Trailer,
+    Done,
+}
+
+/// The reader the decompresses zlib (RFC 1950) wrapped deflate64 from another BufRead.
+///
+/// Streams with CINFO = 8 (64 KiB window) are decoded as deflate64, and
+/// streams with smaller window are decoded as plain deflate.
+/// The inner BufRead is read exactly to the end of the zlib stream.
+pub struct ZlibDeflate64Decoder<R> {
+    inner: R,
+    inflater: Box<InflaterManaged>,
+    dictionary: Option<Vec<u8>>,
+    adler32: Adler32,
+    state: ZlibState,
+}
+
+impl<R: Read> ZlibDeflate64Decoder<BufReader<R>> {
+    /// Creates ZlibDeflate64Decoder with Read
+    pub fn new(inner: R) -> Self {
+        Self::with_buffer(BufReader::new(inner))
+    }
+}
+
+impl<R: BufRead> ZlibDeflate64Decoder<R> {
+    /// Creates ZlibDeflate64Decoder with BufRead
+    pub fn with_buffer(inner: R) -> Self {
+        Self {
+            inner,
+            inflater: Box::new(InflaterManaged::new()),
+            dictionary: None,
+            adler32: Adler32::new(),
+            state: ZlibState::Header,
+        }
+    }
+
+    /// Creates ZlibDeflate64Decoder with BufRead and the preset dictionary used if the stream
+    /// requires one
+    pub fn with_dictionary(inner: R, dictionary: &[u8]) -> Self {
+        Self {
+            dictionary: Some(dictionary.to_vec()),
+            ..Self::with_buffer(inner)
+        }
+    }
+
+    fn read_header(&mut self) -> io::Result<()> {
+        let mut header = [0u8; 2];
+        self.inner.read_exact(&mut header)?;
+        let [cmf, flg] = header;
+
+        if cmf & 0x0F != ZLIB_METHOD_DEFLATE {
+            return Err(ZlibError::UnknownCompressionMethod(cmf & 0x0F).into());
+        }
+        let cinfo = cmf >> 4;
+        if cinfo > ZLIB_CINFO_DEFLATE64 {
+            return Err(ZlibError::InvalidWindowSize(cinfo).into());
+        }
+        if u16::from_be_bytes(header) % 31 != 0 {
+            return Err(ZlibError::InvalidHeaderCheck.into());
+        }
+
+        // The inflater is reconfigured in place to avoid the window on the stack
+        self.inflater.set_deflate64(cinfo == ZLIB_CINFO_DEFLATE64);
+        self.inflater.reset();
+
+        if flg & ZLIB_FLAG_DICTIONARY != 0 {
+            let mut dictionary_id = [0u8; 4];
+            self.inner.read_exact(&mut dictionary_id)?;
+            let expected = u32::from_be_bytes(dictionary_id);
+            let dictionary = self
+                .dictionary
+                .as_deref()
+                .ok_or(ZlibError::DictionaryRequired(expected))?;
+            let mut adler32 = Adler32::new();
+            adler32.update(dictionary);
+            let actual = adler32.value();
+            if actual != expected {
+                return Err(ZlibError::DictionaryMismatch { expected, actual }.into());
+            }
+            self.inflater.set_dictionary(dictionary);
+        }
+
+        Ok(())
+    }
+
+    fn read_trailer(&mut self) -> io::Result<()> {
+        let mut trailer = [0u8; 4];
+        self.inner.read_exact(&mut trailer)?;
+        let expected = u32::from_be_bytes(trailer);
+        let actual = self.adler32.value();
+        if actual != expected {
+            return Err(ZlibError::ChecksumMismatch { expected, actual }.into());
+        }
+        Ok(())
+    }
+}
+
+impl<R> ZlibDeflate64Decoder<R> {
+    /// Returns inner BufRead instance
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns reference to innner BufRead instance
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns mutable reference to innner BufRead instance
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+
+impl<R: BufRead> Read for ZlibDeflate64Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            // we received empty buffer, so it won't be possible to write anything
+            return Ok(0);
+        }
+
+        loop {
+            match self.state {
+                ZlibState::Header => {
+                    self.read_header()?;
+                    self.state = ZlibState::Body;
+                }
+                ZlibState::Body => {
+                    let input = self.inner.fill_buf()?;
+                    let eof = input.is_empty();
+
+                    let result = self.inflater.inflate(input, buf);
+
+                    self.inner.consume(result.bytes_consumed);
+
+                    if let Some(error) = result.error {
+                        // the output before the error is returned first,
+                        // and the error is returned again
+                        if result.bytes_written == 0 {
+                            return Err(io::Error::new(io::ErrorKind::InvalidInput, error));
+                        }
+                    }
+
+                    self.adler32.update(&buf[..result.bytes_written]);
+
+                    if self.inflater.finished() && !self.inflater.errored() {
+                        self.state = ZlibState::Trailer;
+                    } else if result.bytes_written == 0 && eof {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "unexpected end of zlib stream",
+                        ));
+                    }
+
+                    if result.bytes_written != 0 {
+                        return Ok(result.bytes_written);
+                    }
+                }
+                ZlibState::Trailer => {
+                    self.read_trailer()?;
+                    self.state = ZlibState::Done;
+                }
+                ZlibState::Done => return Ok(0),
+            }
+        }
+    }
+}
@@ -1,7 +1,10 @@
 //! This test compresses some random data with deflate64 using p7zip `7z` command and check decompression
+//! and check p7zip can decompress data compressed with `DeflaterManaged`.
+
+#![cfg(feature = "std")]
 
 use bytemuck::{Pod, Zeroable};
-use deflate64::Deflate64Decoder;
+use deflate64::{Deflate64Decoder, DeflateFlush, DeflaterManaged};
 use proptest::proptest;
 use std::ffi::OsString;
 use std::fs::File;
@@ -96,6 +99,102 @@ fn compress_with_7zip(data: &[u8]) -> Vec<u8> {
     compressed_buffer
 }
 
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn create_zip_with_deflater(data: &[u8]) -> Vec<u8> {
+    let mut deflater = DeflaterManaged::new();
+    let mut compressed = vec![0u8; data.len() * 2 + 1024];
+    let result = deflater.deflate(data, &mut compressed, DeflateFlush::Finish);
+    assert!(deflater.finished());
+    compressed.truncate(result.bytes_written);
+
+    let crc = crc32(data);
+    let mut zip = Vec::new();
+
+    // local file header
+    zip.extend_from_slice(&ZipLocalFileHeader::SIGNATURE);
+    zip.extend_from_slice(&21u16.to_le_bytes()); // version needed to extract
+    zip.extend_from_slice(&0u16.to_le_bytes()); // flags
+    zip.extend_from_slice(&9u16.to_le_bytes()); // compression method: deflate64
+    zip.extend_from_slice(&[0; 4]); // last mod time / date
+    zip.extend_from_slice(&crc.to_le_bytes());
+    zip.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+    zip.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    zip.extend_from_slice(&(TEST_FILE_NAME.len() as u16).to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+    zip.extend_from_slice(TEST_FILE_NAME.as_bytes());
+    zip.extend_from_slice(&compressed);
+
+    // central directory
+    let central_directory_offset = zip.len();
+    zip.extend_from_slice(&[0x50, 0x4B, 0x01, 0x02]);
+    zip.extend_from_slice(&21u16.to_le_bytes()); // version made by
+    zip.extend_from_slice(&21u16.to_le_bytes()); // version needed to extract
+    zip.extend_from_slice(&0u16.to_le_bytes()); // flags
+    zip.extend_from_slice(&9u16.to_le_bytes()); // compression method: deflate64
+    zip.extend_from_slice(&[0; 4]); // last mod time / date
+    zip.extend_from_slice(&crc.to_le_bytes());
+    zip.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+    zip.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    zip.extend_from_slice(&(TEST_FILE_NAME.len() as u16).to_le_bytes());
+    zip.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+    zip.extend_from_slice(&0u32.to_le_bytes()); // local header offset
+    zip.extend_from_slice(TEST_FILE_NAME.as_bytes());
+    let central_directory_size = zip.len() - central_directory_offset;
+
+    // end of central directory
+    zip.extend_from_slice(&[0x50, 0x4B, 0x05, 0x06]);
+    zip.extend_from_slice(&[0; 4]); // disk numbers
+    zip.extend_from_slice(&1u16.to_le_bytes());
+    zip.extend_from_slice(&1u16.to_le_bytes());
+    zip.extend_from_slice(&(central_directory_size as u32).to_le_bytes());
+    zip.extend_from_slice(&(central_directory_offset as u32).to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes()); // comment length
+
+    zip
+}
+
+fn decompress_with_7zip(zip: &[u8]) -> Vec<u8> {
+    let temp_dir = TempDir::new().unwrap();
+
+    File::create(temp_dir.path().join(TEST_ZIP_NAME))
+        .unwrap()
+        .write_all(zip)
+        .unwrap();
+
+    let seven_zip = std::env::var_os("SEVEN_ZIP_PATH").unwrap_or_else(|| OsString::from("7z"));
+
+    let seven_zip_process = Command::new(seven_zip)
+        .arg("e")
+        .arg("-so")
+        .arg(TEST_ZIP_NAME)
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+
+    if !seven_zip_process.status.success() {
+        panic!(
+            "7zip failure.\nstderr:\n{stderr}",
+            stderr = String::from_utf8(seven_zip_process.stderr).unwrap(),
+        );
+    }
+
+    seven_zip_process.stdout
+}
+
 proptest! {
     #[test]
     #[ignore = "requires `p7zip` command line tool"]
@@ -111,3 +210,16 @@ proptest! {
         assert_eq!(&uncompressed_data[..], source_data);
     }
 }
+
+proptest! {
+    #[test]
+    #[ignore = "requires `p7zip` command line tool"]
+    fn decompress_with_7zip_compressed_by_deflater(source_data in "\\PC{1000,}") {
+        let source_data = source_data.as_bytes();
+        let zip = create_zip_with_deflater(source_data);
+
+        let uncompressed_data = decompress_with_7zip(&zip);
+
+        assert_eq!(&uncompressed_data[..], source_data);
+    }
+}
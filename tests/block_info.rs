@@ -0,0 +1,122 @@
+use deflate64::{BlockInfo, BlockType, InflaterManaged};
+use std::io::Write;
+
+const BINARY_WAV_DATA_OFFSET: usize = 40;
+const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
+
+static ZIP_FILE_DATA: &[u8] = include_bytes!("../test-assets/deflate64.zip");
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+fn collect_blocks(
+    mut inflater: InflaterManaged,
+    compressed: &[u8],
+    chunk: usize,
+) -> Vec<BlockInfo> {
+    let mut blocks = vec![];
+    let mut output = vec![0u8; 100_000];
+    let mut input = compressed;
+    let mut total_written = 0;
+    while !inflater.finished() {
+        let result =
+            inflater.inflate_with_events(&input[..input.len().min(chunk)], &mut output, |info| {
+                blocks.push(info.clone())
+            });
+        assert!(!result.data_error());
+        input = &input[result.bytes_consumed..];
+        total_written += result.bytes_written as u64;
+    }
+
+    // blocks cover the whole stream
+    assert_eq!(blocks.first().unwrap().start_bit_offset(), 0);
+    for pair in blocks.windows(2) {
+        assert_eq!(pair[0].end_bit_offset(), pair[1].start_bit_offset());
+        assert!(!pair[0].is_final());
+    }
+    let last = blocks.last().unwrap();
+    assert!(last.is_final());
+    assert_eq!(
+        last.end_bit_offset().div_ceil(8),
+        (compressed.len() - input.len()) as u64
+    );
+    let uncompressed_size: u64 = blocks.iter().map(|x| x.uncompressed_size()).sum();
+    assert_eq!(uncompressed_size, total_written);
+
+    blocks
+}
+
+fn assert_dynamic_header(info: &BlockInfo) {
+    assert_eq!(info.block_type(), BlockType::Dynamic);
+    assert!((257..=288).contains(&info.literal_length_code_count()));
+    assert!((1..=32).contains(&info.distance_code_count()));
+    assert!((4..=19).contains(&info.code_length_code_count()));
+    assert_eq!(info.code_length_code_lengths().len(), 19);
+    assert_eq!(
+        info.literal_length_code_lengths().len(),
+        info.literal_length_code_count()
+    );
+    assert_eq!(
+        info.distance_code_lengths().len(),
+        info.distance_code_count()
+    );
+    // end of block code
+    assert_ne!(info.literal_length_code_lengths()[256], 0);
+}
+
+#[test]
+fn deflate64_blocks() {
+    let compressed = &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE];
+    let blocks = collect_blocks(InflaterManaged::new(), compressed, 10000);
+
+    let uncompressed_size: u64 = blocks.iter().map(|x| x.uncompressed_size()).sum();
+    assert_eq!(uncompressed_size, BINARY_WAV_DATA.len() as u64);
+    for info in &blocks {
+        if info.block_type() == BlockType::Dynamic {
+            assert_dynamic_header(info);
+        }
+    }
+}
+
+fn flate2_compress(data: &[u8], level: u32) -> Vec<u8> {
+    let mut encoder =
+        flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::new(level));
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn stored_blocks() {
+    let data = &BINARY_WAV_DATA[..200_000];
+    let compressed = flate2_compress(data, 0);
+    let blocks = collect_blocks(InflaterManaged::new_deflate(), &compressed, 1);
+
+    assert!(blocks.len() > 1);
+    for info in &blocks[..blocks.len() - 1] {
+        assert_eq!(info.block_type(), BlockType::Uncompressed);
+        assert_eq!(info.literal_length_code_count(), 0);
+        assert!(info.code_length_code_lengths().is_empty());
+        assert!(info.literal_length_code_lengths().is_empty());
+        // header is padded to byte boundary
+        assert_eq!(
+            info.compressed_bits().div_ceil(8),
+            info.uncompressed_size() + 5
+        );
+    }
+}
+
+#[test]
+fn static_and_dynamic_blocks() {
+    let compressed = flate2_compress(b"a", 9);
+    let blocks = collect_blocks(InflaterManaged::new_deflate(), &compressed, 1);
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].block_type(), BlockType::Static);
+    assert_eq!(blocks[0].uncompressed_size(), 1);
+    assert_eq!(blocks[0].distance_code_count(), 0);
+
+    let data = &BINARY_WAV_DATA[..500_000];
+    let compressed = flate2_compress(data, 9);
+    let blocks = collect_blocks(InflaterManaged::new_deflate(), &compressed, 4096);
+    assert!(blocks.len() > 1);
+    for info in &blocks {
+        assert_dynamic_header(info);
+    }
+}
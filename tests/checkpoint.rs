@@ -1,4 +1,4 @@
-#![cfg(feature = "checkpoint")]
+#![cfg(all(feature = "checkpoint", feature = "alloc"))]
 
 use deflate64::{checkpoint::CheckpointStreamPositions, InflaterManaged};
 
@@ -48,7 +48,7 @@ fn inflate_with_checkpoints(output_interval: usize) -> Vec<(Vec<u8>, CheckpointS
         let result = inflater.inflate(&compressed[consumed..], &mut output[written..out_end]);
         consumed += result.bytes_consumed;
         written += result.bytes_written;
-        assert!(!result.data_error);
+        assert!(!result.data_error());
 
         if written >= next_checkpoint_at {
             if let Some((cp_data, positions)) = inflater.checkpoint() {
@@ -78,7 +78,7 @@ fn resume_from_checkpoint(
         let r = inflater.inflate(&compressed[consumed..], &mut output[written..]);
         consumed += r.bytes_consumed;
         written += r.bytes_written;
-        assert!(!r.data_error);
+        assert!(!r.data_error());
     }
 
     output.truncate(written);
@@ -138,7 +138,7 @@ fn checkpoint_availability_lifecycle() {
     // Mid-stream
     let mut output = vec![0u8; 1024];
     let result = inflater.inflate(&compressed_data()[..1000], &mut output);
-    assert!(!inflater.finished() && !result.data_error);
+    assert!(!inflater.finished() && !result.data_error());
     assert!(inflater.checkpoint().is_some());
 
     // After finished with output drained
@@ -219,7 +219,7 @@ fn restore_and_reserialize() {
         if already_in_buffer + out_skip < BINARY_WAV_COMPRESSED_SIZE {
             let mut out = vec![0u8; already_in_buffer + 1000];
             let r = restored.inflate(&compressed_data()[skip..], &mut out);
-            assert!(!r.data_error && r.bytes_written > already_in_buffer);
+            assert!(!r.data_error() && r.bytes_written > already_in_buffer);
             assert_bytes_eq(
                 &out[..r.bytes_written],
                 &BINARY_WAV_DATA[out_skip..][..r.bytes_written],
@@ -255,6 +255,31 @@ fn restore_continue_restore() {
     );
 }
 
+#[test]
+fn checkpoint_after_inflate_to_slice() {
+    let compressed = compressed_data();
+    for (input_len, output_len) in [
+        (1000, BINARY_WAV_UNCOMPRESSED_SIZE),
+        (100000, BINARY_WAV_UNCOMPRESSED_SIZE),
+        (BINARY_WAV_COMPRESSED_SIZE, 50000),
+        (BINARY_WAV_COMPRESSED_SIZE, 500000),
+    ] {
+        let mut inflater = Box::new(InflaterManaged::new());
+        let mut output = vec![0u8; output_len];
+        inflater.inflate_to_slice(&compressed[..input_len], &mut output);
+
+        let (cp_data, positions) = inflater.checkpoint().unwrap();
+        let mut restored = Box::new(InflaterManaged::new());
+        restored.restore_from_checkpoint(&cp_data).unwrap();
+        let output = resume_from_checkpoint(&mut restored, compressed, &positions);
+        assert_bytes_eq(
+            &output,
+            &BINARY_WAV_DATA[positions.output_bytes_already_returned as usize..],
+            "output",
+        );
+    }
+}
+
 #[test]
 fn checkpoint_uncompressed_block() {
     let original: Vec<u8> = (0..1000).map(|i| (i % 256) as u8).collect();
@@ -344,3 +369,54 @@ fn all_window_bytes_restored_properly() {
         "output after real restore",
     );
 }
+
+#[test]
+fn checkpoint_with_dictionary() {
+    use deflate64::{DeflateFlush, DeflaterManaged};
+
+    let dictionary = &BINARY_WAV_DATA[..65536];
+    let data = &BINARY_WAV_DATA[1000..100000];
+    let mut deflater = DeflaterManaged::new();
+    deflater.set_dictionary(dictionary);
+    let mut compressed = vec![0u8; data.len() + 1024];
+    let result = deflater.deflate(data, &mut compressed, DeflateFlush::Finish);
+    compressed.truncate(result.bytes_written);
+
+    // make checkpoint soon after start so that the history has the dictionary
+    let mut inflater = Box::new(InflaterManaged::new());
+    inflater.set_dictionary(dictionary);
+    let mut output = vec![0u8; 1000];
+    let result = inflater.inflate(&compressed, &mut output);
+    assert!(!result.data_error());
+    let (checkpoint, positions) = inflater.checkpoint().unwrap();
+    let already_returned = positions.output_bytes_already_returned as usize;
+
+    // the restored inflater doesn't need the dictionary
+    let mut restored = Box::new(InflaterManaged::new());
+    restored
+        .restore_from_checkpoint(&checkpoint)
+        .expect("restore failed");
+    let resumed = resume_from_checkpoint(&mut restored, &compressed, &positions);
+    assert_bytes_eq(&resumed, &data[already_returned..], "resumed data");
+}
+
+#[test]
+fn checkpoint_into_buffer() {
+    use deflate64::checkpoint::MAX_CHECKPOINT_SIZE;
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut buffer = vec![0u8; MAX_CHECKPOINT_SIZE];
+    assert!(inflater.checkpoint_into(&mut buffer).is_none());
+
+    let mut output = vec![0u8; 100000];
+    let result = inflater.inflate(&compressed_data()[..50000], &mut output);
+    assert!(!result.data_error());
+
+    let (checkpoint, positions) = inflater.checkpoint().unwrap();
+    let (len, positions_into) = inflater.checkpoint_into(&mut buffer).unwrap();
+    assert_eq!(positions_into, positions);
+    assert_bytes_eq(&buffer[..len], &checkpoint, "checkpoint_into");
+
+    // too short buffer
+    assert!(inflater.checkpoint_into(&mut buffer[..len - 1]).is_none());
+}
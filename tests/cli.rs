@@ -0,0 +1,252 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+use common::corrupt_after;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+const BINARY_WAV_DATA_OFFSET: usize = 40;
+const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
+
+static ZIP_FILE_DATA: &[u8] = include_bytes!("../test-assets/deflate64.zip");
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+static FIRST_TXT_DATA: &[u8] = include_bytes!("../test-assets/folder/first.txt");
+
+fn source_stream() -> &'static [u8] {
+    &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE]
+}
+
+fn deflate64(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_deflate64"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut child_stdin = child.stdin.take().unwrap();
+    let stdin = stdin.to_vec();
+    let writer = std::thread::spawn(move || {
+        // the command may exit before reading all input
+        let _ = child_stdin.write_all(&stdin);
+    });
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    output
+}
+
+fn path_str(path: &Path) -> &str {
+    path.to_str().unwrap()
+}
+
+#[test]
+fn inflate_stdin_with_offset() {
+    let output = deflate64(&["inflate", "--offset", "40"], ZIP_FILE_DATA);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, BINARY_WAV_DATA);
+}
+
+#[test]
+fn inflate_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.deflate64");
+    let output_file = dir.path().join("output");
+    fs::write(&input, source_stream()).unwrap();
+
+    let output = deflate64(
+        &["inflate", path_str(&input), "-o", path_str(&output_file)],
+        &[],
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(fs::read(&output_file).unwrap(), BINARY_WAV_DATA);
+}
+
+#[test]
+fn deflate_round_trip() {
+    let data = &BINARY_WAV_DATA[..200_000];
+    let compressed = deflate64(&["deflate", "--level", "9"], data);
+    assert_eq!(compressed.status.code(), Some(0));
+    let output = deflate64(&["inflate"], &compressed.stdout);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, data);
+}
+
+#[test]
+fn exit_codes() {
+    // truncated
+    let output = deflate64(&["inflate"], &source_stream()[..10000]);
+    assert_eq!(output.status.code(), Some(4));
+
+    // corrupt: reserved block type
+    let output = deflate64(&["inflate"], &[0xFF; 16]);
+    assert_eq!(output.status.code(), Some(3));
+
+    // the output before the error is written
+    let output = deflate64(&["inflate"], &corrupt_after(&BINARY_WAV_DATA[..1000]));
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(output.stdout, &BINARY_WAV_DATA[..1000]);
+
+    // usage
+    let output = deflate64(&["inflate", "--unknown"], &[]);
+    assert_eq!(output.status.code(), Some(2));
+    let output = deflate64(&["unknown"], &[]);
+    assert_eq!(output.status.code(), Some(2));
+
+    // file not found
+    let output = deflate64(&["inflate", "/nonexistent/file"], &[]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn unzip() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive = dir.path().join("deflate64.zip");
+    fs::write(&archive, ZIP_FILE_DATA).unwrap();
+    let extract = dir.path().join("extract");
+
+    let output = deflate64(&["unzip", "-l", path_str(&archive)], &[]);
+    assert_eq!(output.status.code(), Some(0));
+    let listing = String::from_utf8(output.stdout).unwrap();
+    assert!(listing.contains("deflate64"));
+    assert!(listing.contains("binary.wmv"));
+
+    let output = deflate64(
+        &[
+            "unzip",
+            "-d",
+            path_str(&extract),
+            path_str(&archive),
+            "binary.wmv",
+        ],
+        &[],
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        fs::read(extract.join("binary.wmv")).unwrap(),
+        BINARY_WAV_DATA
+    );
+}
+
+#[test]
+fn unzip_exit_codes() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive = dir.path().join("deflate64.zip");
+    let extract = dir.path().join("extract");
+    let unzip = |zip: &[u8], name: &str| {
+        fs::write(&archive, zip).unwrap();
+        deflate64(
+            &["unzip", "-d", path_str(&extract), path_str(&archive), name],
+            &[],
+        )
+    };
+
+    // CRC-32 mismatch
+    let mut zip = ZIP_FILE_DATA.to_vec();
+    let position = zip
+        .windows(FIRST_TXT_DATA.len())
+        .position(|window| window == FIRST_TXT_DATA)
+        .unwrap();
+    zip[position] ^= 1;
+    let output = unzip(&zip, "first.txt");
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("CRC-32"));
+
+    // unsafe name is not corrupt data
+    let mut zip = ZIP_FILE_DATA.to_vec();
+    while let Some(position) = zip.windows(9).position(|window| window == b"first.txt") {
+        zip[position..][..9].copy_from_slice(b"../st.txt");
+    }
+    let output = unzip(&zip, "../st.txt");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unsafe entry name"));
+}
+
+#[test]
+fn inspect() {
+    let output = deflate64(&["inspect"], source_stream());
+    assert_eq!(output.status.code(), Some(0));
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.contains("dynamic"));
+    assert!(report.contains(&format!("{}", BINARY_WAV_DATA.len())));
+
+    let output = deflate64(&["inspect"], &source_stream()[..10000]);
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn checkpoint_and_resume() {
+    let dir = tempfile::tempdir().unwrap();
+    let truncated = dir.path().join("truncated");
+    let input = dir.path().join("input");
+    let output_file = dir.path().join("output");
+    let checkpoint = dir.path().join("checkpoint");
+    fs::write(
+        &truncated,
+        &source_stream()[..BINARY_WAV_COMPRESSED_SIZE / 2],
+    )
+    .unwrap();
+    fs::write(&input, source_stream()).unwrap();
+
+    // the input is interrupted in the middle
+    let output = deflate64(
+        &[
+            "checkpoint",
+            "--interval",
+            "100000",
+            "--checkpoint",
+            path_str(&checkpoint),
+            path_str(&truncated),
+            "-o",
+            path_str(&output_file),
+        ],
+        &[],
+    );
+    assert_eq!(output.status.code(), Some(4));
+    assert!(checkpoint.exists());
+
+    // the output lost after the checkpoint is not corrupt input
+    let saved_output = fs::read(&output_file).unwrap();
+    fs::write(&output_file, b"").unwrap();
+    let output = deflate64(
+        &[
+            "resume",
+            "--checkpoint",
+            path_str(&checkpoint),
+            path_str(&input),
+            "-o",
+            path_str(&output_file),
+        ],
+        &[],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("shorter than the checkpoint"));
+    fs::write(&output_file, saved_output).unwrap();
+
+    // the checkpoint is rejected with different options
+    for options in [&["--deflate"][..], &["--offset", "1"]] {
+        let mut args = vec!["resume", "--checkpoint", path_str(&checkpoint)];
+        args.extend_from_slice(options);
+        args.extend_from_slice(&[path_str(&input), "-o", path_str(&output_file)]);
+        let output = deflate64(&args, &[]);
+        assert_eq!(output.status.code(), Some(2), "{options:?}");
+    }
+
+    let output = deflate64(
+        &[
+            "resume",
+            "--interval",
+            "100000",
+            "--checkpoint",
+            path_str(&checkpoint),
+            path_str(&input),
+            "-o",
+            path_str(&output_file),
+        ],
+        &[],
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(fs::read(&output_file).unwrap(), BINARY_WAV_DATA);
+}
@@ -0,0 +1,28 @@
+//! Helpers shared by the integration tests
+
+// each test crate uses some of the helpers
+#![allow(dead_code)]
+
+use deflate64::{DeflateFlush, DeflaterManaged};
+
+/// Compresses `data` to a deflate64 stream with the default level
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut deflater = Box::new(DeflaterManaged::new());
+    let mut compressed = vec![0u8; data.len() + 1024];
+    let result = deflater.deflate(data, &mut compressed, DeflateFlush::Finish);
+    assert!(deflater.finished());
+    compressed.truncate(result.bytes_written);
+    compressed
+}
+
+/// Returns the stream of a stored block with `data` followed by a block with the reserved
+/// block type, so the stream is corrupt after `data` is decoded
+pub fn corrupt_after(data: &[u8]) -> Vec<u8> {
+    let len = data.len() as u16;
+    let mut stream = vec![0x00];
+    stream.extend_from_slice(&len.to_le_bytes());
+    stream.extend_from_slice(&(!len).to_le_bytes());
+    stream.extend_from_slice(data);
+    stream.push(0x07);
+    stream
+}
@@ -0,0 +1,90 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use common::compress;
+use deflate64::{
+    decompress_to_vec, decompress_to_vec_with_limit, InflateErrorKind, InflaterManaged,
+};
+
+static ZIP_FILE_DATA: &[u8] = include_bytes!("../test-assets/deflate64.zip");
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+const BINARY_WAV_DATA_OFFSET: usize = 40;
+const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
+
+fn binary_wav_compressed() -> &'static [u8] {
+    &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE]
+}
+
+#[test]
+fn binary_wav() {
+    let (data, used) = decompress_to_vec(binary_wav_compressed()).unwrap();
+    assert_eq!(used, BINARY_WAV_COMPRESSED_SIZE);
+    assert!(data == BINARY_WAV_DATA);
+}
+
+#[test]
+fn size_hint() {
+    let compressed = compress(&vec![7u8; 1 << 20]);
+    for size_hint in [0, 1, 1000, (1 << 20) - 1, 1 << 20, 1 << 21] {
+        let (data, used) =
+            decompress_to_vec_with_limit(&compressed, Some(size_hint), usize::MAX).unwrap();
+        assert_eq!(used, compressed.len(), "size_hint: {size_hint}");
+        assert!(data == vec![7u8; 1 << 20], "size_hint: {size_hint}");
+    }
+}
+
+#[test]
+fn huge_size_hint() {
+    let compressed = compress(b"hello world");
+    let (data, used) =
+        decompress_to_vec_with_limit(&compressed, Some(usize::MAX), usize::MAX).unwrap();
+    assert_eq!(used, compressed.len());
+    assert_eq!(data, b"hello world");
+}
+
+#[test]
+fn trailing_data() {
+    let mut input = compress(b"hello world");
+    let stream_len = input.len();
+    input.extend_from_slice(b"trailing");
+    let (data, used) = decompress_to_vec(&input).unwrap();
+    assert_eq!(data, b"hello world");
+    assert_eq!(used, stream_len);
+}
+
+#[test]
+fn truncated() {
+    let compressed = compress(&BINARY_WAV_DATA[..200000]);
+    for len in (0..compressed.len()).step_by(997) {
+        let error = decompress_to_vec(&compressed[..len]).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            InflateErrorKind::UnexpectedEndOfInput,
+            "len: {len}"
+        );
+        assert!(error.input_bit_offset() <= len as u64 * 8, "len: {len}");
+        assert!(error.output_byte_offset() < 200000, "len: {len}");
+    }
+}
+
+#[test]
+fn invalid_data() {
+    let compressed = [0xFF, 0xFF, 0xFF];
+    let mut inflater = Box::new(InflaterManaged::new());
+    let expected = inflater.inflate(&compressed, &mut [0; 100]).error;
+    assert_eq!(decompress_to_vec(&compressed).err(), expected);
+}
+
+#[test]
+fn limit() {
+    let compressed = compress(&vec![7u8; 100000]);
+    for size_hint in [None, Some(100000)] {
+        let (data, _) = decompress_to_vec_with_limit(&compressed, size_hint, 100000).unwrap();
+        assert_eq!(data.len(), 100000);
+
+        let error = decompress_to_vec_with_limit(&compressed, size_hint, 99999).unwrap_err();
+        assert_eq!(error.kind(), InflateErrorKind::OutputLimitExceeded);
+        assert_eq!(error.output_byte_offset(), 99999);
+    }
+}
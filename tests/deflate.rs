@@ -0,0 +1,161 @@
+#![cfg(feature = "std")]
+
+use deflate64::{
+    Deflate64Decoder, DeflateFlush, DeflaterManaged, InflateErrorKind, InflaterManaged,
+};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::{BufReader, Read, Write};
+
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+fn flate2_compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn binary_wav() {
+    let compressed = flate2_compress(BINARY_WAV_DATA);
+    let mut uncompressed_data = vec![0u8; BINARY_WAV_DATA.len() + 10];
+
+    let mut inflater = Box::new(InflaterManaged::new_deflate());
+    assert!(!inflater.is_deflate64());
+    let output = inflater.inflate(&compressed, &mut uncompressed_data);
+    assert!(!output.data_error(), "unexpected error");
+    assert_eq!(output.bytes_consumed, compressed.len());
+    assert_eq!(output.bytes_written, BINARY_WAV_DATA.len());
+    assert!(inflater.finished());
+
+    assert_eq!(&uncompressed_data[..output.bytes_written], BINARY_WAV_DATA);
+}
+
+#[test]
+fn length_258() {
+    // zeros are encoded with length code 285, which is length 258 in deflate
+    let data = vec![0u8; 1024 * 1024];
+    let compressed = flate2_compress(&data);
+    let mut uncompressed_data = vec![0u8; data.len() + 10];
+
+    let mut inflater = Box::new(InflaterManaged::deflate_with_uncompressed_size(data.len()));
+    let output = inflater.inflate(&compressed, &mut uncompressed_data);
+    assert!(!output.data_error(), "unexpected error");
+    assert_eq!(output.bytes_written, data.len());
+    assert_eq!(&uncompressed_data[..output.bytes_written], &data[..]);
+
+    // while deflate64 decoder reads 16 extra bits for the code
+    let mut inflater = Box::new(InflaterManaged::new());
+    let output = inflater.inflate(&compressed, &mut uncompressed_data);
+    assert!(output.data_error() || output.bytes_written != data.len());
+}
+
+#[test]
+fn shredded() {
+    let compressed = flate2_compress(BINARY_WAV_DATA);
+    let mut uncompressed_data = Vec::new();
+    let mut buffer = [0u8; 1000];
+
+    let mut inflater = Box::new(InflaterManaged::new_deflate());
+    let mut input = &compressed[..];
+    while !inflater.finished() {
+        let output = inflater.inflate(&input[..input.len().min(100)], &mut buffer);
+        assert!(!output.data_error(), "unexpected error");
+        input = &input[output.bytes_consumed..];
+        uncompressed_data.extend_from_slice(&buffer[..output.bytes_written]);
+    }
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[test]
+fn reject_deflate64_distance() {
+    let mut state = 0x2545F491u32;
+    let mut random = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as u8
+    };
+    // short runs which only match with distance 40000, which needs distance code 30
+    let mut data = Vec::new();
+    data.extend((0..40000).map(|_| random()));
+    for i in 0..100 {
+        data.extend_from_within(i * 100..i * 100 + 10);
+        data.extend((0..90).map(|_| random()));
+    }
+
+    let mut deflater = DeflaterManaged::new();
+    let mut compressed = vec![0u8; data.len() + 1024];
+    let result = deflater.deflate(&data, &mut compressed, DeflateFlush::Finish);
+    compressed.truncate(result.bytes_written);
+
+    let mut uncompressed_data = vec![0u8; data.len() + 10];
+
+    let mut inflater = Box::new(InflaterManaged::new_deflate());
+    let output = inflater.inflate(&compressed, &mut uncompressed_data);
+    assert_eq!(
+        output.error.map(|error| error.kind()),
+        Some(InflateErrorKind::InvalidDistanceSymbol)
+    );
+    assert!(inflater.errored());
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let output = inflater.inflate(&compressed, &mut uncompressed_data);
+    assert!(!output.data_error(), "unexpected error");
+    assert_eq!(&uncompressed_data[..output.bytes_written], &data[..]);
+}
+
+#[test]
+fn stream() {
+    let compressed = flate2_compress(BINARY_WAV_DATA);
+    let mut decoder = Deflate64Decoder::with_inflater(
+        BufReader::new(&compressed[..]),
+        Box::new(InflaterManaged::new_deflate()),
+    );
+    let mut uncompressed_data = vec![];
+    decoder.read_to_end(&mut uncompressed_data).unwrap();
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[test]
+fn skewed_literals() {
+    let mut state = 0x2545F491u32;
+    let mut random = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    };
+    // geometric distribution of literals gives codes from 1 bit to 15 bits
+    let data = (0..200000)
+        .map(|_| {
+            let value = random();
+            if value % 64 == 0 {
+                (value >> 8) as u8
+            } else {
+                b'a' + (value >> 6).trailing_zeros() as u8
+            }
+        })
+        .collect::<Vec<_>>();
+    let compressed = flate2_compress(&data);
+
+    let mut uncompressed_data = vec![0u8; data.len() + 10];
+    let mut inflater = Box::new(InflaterManaged::new_deflate());
+    let output = inflater.inflate(&compressed, &mut uncompressed_data);
+    assert!(!output.data_error(), "unexpected error");
+    assert_eq!(&uncompressed_data[..output.bytes_written], &data[..]);
+
+    let mut uncompressed_data = Vec::new();
+    let mut buffer = [0u8; 1000];
+    let mut inflater = Box::new(InflaterManaged::new_deflate());
+    let mut input = &compressed[..];
+    while !inflater.finished() {
+        let output = inflater.inflate(&input[..input.len().min(7)], &mut buffer);
+        assert!(!output.data_error(), "unexpected error");
+        input = &input[output.bytes_consumed..];
+        uncompressed_data.extend_from_slice(&buffer[..output.bytes_written]);
+    }
+    assert_eq!(uncompressed_data, data);
+}
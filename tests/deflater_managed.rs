@@ -0,0 +1,208 @@
+#![cfg(feature = "alloc")]
+
+use deflate64::{DeflateFlush, DeflaterManaged, InflaterManaged};
+use proptest::proptest;
+use std::cmp::min;
+
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+static LOGO_PNG_DATA: &[u8] = include_bytes!("../test-assets/issue-13/logo.png");
+
+fn compress(data: &[u8], level: u8) -> Vec<u8> {
+    let mut deflater = DeflaterManaged::with_level(level);
+    let mut compressed = vec![0u8; data.len() + data.len() / 100 + 1024];
+    let result = deflater.deflate(data, &mut compressed, DeflateFlush::Finish);
+    assert_eq!(result.bytes_consumed, data.len());
+    assert!(deflater.finished());
+    compressed.truncate(result.bytes_written);
+    compressed
+}
+
+fn decompress(compressed: &[u8], size: usize) -> Vec<u8> {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut uncompressed = vec![0u8; size + 10];
+    let result = inflater.inflate(compressed, &mut uncompressed);
+    assert!(!result.data_error(), "unexpected error");
+    assert_eq!(result.bytes_consumed, compressed.len());
+    assert!(inflater.finished());
+    uncompressed.truncate(result.bytes_written);
+    uncompressed
+}
+
+#[test]
+fn binary_wav() {
+    let compressed = compress(BINARY_WAV_DATA, DeflaterManaged::DEFAULT_LEVEL);
+    assert!(compressed.len() < BINARY_WAV_DATA.len());
+    assert_eq!(
+        decompress(&compressed, BINARY_WAV_DATA.len()),
+        BINARY_WAV_DATA
+    );
+}
+
+#[test]
+fn all_levels() {
+    let mut data = LOGO_PNG_DATA.to_vec();
+    data.extend_from_slice(&BINARY_WAV_DATA[..100000]);
+    data.extend_from_slice(&[0u8; 200000]);
+    data.extend(b"deflate64 ".iter().cycle().take(100000));
+
+    for level in 0..=9 {
+        let compressed = compress(&data, level);
+        assert_eq!(decompress(&compressed, data.len()), data, "level {level}");
+    }
+}
+
+#[test]
+fn empty() {
+    let compressed = compress(&[], DeflaterManaged::DEFAULT_LEVEL);
+    assert_eq!(decompress(&compressed, 0), b"");
+}
+
+#[test]
+fn long_zero_run() {
+    // deflate64 can encode 65538 bytes with one length code
+    let data = vec![0u8; 16 * 1024 * 1024];
+    let compressed = compress(&data, DeflaterManaged::DEFAULT_LEVEL);
+    assert!(compressed.len() < 2048, "{}", compressed.len());
+    assert_eq!(decompress(&compressed, data.len()), data);
+}
+
+#[test]
+fn shredded() {
+    let data = &BINARY_WAV_DATA[..300000];
+    let mut deflater = DeflaterManaged::new();
+    let mut compressed = Vec::new();
+    let mut input = data;
+    let mut buffer = [0u8; 100];
+
+    while !deflater.finished() {
+        let chunk = &input[..min(1000, input.len())];
+        let flush = if input.len() == chunk.len() {
+            DeflateFlush::Finish
+        } else {
+            DeflateFlush::None
+        };
+        let result = deflater.deflate(chunk, &mut buffer, flush);
+        input = &input[result.bytes_consumed..];
+        compressed.extend_from_slice(&buffer[..result.bytes_written]);
+    }
+
+    assert!(input.is_empty());
+    assert_eq!(decompress(&compressed, data.len()), data);
+}
+
+#[test]
+fn sync_flush() {
+    let data = &BINARY_WAV_DATA[..100000];
+    let mut deflater = DeflaterManaged::new();
+    let mut compressed = vec![0u8; 200000];
+
+    let result = deflater.deflate(&data[..50000], &mut compressed, DeflateFlush::Sync);
+    assert_eq!(result.bytes_consumed, 50000);
+    let synced = result.bytes_written;
+    assert_eq!(&compressed[synced - 4..synced], &[0x00, 0x00, 0xFF, 0xFF]);
+
+    // everything written so far can be decompressed
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut uncompressed = vec![0u8; 100000];
+    let partial = inflater.inflate(&compressed[..synced], &mut uncompressed);
+    assert!(!partial.data_error());
+    assert_eq!(&uncompressed[..partial.bytes_written], &data[..50000]);
+
+    // flushing again without input writes nothing
+    let result = deflater.deflate(&[], &mut compressed[synced..], DeflateFlush::Sync);
+    assert_eq!(result.bytes_written, 0);
+
+    let result = deflater.deflate(
+        &data[50000..],
+        &mut compressed[synced..],
+        DeflateFlush::Finish,
+    );
+    assert!(deflater.finished());
+    compressed.truncate(synced + result.bytes_written);
+    assert_eq!(decompress(&compressed, data.len()), data);
+}
+
+proptest! {
+    #[test]
+    fn round_trip(data in proptest::collection::vec(0u8..4, 0..20000), level in 0u8..=9) {
+        let compressed = compress(&data, level);
+        assert_eq!(decompress(&compressed, data.len()), data);
+    }
+}
+
+#[test]
+fn skewed_frequencies() {
+    // symbol i appears fib(i) times so the huffman code needs more than 15 bits without limit
+    let mut data = Vec::new();
+    let (mut a, mut b) = (1usize, 1usize);
+    for symbol in 0..26u8 {
+        data.extend(std::iter::repeat_n(symbol, a));
+        (a, b) = (b, a + b);
+    }
+    // shuffle with xorshift to avoid long matches
+    let mut state = 0x2545F491u32;
+    for i in (1..data.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        data.swap(i, state as usize % (i + 1));
+    }
+
+    let compressed = compress(&data, DeflaterManaged::DEFAULT_LEVEL);
+    assert_eq!(decompress(&compressed, data.len()), data);
+}
+
+#[test]
+fn stored_after_compressed() {
+    // random blocks are stored as uncompressed blocks, and their repetitions are compressed
+    let mut state = 0x2545F491u32;
+    let mut random = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as u8
+    };
+    let mut data = Vec::new();
+    for _ in 0..4 {
+        let block = (0..65536).map(|_| random()).collect::<Vec<_>>();
+        data.extend_from_slice(&block);
+        data.extend_from_slice(&block);
+        data.extend((0..777).map(|_| random()));
+    }
+
+    let compressed = compress(&data, DeflaterManaged::DEFAULT_LEVEL);
+    assert_eq!(decompress(&compressed, data.len()), data);
+}
+
+#[test]
+fn chains_after_slide() {
+    // the block is found through the hash chain behind its mutated copy
+    let mut state = 0x2545F491u32;
+    let mut random = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as u8
+    };
+    let block = (0..30000).map(|_| random()).collect::<Vec<_>>();
+    let mut mutated = block.clone();
+    for byte in mutated.iter_mut().step_by(8) {
+        *byte ^= 0x80;
+    }
+    let mut data = Vec::new();
+    while data.len() < 8 * 240000 {
+        data.extend_from_slice(&block);
+        data.extend_from_slice(&mutated);
+    }
+
+    // the window doesn't slide in the first part
+    let first = compress(&data[..240000], DeflaterManaged::DEFAULT_LEVEL);
+    let compressed = compress(&data[..8 * 240000], DeflaterManaged::DEFAULT_LEVEL);
+    assert!(
+        compressed.len() <= first.len() * 8,
+        "{} > {} * 8",
+        compressed.len(),
+        first.len()
+    );
+    assert_eq!(decompress(&compressed, 8 * 240000), data[..8 * 240000]);
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "alloc")]
+
+use deflate64::{DeflateFlush, DeflaterManaged, InflateErrorKind, InflaterManaged};
+
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+fn compress_with_dictionary(data: &[u8], dictionary: &[u8], level: u8) -> Vec<u8> {
+    let mut deflater = DeflaterManaged::with_level(level);
+    deflater.set_dictionary(dictionary);
+    let mut compressed = vec![0u8; data.len() + 1024];
+    let result = deflater.deflate(data, &mut compressed, DeflateFlush::Finish);
+    assert!(deflater.finished());
+    compressed.truncate(result.bytes_written);
+    compressed
+}
+
+fn decompress(compressed: &[u8], dictionary: Option<&[u8]>, size: usize) -> Vec<u8> {
+    let mut inflater = Box::new(InflaterManaged::new());
+    if let Some(dictionary) = dictionary {
+        inflater.set_dictionary(dictionary);
+    }
+    let mut uncompressed = vec![0u8; size + 10];
+    let result = inflater.inflate(compressed, &mut uncompressed);
+    assert!(!result.data_error(), "unexpected error");
+    assert_eq!(result.bytes_consumed, compressed.len());
+    uncompressed.truncate(result.bytes_written);
+    uncompressed
+}
+
+#[test]
+fn round_trip() {
+    let dictionary = &BINARY_WAV_DATA[..60000];
+    // records which are similar to the dictionary
+    let data = &BINARY_WAV_DATA[10000..20000];
+
+    let compressed = compress_with_dictionary(data, dictionary, DeflaterManaged::DEFAULT_LEVEL);
+    assert!(compressed.len() < 100, "{}", compressed.len());
+    assert_eq!(decompress(&compressed, Some(dictionary), data.len()), data);
+
+    // the dictionary is not output, and the stream can't be decoded without it
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut uncompressed = vec![0u8; data.len()];
+    let result = inflater.inflate(&compressed, &mut uncompressed);
+    assert_eq!(
+        result.error.map(|x| x.kind()),
+        Some(InflateErrorKind::DistanceTooFar)
+    );
+}
+
+#[test]
+fn long_dictionary() {
+    // only the last 64 KiB of the dictionary is used
+    let dictionary = &BINARY_WAV_DATA[..200000];
+    let data = &BINARY_WAV_DATA[150000..160000];
+
+    let compressed = compress_with_dictionary(data, dictionary, DeflaterManaged::DEFAULT_LEVEL);
+    assert!(compressed.len() < 100, "{}", compressed.len());
+    assert_eq!(
+        decompress(&compressed, Some(&dictionary[200000 - 65536..]), data.len()),
+        data
+    );
+    assert_eq!(decompress(&compressed, Some(dictionary), data.len()), data);
+}
+
+#[test]
+fn all_levels() {
+    let dictionary = b"the quick brown fox jumps over the lazy dog. ".repeat(10);
+    let data = b"the lazy dog jumps over the quick brown fox. ".repeat(100);
+
+    for level in 0..=9 {
+        let compressed = compress_with_dictionary(&data, &dictionary, level);
+        assert_eq!(
+            decompress(&compressed, Some(&dictionary), data.len()),
+            data,
+            "level {level}"
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "set_dictionary must be called before decompression")]
+fn set_dictionary_after_start() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = [0u8; 10];
+    let _ = inflater.inflate(&[0x00, 0x05], &mut output);
+    inflater.set_dictionary(b"dictionary");
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "std")]
+
+use deflate64::{Deflate64Decoder, InflateError, InflateErrorKind, InflaterManaged};
+use std::io::{Cursor, ErrorKind, Read};
+
+fn inflate_error(input: &[u8]) -> InflateError {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![0u8; 1024];
+    let result = inflater.inflate(input, &mut output);
+    assert!(inflater.errored());
+    assert_eq!(inflater.error(), result.error);
+    result.error.expect("expected an error")
+}
+
+#[test]
+fn unknown_block_type() {
+    // bfinal = 1, btype = 3
+    let error = inflate_error(&[0x07]);
+    assert_eq!(error.kind(), InflateErrorKind::UnknownBlockType);
+    assert_eq!(error.input_bit_offset(), 3);
+    assert_eq!(error.output_byte_offset(), 0);
+}
+
+#[test]
+fn invalid_block_length() {
+    // uncompressed block with length 5 and complement of 0
+    let error = inflate_error(&[0x01, 0x05, 0x00, 0x00, 0x00]);
+    assert_eq!(error.kind(), InflateErrorKind::InvalidBlockLength);
+}
+
+#[test]
+fn offsets_after_data() {
+    // uncompressed block with "abc" followed by block with unknown block type
+    let error = inflate_error(&[0x00, 0x03, 0x00, 0xFC, 0xFF, b'a', b'b', b'c', 0x07]);
+    assert_eq!(error.kind(), InflateErrorKind::UnknownBlockType);
+    assert_eq!(error.input_bit_offset(), 8 * 8 + 3);
+    assert_eq!(error.output_byte_offset(), 3);
+}
+
+#[test]
+fn missing_end_of_block_code() {
+    // dynamic block: HLIT = 257, HDIST = 1, HCLEN = 4
+    // code length codes for 16, 17, 18, 0: only 18 and 0 have length 1
+    // then literal/length code lengths are all zero (18 repeats 138 + 119 zeros)
+    let mut input = Vec::new();
+    let mut bits = 0u64;
+    let mut count = 0;
+    let mut write = |value: u64, len: u32| {
+        bits |= value << count;
+        count += len;
+    };
+    write(1, 1); // bfinal
+    write(2, 2); // dynamic
+    write(0, 5); // HLIT
+    write(0, 5); // HDIST
+    write(0, 4); // HCLEN
+    write(0, 3); // 16
+    write(0, 3); // 17
+    write(1, 3); // 18
+    write(1, 3); // 0
+    write(1, 1); // 18 (code 1)
+    write(127, 7); // 138 zeros
+    write(1, 1); // 18
+    write(108, 7); // 119 zeros
+    write(0, 1); // 0 for distance
+    for _ in 0..8 {
+        input.push(bits as u8);
+        bits >>= 8;
+    }
+
+    let error = inflate_error(&input);
+    assert_eq!(error.kind(), InflateErrorKind::MissingEndOfBlockCode);
+}
+
+#[test]
+fn error_is_kept() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![0u8; 1024];
+    let first = inflater.inflate(&[0x07], &mut output).error.unwrap();
+    let second = inflater.inflate(&[0x00], &mut output);
+    assert_eq!(second.error, Some(first));
+    assert_eq!(second.bytes_consumed, 0);
+}
+
+#[test]
+fn stream_error() {
+    let mut decoder = Deflate64Decoder::new(Cursor::new(&[0x07]));
+    let mut output = Vec::new();
+    let error = decoder.read_to_end(&mut output).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    let inflate_error = error
+        .get_ref()
+        .unwrap()
+        .downcast_ref::<InflateError>()
+        .unwrap();
+    assert_eq!(inflate_error.kind(), InflateErrorKind::UnknownBlockType);
+}
@@ -0,0 +1,124 @@
+#![cfg(feature = "futures")]
+
+mod common;
+
+use common::corrupt_after;
+use deflate64::futures::AsyncDeflate64Decoder;
+use deflate64::InflateError;
+use futures::executor::block_on;
+use futures::io::{AsyncBufRead, AsyncRead, AsyncReadExt};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+const BINARY_WAV_DATA_OFFSET: usize = 40;
+const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
+
+static ZIP_FILE_DATA: &[u8] = include_bytes!("../test-assets/deflate64.zip");
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+fn source_stream() -> &'static [u8] {
+    &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE]
+}
+
+/// AsyncBufRead which returns a few bytes at once and is pending every other poll
+struct Trickle {
+    data: &'static [u8],
+    pending: bool,
+}
+
+impl AsyncRead for Trickle {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let input = futures::ready!(self.as_mut().poll_fill_buf(cx))?;
+        let len = input.len().min(buf.len());
+        buf[..len].copy_from_slice(&input[..len]);
+        self.consume(len);
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl AsyncBufRead for Trickle {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        this.pending = !this.pending;
+        if this.pending {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        Poll::Ready(Ok(&this.data[..this.data.len().min(7)]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        this.data = &this.data[amt..];
+    }
+}
+
+#[test]
+fn decode_from_async_read() {
+    let mut decoder = AsyncDeflate64Decoder::new(source_stream());
+
+    let mut uncompressed_data = vec![];
+    block_on(decoder.read_to_end(&mut uncompressed_data)).unwrap();
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[test]
+fn decode_from_async_buf_read() {
+    let mut decoder = AsyncDeflate64Decoder::with_buffer(source_stream());
+
+    let mut uncompressed_data = vec![];
+    block_on(decoder.read_to_end(&mut uncompressed_data)).unwrap();
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[test]
+fn decode_with_pending() {
+    let inner = Trickle {
+        data: source_stream(),
+        pending: false,
+    };
+    let mut decoder = AsyncDeflate64Decoder::with_buffer(inner);
+
+    let mut uncompressed_data = vec![];
+    let mut buf = [0u8; 13];
+    loop {
+        let read = block_on(decoder.read(&mut buf)).unwrap();
+        if read == 0 {
+            break;
+        }
+        uncompressed_data.extend_from_slice(&buf[..read]);
+    }
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+    assert!(decoder.into_inner().data.is_empty());
+}
+
+#[test]
+fn decode_error() {
+    let mut decoder = AsyncDeflate64Decoder::with_buffer(&[0xFFu8; 16][..]);
+
+    let mut uncompressed_data = vec![];
+    let error = block_on(decoder.read_to_end(&mut uncompressed_data)).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    assert!(error.into_inner().unwrap().is::<InflateError>());
+}
+
+#[test]
+fn output_before_error() {
+    let data = &BINARY_WAV_DATA[..1000];
+    let stream = corrupt_after(data);
+    let mut decoder = AsyncDeflate64Decoder::with_buffer(&stream[..]);
+
+    let mut buf = vec![0u8; 2000];
+    let read = block_on(decoder.read(&mut buf)).unwrap();
+    assert_eq!(&buf[..read], data);
+    let error = block_on(decoder.read(&mut buf)).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
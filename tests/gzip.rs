@@ -0,0 +1,278 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use common::corrupt_after;
+use deflate64::{DeflateFlush, DeflaterManaged, GzDeflate64Decoder, GzError};
+use std::io::{Cursor, ErrorKind, Read};
+
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[derive(Default)]
+struct Header<'a> {
+    extra: Option<&'a [u8]>,
+    filename: Option<&'a [u8]>,
+    comment: Option<&'a [u8]>,
+    header_crc: bool,
+}
+
+fn create_gzip_member(data: &[u8], header: &Header<'_>) -> Vec<u8> {
+    let mut deflater = DeflaterManaged::new();
+    let mut compressed = vec![0u8; data.len() + 1024];
+    let result = deflater.deflate(data, &mut compressed, DeflateFlush::Finish);
+    assert!(deflater.finished());
+    compressed.truncate(result.bytes_written);
+
+    let mut flags = 0;
+    if header.header_crc {
+        flags |= 0x02;
+    }
+    if header.extra.is_some() {
+        flags |= 0x04;
+    }
+    if header.filename.is_some() {
+        flags |= 0x08;
+    }
+    if header.comment.is_some() {
+        flags |= 0x10;
+    }
+
+    let mut gzip = vec![0x1f, 0x8b, 8, flags];
+    gzip.extend_from_slice(&0x12345678u32.to_le_bytes()); // mtime
+    gzip.extend_from_slice(&[0, 3]); // xfl, os
+    if let Some(extra) = header.extra {
+        gzip.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+        gzip.extend_from_slice(extra);
+    }
+    if let Some(filename) = header.filename {
+        gzip.extend_from_slice(filename);
+        gzip.push(0);
+    }
+    if let Some(comment) = header.comment {
+        gzip.extend_from_slice(comment);
+        gzip.push(0);
+    }
+    if header.header_crc {
+        let crc16 = crc32(&gzip) as u16;
+        gzip.extend_from_slice(&crc16.to_le_bytes());
+    }
+    gzip.extend_from_slice(&compressed);
+    gzip.extend_from_slice(&crc32(data).to_le_bytes());
+    gzip.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    gzip
+}
+
+fn decode(gzip: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut decoder = GzDeflate64Decoder::new(Cursor::new(gzip));
+    let mut uncompressed = Vec::new();
+    decoder.read_to_end(&mut uncompressed)?;
+    Ok(uncompressed)
+}
+
+fn gz_error(error: std::io::Error) -> GzError {
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    *error.get_ref().unwrap().downcast_ref::<GzError>().unwrap()
+}
+
+#[test]
+fn single_member() {
+    let data = &BINARY_WAV_DATA[..500000];
+    let gzip = create_gzip_member(data, &Header::default());
+    assert_eq!(decode(&gzip).unwrap(), data);
+}
+
+#[test]
+fn multi_member() {
+    let mut gzip = Vec::new();
+    gzip.extend(create_gzip_member(
+        &BINARY_WAV_DATA[..100000],
+        &Header::default(),
+    ));
+    gzip.extend(create_gzip_member(b"", &Header::default()));
+    gzip.extend(create_gzip_member(
+        &BINARY_WAV_DATA[100000..300000],
+        &Header::default(),
+    ));
+
+    assert_eq!(decode(&gzip).unwrap(), &BINARY_WAV_DATA[..300000]);
+}
+
+#[test]
+fn header_fields() {
+    let header = Header {
+        extra: Some(b"AB\x02\x00xy"),
+        filename: Some(b"binary.wmv"),
+        comment: Some(b"test comment"),
+        header_crc: true,
+    };
+    let data = &BINARY_WAV_DATA[..10000];
+    let gzip = create_gzip_member(data, &header);
+
+    let mut decoder = GzDeflate64Decoder::new(Cursor::new(&gzip));
+    assert!(decoder.header().is_none());
+    let mut uncompressed = Vec::new();
+    decoder.read_to_end(&mut uncompressed).unwrap();
+    assert_eq!(uncompressed, data);
+
+    let header = decoder.header().unwrap();
+    assert_eq!(header.mtime(), 0x12345678);
+    assert_eq!(header.os(), 3);
+    assert_eq!(header.extra(), Some(&b"AB\x02\x00xy"[..]));
+    assert_eq!(header.filename(), Some(&b"binary.wmv"[..]));
+    assert_eq!(header.comment(), Some(&b"test comment"[..]));
+}
+
+#[test]
+fn header_crc_mismatch() {
+    let header = Header {
+        filename: Some(b"binary.wmv"),
+        header_crc: true,
+        ..Header::default()
+    };
+    let mut gzip = create_gzip_member(b"test", &header);
+    // change the file name
+    gzip[10] ^= 1;
+
+    assert!(matches!(
+        gz_error(decode(&gzip).unwrap_err()),
+        GzError::HeaderChecksumMismatch { .. }
+    ));
+}
+
+#[test]
+fn header_field_too_long() {
+    let filename = vec![b'a'; 64 * 1024];
+    let header = Header {
+        filename: Some(&filename),
+        ..Header::default()
+    };
+    let gzip = create_gzip_member(b"test", &header);
+    assert_eq!(decode(&gzip).unwrap(), b"test");
+
+    let comment = vec![b'a'; 64 * 1024 + 1];
+    let header = Header {
+        comment: Some(&comment),
+        ..Header::default()
+    };
+    let gzip = create_gzip_member(b"test", &header);
+    assert_eq!(
+        gz_error(decode(&gzip).unwrap_err()),
+        GzError::HeaderFieldTooLong
+    );
+
+    // the name without zero terminator is not read to the end of the input
+    let mut gzip = vec![0x1f, 0x8b, 8, 0x08, 0, 0, 0, 0, 0, 3];
+    gzip.extend(std::iter::repeat_n(b'a', 1 << 20));
+    assert_eq!(
+        gz_error(decode(&gzip).unwrap_err()),
+        GzError::HeaderFieldTooLong
+    );
+}
+
+#[test]
+fn checksum_mismatch() {
+    let data = &BINARY_WAV_DATA[..10000];
+    let mut gzip = create_gzip_member(data, &Header::default());
+    let crc_position = gzip.len() - 8;
+    gzip[crc_position] ^= 1;
+
+    assert_eq!(
+        gz_error(decode(&gzip).unwrap_err()),
+        GzError::ChecksumMismatch {
+            expected: crc32(data) ^ 1,
+            actual: crc32(data),
+        }
+    );
+}
+
+#[test]
+fn size_mismatch() {
+    let data = &BINARY_WAV_DATA[..10000];
+    let mut gzip = create_gzip_member(data, &Header::default());
+    let size_position = gzip.len() - 4;
+    gzip[size_position] ^= 1;
+
+    assert_eq!(
+        gz_error(decode(&gzip).unwrap_err()),
+        GzError::SizeMismatch {
+            expected: 10000 ^ 1,
+            actual: 10000,
+        }
+    );
+}
+
+#[test]
+fn invalid_header() {
+    let gzip = create_gzip_member(b"test", &Header::default());
+
+    let mut invalid_magic = gzip.clone();
+    invalid_magic[1] = 0;
+    assert_eq!(
+        gz_error(decode(&invalid_magic).unwrap_err()),
+        GzError::InvalidMagic
+    );
+
+    let mut invalid_method = gzip.clone();
+    invalid_method[2] = 9;
+    assert_eq!(
+        gz_error(decode(&invalid_method).unwrap_err()),
+        GzError::UnknownCompressionMethod(9)
+    );
+
+    let mut reserved_flags = gzip.clone();
+    reserved_flags[3] = 0x20;
+    assert_eq!(
+        gz_error(decode(&reserved_flags).unwrap_err()),
+        GzError::ReservedFlags(0x20)
+    );
+
+    // garbage after the first member is not a valid member
+    let mut trailing_garbage = gzip.clone();
+    trailing_garbage.extend_from_slice(b"garbage!!!");
+    assert_eq!(
+        gz_error(decode(&trailing_garbage).unwrap_err()),
+        GzError::InvalidMagic
+    );
+}
+
+#[test]
+fn truncated() {
+    let header = Header {
+        filename: Some(b"binary.wmv"),
+        ..Header::default()
+    };
+    let gzip = create_gzip_member(&BINARY_WAV_DATA[..10000], &header);
+    for len in [0, 5, 15, 100, gzip.len() - 4] {
+        let error = decode(&gzip[..len]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof, "len {len}");
+    }
+}
+
+#[test]
+fn output_before_error() {
+    let data = &BINARY_WAV_DATA[..1000];
+    let mut gzip = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3];
+    gzip.extend_from_slice(&corrupt_after(data));
+
+    let mut decoder = GzDeflate64Decoder::new(Cursor::new(&gzip));
+    let mut buf = vec![0u8; 2000];
+    let read = decoder.read(&mut buf).unwrap();
+    assert_eq!(&buf[..read], data);
+    let error = decoder.read(&mut buf).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
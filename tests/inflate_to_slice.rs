@@ -0,0 +1,212 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use common::compress;
+use deflate64::{InflateError, InflateErrorKind, InflateLimits, InflaterManaged};
+
+static ZIP_FILE_DATA: &[u8] = include_bytes!("../test-assets/deflate64.zip");
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+const BINARY_WAV_DATA_OFFSET: usize = 40;
+const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
+
+fn binary_wav_compressed() -> &'static [u8] {
+    &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE]
+}
+
+/// Text-like data with matches, followed by random bytes stored in uncompressed blocks
+fn mixed_data() -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut state = 0x2545F491u32;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    };
+    while data.len() < 150000 {
+        let word = next() % 64;
+        data.extend(format!("word{word} ").bytes());
+    }
+    data.extend((0..70000).map(|_| next() as u8));
+    data.extend(vec![b'a'; 1000]);
+    data
+}
+
+/// Decompresses `input` with `inflate_to_slice` first and `inflate` after that,
+/// feeding the input by `chunk` bytes into the output of `output_len` bytes
+fn inflate_to_slice_all(
+    inflater: &mut InflaterManaged,
+    input: &[u8],
+    chunk: usize,
+    output_len: usize,
+) -> (Vec<u8>, usize, Option<InflateError>) {
+    let mut output = vec![0u8; output_len];
+    let mut written = 0;
+    let mut consumed = 0;
+    let mut first = true;
+    loop {
+        let input = &input[consumed..];
+        let input = &input[..chunk.min(input.len())];
+        let result = if first {
+            inflater.inflate_to_slice(input, &mut output[written..])
+        } else {
+            inflater.inflate(input, &mut output[written..])
+        };
+        first = false;
+        consumed += result.bytes_consumed;
+        written += result.bytes_written;
+        if result.error.is_some() || inflater.finished() {
+            output.truncate(written);
+            return (output, consumed, result.error);
+        }
+        if result.bytes_consumed == 0 && result.bytes_written == 0 {
+            output.resize(output.len() + 1000, 0);
+        }
+    }
+}
+
+fn inflate_all(inflater: &mut InflaterManaged, input: &[u8]) -> (Vec<u8>, Option<InflateError>) {
+    let mut output = vec![0u8; 1 << 22];
+    let result = inflater.inflate(input, &mut output);
+    output.truncate(result.bytes_written);
+    (output, result.error)
+}
+
+#[test]
+fn binary_wav() {
+    let compressed = binary_wav_compressed();
+    let mut output = vec![0u8; BINARY_WAV_DATA.len()];
+    let mut inflater = Box::new(InflaterManaged::new());
+    let result = inflater.inflate_to_slice(compressed, &mut output);
+    assert_eq!(result.error, None);
+    assert_eq!(result.bytes_consumed, compressed.len());
+    assert_eq!(result.bytes_written, BINARY_WAV_DATA.len());
+    assert!(inflater.finished());
+    assert_eq!(inflater.total_out(), BINARY_WAV_DATA.len() as u64);
+    assert!(output == BINARY_WAV_DATA);
+}
+
+#[test]
+fn larger_output_and_trailing_data() {
+    let mut input = compress(&mixed_data());
+    let stream_len = input.len();
+    input.extend_from_slice(b"trailing");
+    let mut output = vec![0u8; 1 << 20];
+    let mut inflater = Box::new(InflaterManaged::new());
+    let result = inflater.inflate_to_slice(&input, &mut output);
+    assert_eq!(result.error, None);
+    assert_eq!(result.bytes_consumed, stream_len);
+    assert!(output[..result.bytes_written] == mixed_data());
+    assert!(inflater.finished());
+}
+
+#[test]
+fn small_output() {
+    let data = mixed_data();
+    let compressed = compress(&data);
+    for output_len in [0, 1, 7, 1000, 65536, 100000, 200000, data.len() - 1] {
+        let mut inflater = Box::new(InflaterManaged::new());
+        let (output, consumed, error) =
+            inflate_to_slice_all(&mut inflater, &compressed, usize::MAX, output_len);
+        assert_eq!(error, None, "output_len: {output_len}");
+        assert_eq!(consumed, compressed.len(), "output_len: {output_len}");
+        assert!(output == data, "output_len: {output_len}");
+    }
+}
+
+#[test]
+fn partial_input() {
+    let data = mixed_data();
+    let compressed = compress(&data);
+    for chunk in [1, 2, 5, 13, 1000, 30000, compressed.len() - 1] {
+        let mut inflater = Box::new(InflaterManaged::new());
+        let (output, consumed, error) =
+            inflate_to_slice_all(&mut inflater, &compressed, chunk, data.len());
+        assert_eq!(error, None, "chunk: {chunk}");
+        assert_eq!(consumed, compressed.len(), "chunk: {chunk}");
+        assert!(output == data, "chunk: {chunk}");
+    }
+}
+
+#[test]
+fn deflate() {
+    let data = mixed_data();
+    let mut input = vec![0u8; data.len() + 1024];
+    let mut compress = flate2::Compress::new(flate2::Compression::default(), false);
+    compress
+        .compress(&data, &mut input, flate2::FlushCompress::Finish)
+        .unwrap();
+    input.truncate(compress.total_out() as usize);
+
+    let mut output = vec![0u8; data.len()];
+    let mut inflater = Box::new(InflaterManaged::new_deflate());
+    let result = inflater.inflate_to_slice(&input, &mut output);
+    assert_eq!(result.error, None);
+    assert_eq!(result.bytes_consumed, input.len());
+    assert!(output == data);
+}
+
+#[test]
+fn same_error_as_inflate() {
+    let compressed = compress(&mixed_data());
+    for position in (0..compressed.len()).step_by(97) {
+        let mut corrupted = compressed.clone();
+        corrupted[position] ^= 0x5A;
+
+        let mut inflater = Box::new(InflaterManaged::new());
+        let (expected, expected_error) = inflate_all(&mut inflater, &corrupted);
+        let mut inflater = Box::new(InflaterManaged::new());
+        let (output, _, error) =
+            inflate_to_slice_all(&mut inflater, &corrupted, usize::MAX, 1 << 22);
+        assert_eq!(error, expected_error, "position: {position}");
+        assert!(output == expected, "position: {position}");
+    }
+}
+
+#[test]
+fn strict_size() {
+    let data = mixed_data();
+    let compressed = compress(&data);
+
+    let mut inflater = Box::new(InflaterManaged::with_uncompressed_size(data.len() - 10));
+    inflater.set_strict(true);
+    let (output, _, error) =
+        inflate_to_slice_all(&mut inflater, &compressed, usize::MAX, data.len());
+    assert_eq!(error.unwrap().kind(), InflateErrorKind::OutputTooLong);
+    assert!(output == data[..data.len() - 10]);
+
+    let mut inflater = Box::new(InflaterManaged::with_uncompressed_size(data.len() + 10));
+    inflater.set_strict(true);
+    let (output, _, error) =
+        inflate_to_slice_all(&mut inflater, &compressed, usize::MAX, data.len() + 10);
+    assert_eq!(error.unwrap().kind(), InflateErrorKind::OutputTooShort);
+    assert!(output == data);
+
+    let mut input = compressed.clone();
+    input.push(0);
+    let mut inflater = Box::new(InflaterManaged::with_uncompressed_size(data.len()));
+    inflater.set_strict(true);
+    let (output, _, error) = inflate_to_slice_all(&mut inflater, &input, usize::MAX, data.len());
+    assert_eq!(error.unwrap().kind(), InflateErrorKind::TrailingData);
+    assert!(output == data);
+}
+
+#[test]
+fn limits() {
+    let compressed = compress(&vec![0u8; 1 << 20]);
+    for limits in [
+        InflateLimits::new().max_output(100000),
+        InflateLimits::new().max_ratio(10),
+    ] {
+        let mut inflater = Box::new(InflaterManaged::new());
+        inflater.set_limits(limits);
+        let (expected, expected_error) = inflate_all(&mut inflater, &compressed);
+        let mut inflater = Box::new(InflaterManaged::new());
+        inflater.set_limits(limits);
+        let (output, _, error) =
+            inflate_to_slice_all(&mut inflater, &compressed, usize::MAX, 1 << 20);
+        assert_eq!(error, expected_error);
+        assert_eq!(output.len(), expected.len());
+    }
+}
@@ -24,7 +24,7 @@ fn binary_wav() {
     let output = inflater.inflate(binary_wav_compressed, &mut uncompressed_data);
     assert_eq!(output.bytes_consumed, BINARY_WAV_COMPRESSED_SIZE);
     assert_eq!(output.bytes_written, BINARY_WAV_UNCOMPRESSED_SIZE);
-    assert!(!output.data_error, "unexpected error");
+    assert!(!output.data_error(), "unexpected error");
 
     assert_eq!(
         &uncompressed_data[..BINARY_WAV_UNCOMPRESSED_SIZE],
@@ -44,7 +44,7 @@ fn binary_wav_with_size() {
     let output = inflater.inflate(binary_wav_compressed, &mut uncompressed_data);
     assert_eq!(output.bytes_consumed, BINARY_WAV_COMPRESSED_SIZE);
     assert_eq!(output.bytes_written, BINARY_WAV_UNCOMPRESSED_SIZE);
-    assert!(!output.data_error, "unexpected error");
+    assert!(!output.data_error(), "unexpected error");
 
     assert_eq!(
         &uncompressed_data[..BINARY_WAV_UNCOMPRESSED_SIZE],
@@ -84,7 +84,7 @@ fn binary_wav_shredded(chunk: usize) {
         );
         compressed = &compressed[output.bytes_consumed..];
         written += output.bytes_written;
-        assert!(!output.data_error, "unexpected error");
+        assert!(!output.data_error(), "unexpected error");
     }
 
     assert_eq!(written, BINARY_WAV_UNCOMPRESSED_SIZE);
@@ -18,7 +18,7 @@ fn issue_13() {
 
     assert_eq!(output.bytes_consumed, compressed_data.len());
     assert_eq!(output.bytes_written, uncompressed_data.len());
-    assert!(!output.data_error, "unexpected error");
+    assert!(!output.data_error(), "unexpected error");
 
     assert_eq!(uncompressed_data.as_slice(), BINARY_DATA);
 }
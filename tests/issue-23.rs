@@ -10,7 +10,7 @@ fn issue_23() {
     let mut inflater = Box::new(InflaterManaged::new());
     let output = inflater.inflate(compressed_data, &mut vec![0u8; 1024]);
     assert!(
-        output.data_error,
+        output.data_error(),
         "expected an error since this deflate64 file is invalid"
     );
 }
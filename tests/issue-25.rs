@@ -19,7 +19,7 @@ fn issue_25() {
     let output = inflater.inflate(compressed_data, &mut sink);
 
     assert!(
-        output.data_error,
+        output.data_error(),
         "expected an error since this deflate64 file is invalid"
     );
 }
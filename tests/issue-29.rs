@@ -1,3 +1,5 @@
+#![cfg(feature = "std")]
+
 use std::io::{Cursor, Read};
 
 use deflate64::Deflate64Decoder;
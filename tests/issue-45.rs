@@ -1,3 +1,5 @@
+#![cfg(feature = "std")]
+
 // unexpected error with 65538 match length
 // (previous version limits 65536 but actually 65538)
 
@@ -0,0 +1,102 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use common::compress;
+use deflate64::{
+    Deflate64Decoder, DeflateFlush, DeflaterManaged, InflateError, InflateErrorKind, InflateLimits,
+    InflaterManaged,
+};
+use std::io::{Cursor, ErrorKind, Read};
+
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+/// Decompresses with the limits, returning the count of bytes written and the error
+fn inflate_limited(compressed: &[u8], limits: InflateLimits) -> (usize, Option<InflateError>) {
+    let mut inflater = Box::new(InflaterManaged::new());
+    inflater.set_limits(limits);
+    let mut output = vec![0u8; 64 * 1024];
+    let mut input = compressed;
+    let mut written = 0;
+    loop {
+        let result = inflater.inflate(input, &mut output);
+        input = &input[result.bytes_consumed..];
+        written += result.bytes_written;
+        if result.error.is_some() || inflater.finished() {
+            return (written, result.error);
+        }
+        assert!(result.bytes_consumed != 0 || result.bytes_written != 0);
+    }
+}
+
+#[test]
+fn output_limit() {
+    let bomb = compress(&vec![0u8; 8 << 20]);
+
+    let (written, error) = inflate_limited(&bomb, InflateLimits::new().max_output(1 << 20));
+    let error = error.expect("expected an error");
+    assert_eq!(error.kind(), InflateErrorKind::OutputLimitExceeded);
+    assert_eq!(written, 1 << 20);
+    assert_eq!(error.output_byte_offset(), 1 << 20);
+
+    let (written, error) = inflate_limited(&bomb, InflateLimits::new().max_output(8 << 20));
+    assert_eq!(error, None);
+    assert_eq!(written, 8 << 20);
+}
+
+#[test]
+fn ratio_limit() {
+    let bomb = compress(&vec![0u8; 8 << 20]);
+    let (_, error) = inflate_limited(&bomb, InflateLimits::new().max_ratio(100));
+    assert_eq!(
+        error.map(|x| x.kind()),
+        Some(InflateErrorKind::RatioLimitExceeded)
+    );
+
+    let compressed = compress(BINARY_WAV_DATA);
+    let (written, error) = inflate_limited(&compressed, InflateLimits::new().max_ratio(100));
+    assert_eq!(error, None);
+    assert_eq!(written, BINARY_WAV_DATA.len());
+}
+
+#[test]
+fn block_limit() {
+    let mut deflater = DeflaterManaged::new();
+    let mut compressed = vec![0u8; 1024];
+    let mut written = 0;
+    for flush in [DeflateFlush::Sync, DeflateFlush::Sync, DeflateFlush::Finish] {
+        let result = deflater.deflate(b"hello, world", &mut compressed[written..], flush);
+        written += result.bytes_written;
+    }
+    compressed.truncate(written);
+
+    let mut blocks = 0;
+    let mut output = vec![0u8; 1024];
+    InflaterManaged::new().inflate_with_events(&compressed, &mut output, |_| blocks += 1);
+
+    let (_, error) = inflate_limited(&compressed, InflateLimits::new().max_blocks(blocks));
+    assert_eq!(error, None);
+
+    let (_, error) = inflate_limited(&compressed, InflateLimits::new().max_blocks(blocks - 1));
+    assert_eq!(
+        error.map(|x| x.kind()),
+        Some(InflateErrorKind::BlockLimitExceeded)
+    );
+}
+
+#[test]
+fn decoder_output_limit() {
+    let bomb = compress(&vec![0u8; 8 << 20]);
+    let mut decoder = Deflate64Decoder::new(Cursor::new(bomb));
+    decoder.set_limits(InflateLimits::new().max_output(1 << 20));
+
+    let mut output = Vec::new();
+    let error = decoder.read_to_end(&mut output).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    let error = error
+        .get_ref()
+        .and_then(|x| x.downcast_ref::<InflateError>())
+        .expect("expected InflateError");
+    assert_eq!(error.kind(), InflateErrorKind::OutputLimitExceeded);
+    assert_eq!(output.len(), 1 << 20);
+}
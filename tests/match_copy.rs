@@ -0,0 +1,79 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use common::compress;
+use deflate64::InflaterManaged;
+
+fn random_bytes(state: &mut u32, len: usize) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            *state ^= *state << 13;
+            *state ^= *state >> 17;
+            *state ^= *state << 5;
+            *state as u8
+        })
+        .collect()
+}
+
+fn assert_roundtrip(data: &[u8]) {
+    let compressed = compress(data);
+
+    let mut output = vec![0u8; data.len() + 10];
+    let mut inflater = Box::new(InflaterManaged::new());
+    let result = inflater.inflate(&compressed, &mut output);
+    assert_eq!(result.error, None);
+    assert!(inflater.finished());
+    assert!(output[..result.bytes_written] == *data);
+
+    // small output makes the matches copied at the various positions of the window
+    let mut output = Vec::new();
+    let mut buffer = [0u8; 1000];
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut input = &compressed[..];
+    while !inflater.finished() {
+        let result = inflater.inflate(input, &mut buffer);
+        assert_eq!(result.error, None);
+        input = &input[result.bytes_consumed..];
+        output.extend_from_slice(&buffer[..result.bytes_written]);
+    }
+    assert!(output == data);
+}
+
+#[test]
+fn repeated_patterns() {
+    let mut state = 0x2545F491;
+    let mut data = Vec::new();
+    for period in [1, 2, 3, 4, 5, 7, 8, 9, 16, 31, 258, 1000] {
+        let pattern = random_bytes(&mut state, period);
+        data.extend(pattern.iter().cycle().take(70000));
+        data.extend(random_bytes(&mut state, 1000));
+    }
+    assert_roundtrip(&data);
+}
+
+#[test]
+fn long_runs() {
+    let mut data = vec![0u8; 300000];
+    data.extend(vec![0xFFu8; 200000]);
+    data.push(1);
+    data.extend(vec![0u8; 65538 * 3]);
+    assert_roundtrip(&data);
+}
+
+#[test]
+fn far_matches() {
+    let mut state = 0x2545F491;
+    let mut data = Vec::new();
+    for _ in 0..4 {
+        // few symbols so that the block is not stored as is
+        let block = random_bytes(&mut state, 65536)
+            .into_iter()
+            .map(|b| b & 0x0F)
+            .collect::<Vec<_>>();
+        data.extend_from_slice(&block);
+        data.extend_from_slice(&block);
+        data.extend(random_bytes(&mut state, 777));
+    }
+    assert_roundtrip(&data);
+}
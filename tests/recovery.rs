@@ -0,0 +1,143 @@
+#![cfg(feature = "alloc")]
+
+use deflate64::{DeflateFlush, DeflaterManaged, InflateRecovery};
+
+/// Text-like data which is compressed with dynamic blocks
+fn words(count: usize, seed: u32) -> Vec<u8> {
+    const WORDS: &[&str] = &[
+        "lorem", "ipsum", "dolor", "sit", "amet", "deflate", "block", "zip",
+    ];
+    let mut state = seed;
+    let mut data = Vec::new();
+    for _ in 0..count {
+        state = state.wrapping_mul(1103515245).wrapping_add(12345);
+        data.extend_from_slice(WORDS[(state >> 16) as usize % WORDS.len()].as_bytes());
+        data.push(b' ');
+    }
+    data
+}
+
+/// Compresses each chunk into separate blocks ending at a sync point
+fn compress_chunks(chunks: &[&[u8]]) -> (Vec<u8>, Vec<usize>) {
+    let mut deflater = DeflaterManaged::new();
+    let mut compressed = vec![0u8; chunks.iter().map(|x| x.len()).sum::<usize>() + 4096];
+    let mut written = 0;
+    let mut sync_points = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let flush = if i == chunks.len() - 1 {
+            DeflateFlush::Finish
+        } else {
+            DeflateFlush::Sync
+        };
+        let result = deflater.deflate(chunk, &mut compressed[written..], flush);
+        assert_eq!(result.bytes_consumed, chunk.len());
+        written += result.bytes_written;
+        sync_points.push(written);
+    }
+    assert!(deflater.finished());
+    compressed.truncate(written);
+    (compressed, sync_points)
+}
+
+#[test]
+fn valid_stream() {
+    let data = &words(20000, 1);
+    let (compressed, _) = compress_chunks(&[data]);
+
+    let recovered = InflateRecovery::new().recover(&compressed);
+    assert!(recovered.is_complete());
+    assert!(recovered.skipped_ranges().is_empty());
+    assert!(recovered.output() == data);
+}
+
+#[test]
+fn resume_after_corruption() {
+    // the last chunk doesn't refer to the lost chunk
+    let chunks = [
+        &words(10000, 1)[..],
+        &words(10000, 2),
+        &words(10000, 3).to_ascii_uppercase(),
+    ];
+    let (mut compressed, sync_points) = compress_chunks(&chunks);
+
+    // break the header of the middle chunk. BTYPE = 11 is invalid
+    let middle = sync_points[0];
+    compressed[middle..middle + 16].fill(0xFF);
+
+    let recovered = InflateRecovery::new()
+        .placeholder(b'?')
+        .recover(&compressed);
+    assert!(recovered.is_complete());
+    let output = recovered.output();
+    assert!(output[..chunks[0].len()] == *chunks[0]);
+
+    let ranges = recovered.skipped_ranges();
+    assert!(!ranges.is_empty());
+    let first = &ranges[0];
+    assert_eq!(first.start_bit_offset(), middle as u64 * 8);
+    assert_eq!(first.output_offset(), chunks[0].len() as u64);
+    let last = ranges.last().unwrap();
+    let resume = last.resume_bit_offset().unwrap();
+    assert!(resume > middle as u64 * 8 && resume <= sync_points[1] as u64 * 8);
+
+    assert!(output[last.output_offset() as usize..].ends_with(chunks[2]));
+}
+
+#[test]
+fn stored_blocks() {
+    let chunks = [
+        &b"first block. ".repeat(100)[..],
+        &b"second block. ".repeat(100)[..],
+    ];
+    let mut deflater = DeflaterManaged::with_level(0);
+    let mut compressed = vec![0u8; 8192];
+    let mut written = 0;
+    let result = deflater.deflate(chunks[0], &mut compressed, DeflateFlush::Sync);
+    written += result.bytes_written;
+    let result = deflater.deflate(chunks[1], &mut compressed[written..], DeflateFlush::Finish);
+    written += result.bytes_written;
+    compressed.truncate(written);
+
+    // break the header of the first block
+    compressed[0] = 0xFF;
+    compressed[1] = 0xFF;
+
+    let recovered = InflateRecovery::new().recover(&compressed);
+    assert!(recovered.is_complete());
+    assert_eq!(recovered.skipped_ranges()[0].start_bit_offset(), 0);
+    assert!(recovered.output().ends_with(chunks[1]));
+}
+
+#[test]
+fn truncated_stream() {
+    let data = &words(20000, 1);
+    let (compressed, _) = compress_chunks(&[data]);
+
+    let recovered = InflateRecovery::new().recover(&compressed[..compressed.len() / 2]);
+    assert!(!recovered.is_complete());
+    assert!(recovered.skipped_ranges().is_empty());
+    assert!(data.starts_with(recovered.output()));
+}
+
+#[test]
+fn garbage() {
+    let recovered = InflateRecovery::new_deflate().recover(&[0xFF; 64]);
+    assert!(!recovered.is_complete());
+    let ranges = recovered.skipped_ranges();
+    assert_eq!(ranges.last().unwrap().resume_bit_offset(), None);
+}
+
+#[test]
+fn trailing_data() {
+    // the data after the stream, such as the next zip entry, doesn't prevent resuming
+    let chunks = [&words(10000, 1)[..], &words(10000, 3).to_ascii_uppercase()];
+    let (mut compressed, _) = compress_chunks(&chunks);
+    compressed.extend_from_slice(b"trailing data");
+
+    // break the header of the first chunk
+    compressed[..16].fill(0xFF);
+
+    let recovered = InflateRecovery::new().recover(&compressed);
+    assert!(recovered.is_complete());
+    assert!(recovered.output().ends_with(chunks[1]));
+}
@@ -0,0 +1,137 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use common::compress;
+use deflate64::{Deflate64Decoder, InflateErrorKind, InflaterManaged, WindowStorage, WINDOW_SIZE};
+use std::io::{Cursor, Read};
+
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+fn inflate<W: WindowStorage>(inflater: &mut InflaterManaged<W>, compressed: &[u8]) -> Vec<u8> {
+    let mut output = vec![0u8; BINARY_WAV_DATA.len() + 10];
+    let result = inflater.inflate(compressed, &mut output);
+    assert_eq!(result.error, None);
+    assert!(inflater.finished());
+    assert_eq!(result.bytes_consumed, compressed.len());
+    output.truncate(result.bytes_written);
+    output
+}
+
+#[test]
+fn reuse_inflater() {
+    let first = &BINARY_WAV_DATA[..300000];
+    let second = &BINARY_WAV_DATA[300000..];
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    assert!(inflate(&mut inflater, &compress(first)) == first);
+    inflater.reset();
+    assert!(inflate(&mut inflater, &compress(second)) == second);
+}
+
+#[test]
+fn reset_in_middle_of_stream() {
+    let compressed = compress(BINARY_WAV_DATA);
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![0u8; 1000];
+    inflater.inflate(&compressed[..5000], &mut output);
+    assert!(!inflater.finished());
+
+    inflater.reset();
+    assert!(inflate(&mut inflater, &compressed) == BINARY_WAV_DATA);
+}
+
+#[test]
+fn reset_after_error() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![0u8; 1000];
+    // bfinal = 1, btype = 3
+    let result = inflater.inflate(&[0x07], &mut output);
+    assert_eq!(
+        result.error.map(|x| x.kind()),
+        Some(InflateErrorKind::UnknownBlockType)
+    );
+
+    inflater.reset();
+    assert_eq!(inflater.error(), None);
+    assert!(inflate(&mut inflater, &compress(b"hello")) == b"hello");
+}
+
+#[test]
+fn reset_with_uncompressed_size() {
+    let compressed = compress(BINARY_WAV_DATA);
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    inflater.reset_with_uncompressed_size(1000);
+    let mut output = vec![0u8; BINARY_WAV_DATA.len()];
+    let result = inflater.inflate(&compressed, &mut output);
+    assert_eq!(result.error, None);
+    assert!(output[..result.bytes_written] == BINARY_WAV_DATA[..1000]);
+
+    inflater.reset();
+    assert!(inflate(&mut inflater, &compressed) == BINARY_WAV_DATA);
+}
+
+#[test]
+fn reset_with_clear_window() {
+    let data = &BINARY_WAV_DATA[..300000];
+    let mut window = Box::new([0u8; WINDOW_SIZE]);
+    let mut inflater = InflaterManaged::with_window(&mut *window);
+    assert!(inflate(&mut inflater, &compress(data)) == data);
+
+    inflater.reset();
+    assert!(inflater.into_window().iter().any(|&x| x != 0));
+
+    let mut inflater = InflaterManaged::with_window(&mut *window);
+    inflater.reset_with(usize::MAX, true);
+    assert!(inflate(&mut inflater, &compress(b"hello")) == b"hello");
+    let window = inflater.into_window();
+    assert_eq!(window[..5], *b"hello");
+    assert!(window[5..].iter().all(|&x| x == 0));
+}
+
+#[test]
+fn reset_keeps_strict_mode() {
+    let mut input = compress(b"hello");
+    input.extend_from_slice(b"trailing");
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    inflater.set_strict(true);
+    inflater.reset();
+    let mut output = vec![0u8; 100];
+    let result = inflater.inflate(&input, &mut output);
+    assert_eq!(
+        result.error.map(|x| x.kind()),
+        Some(InflateErrorKind::TrailingData)
+    );
+}
+
+#[test]
+fn reset_removes_dictionary() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    inflater.set_dictionary(b"dictionary");
+    assert!(inflate(&mut inflater, &compress(b"hello")) == b"hello");
+
+    // the dictionary can be set again after reset
+    inflater.reset();
+    inflater.set_dictionary(b"another dictionary");
+    assert!(inflate(&mut inflater, &compress(b"world")) == b"world");
+}
+
+#[test]
+fn reset_decoder() {
+    let first = compress(&BINARY_WAV_DATA[..300000]);
+    let second = compress(&BINARY_WAV_DATA[300000..]);
+
+    let mut decoder = Deflate64Decoder::with_buffer(Cursor::new(&first[..]));
+    let mut output = Vec::new();
+    decoder.read_to_end(&mut output).unwrap();
+    assert!(output == BINARY_WAV_DATA[..300000]);
+
+    let previous = decoder.reset(Cursor::new(&second[..]));
+    assert_eq!(previous.position(), first.len() as u64);
+    let mut output = Vec::new();
+    decoder.read_to_end(&mut output).unwrap();
+    assert!(output == BINARY_WAV_DATA[300000..]);
+}
@@ -0,0 +1,203 @@
+#![cfg(all(feature = "checkpoint", feature = "std"))]
+
+mod common;
+
+use common::corrupt_after;
+use deflate64::{
+    DeflateFlush, DeflaterManaged, InflateLimits, InflaterManaged, SeekIndex,
+    SeekableDeflate64Decoder,
+};
+use proptest::prelude::*;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+const BINARY_WAV_DATA_OFFSET: usize = 40;
+const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
+
+static ZIP_FILE_DATA: &[u8] = include_bytes!("../test-assets/deflate64.zip");
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+const SPAN: u64 = 256 * 1024;
+
+fn source_stream() -> &'static [u8] {
+    &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE]
+}
+
+fn build_decoder() -> SeekableDeflate64Decoder<Cursor<&'static [u8]>> {
+    let index = SeekIndex::build(source_stream(), SPAN).unwrap();
+    // the stream is not at the start of the inner reader
+    let mut inner = Cursor::new(ZIP_FILE_DATA);
+    inner.set_position(BINARY_WAV_DATA_OFFSET as u64);
+    SeekableDeflate64Decoder::new(inner, index).unwrap()
+}
+
+fn read_at(decoder: &mut SeekableDeflate64Decoder<Cursor<&[u8]>>, offset: u64, len: usize) {
+    decoder.seek(SeekFrom::Start(offset)).unwrap();
+    let mut buf = vec![0u8; len];
+    decoder.read_exact(&mut buf).unwrap();
+    let offset = offset as usize;
+    assert_eq!(buf, &BINARY_WAV_DATA[offset..][..len], "at {offset}");
+}
+
+#[test]
+fn index() {
+    let index = SeekIndex::build(source_stream(), SPAN).unwrap();
+    assert_eq!(index.compressed_size(), BINARY_WAV_COMPRESSED_SIZE as u64);
+    assert_eq!(index.uncompressed_size(), BINARY_WAV_DATA.len() as u64);
+    assert_eq!(index.len() as u64, BINARY_WAV_DATA.len() as u64 / SPAN);
+}
+
+#[test]
+fn read_all() {
+    let mut decoder = build_decoder();
+    let mut uncompressed_data = vec![];
+    decoder.read_to_end(&mut uncompressed_data).unwrap();
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[test]
+fn seek_back_and_forth() {
+    let mut decoder = build_decoder();
+    read_at(&mut decoder, 2_000_000, 1000);
+    read_at(&mut decoder, 10, 1000);
+    read_at(&mut decoder, 300_000, 1000);
+    read_at(&mut decoder, 300_000, 100_000);
+    read_at(&mut decoder, 1_000_000, 1000);
+    read_at(&mut decoder, 1_000_500, 1000);
+
+    let end = decoder.seek(SeekFrom::End(-100)).unwrap();
+    assert_eq!(end, BINARY_WAV_DATA.len() as u64 - 100);
+    let mut rest = vec![];
+    decoder.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, &BINARY_WAV_DATA[BINARY_WAV_DATA.len() - 100..]);
+
+    decoder.seek(SeekFrom::Current(-50)).unwrap();
+    let mut rest = vec![];
+    decoder.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, &BINARY_WAV_DATA[BINARY_WAV_DATA.len() - 50..]);
+
+    // seek beyond the end
+    decoder.seek(SeekFrom::End(100)).unwrap();
+    assert_eq!(decoder.read(&mut [0u8; 10]).unwrap(), 0);
+    assert!(decoder.seek(SeekFrom::Current(-1_000_000_000)).is_err());
+}
+
+#[test]
+fn plain_deflate() {
+    let data = &BINARY_WAV_DATA[..500_000];
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+    std::io::Write::write_all(&mut encoder, data).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let index = SeekIndex::build_with_inflater(
+        &compressed[..],
+        64 * 1024,
+        Box::new(InflaterManaged::new_deflate()),
+    )
+    .unwrap();
+    assert!(!index.is_empty());
+    let mut decoder = SeekableDeflate64Decoder::new(Cursor::new(&compressed), index).unwrap();
+    decoder.seek(SeekFrom::Start(400_000)).unwrap();
+    let mut buf = vec![0u8; 10_000];
+    decoder.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, &data[400_000..410_000]);
+}
+
+#[test]
+fn dictionary() {
+    let dictionary = &BINARY_WAV_DATA[..60000];
+    let data = &BINARY_WAV_DATA[10000..300_000];
+    let mut deflater = DeflaterManaged::new();
+    deflater.set_dictionary(dictionary);
+    let mut compressed = vec![0u8; data.len() + 1024];
+    let result = deflater.deflate(data, &mut compressed, DeflateFlush::Finish);
+    assert!(deflater.finished());
+    compressed.truncate(result.bytes_written);
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    inflater.set_dictionary(dictionary);
+    let index = SeekIndex::build_with_inflater(&compressed[..], 64 * 1024, inflater).unwrap();
+    let mut decoder = SeekableDeflate64Decoder::new(Cursor::new(&compressed), index).unwrap();
+    for offset in [200_000, 0, 100] {
+        decoder.seek(SeekFrom::Start(offset)).unwrap();
+        let mut buf = vec![0u8; 1000];
+        decoder.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[offset as usize..][..1000], "at {offset}");
+    }
+}
+
+#[test]
+fn write_and_read_index() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    inflater.set_limits(InflateLimits::new().max_output(1 << 30).max_blocks(100_000));
+    inflater.set_strict(true);
+    inflater.set_dictionary(b"dictionary");
+    let index = SeekIndex::build_with_inflater(source_stream(), SPAN, inflater).unwrap();
+    let mut serialized = Vec::new();
+    index.write_to(&mut serialized).unwrap();
+
+    let read = SeekIndex::read_from(&serialized[..]).unwrap();
+    assert_eq!(read.len(), index.len());
+    assert_eq!(read.compressed_size(), index.compressed_size());
+    assert_eq!(read.uncompressed_size(), index.uncompressed_size());
+    let mut reserialized = Vec::new();
+    read.write_to(&mut reserialized).unwrap();
+    assert!(reserialized == serialized);
+
+    let mut decoder = SeekableDeflate64Decoder::new(Cursor::new(source_stream()), read).unwrap();
+    for offset in [2_000_000, 10, 1_000_000] {
+        decoder.seek(SeekFrom::Start(offset)).unwrap();
+        let mut buf = vec![0u8; 1000];
+        decoder.read_exact(&mut buf).unwrap();
+        assert_eq!(
+            buf,
+            &BINARY_WAV_DATA[offset as usize..][..1000],
+            "at {offset}"
+        );
+    }
+
+    let error = SeekIndex::read_from(&serialized[..serialized.len() - 1])
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    let mut invalid = serialized.clone();
+    invalid[0] ^= 1;
+    let error = SeekIndex::read_from(&invalid[..]).err().unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn output_before_error() {
+    // the index is built from the valid stream with one more block
+    let data = &BINARY_WAV_DATA[..1000];
+    let corrupted = corrupt_after(data);
+    let mut valid = corrupted[..corrupted.len() - 1].to_vec();
+    valid.extend_from_slice(&[0x01, 0x01, 0x00, 0xFE, 0xFF, 0x00]);
+    let index = SeekIndex::build(&valid[..], SPAN).unwrap();
+
+    let mut decoder = SeekableDeflate64Decoder::new(Cursor::new(&corrupted), index).unwrap();
+    let mut buf = vec![0u8; 2000];
+    let read = decoder.read(&mut buf).unwrap();
+    assert_eq!(&buf[..read], data);
+    let error = decoder.read(&mut buf).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn truncated() {
+    let error = SeekIndex::build(&source_stream()[..100_000], SPAN)
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn random_seek(offsets in prop::collection::vec(0..BINARY_WAV_DATA.len() as u64 - 1000, 1..8)) {
+        let mut decoder = build_decoder();
+        for offset in offsets {
+            read_at(&mut decoder, offset, 1000);
+        }
+    }
+}
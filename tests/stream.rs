@@ -1,5 +1,12 @@
-use deflate64::Deflate64Decoder;
-use std::io::{Cursor, Read};
+#![cfg(feature = "std")]
+
+mod common;
+
+use common::corrupt_after;
+use deflate64::{
+    Deflate64Decoder, Deflate64DecoderWriter, Deflate64Encoder, InflateErrorKind, InflateLimits,
+};
+use std::io::{Cursor, ErrorKind, Read, Write};
 
 const BINARY_WAV_DATA_OFFSET: usize = 40;
 const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
@@ -36,3 +43,143 @@ fn decode_from_buf_read() {
 
     assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
 }
+
+fn encode(data: &[u8], chunk: usize) -> Vec<u8> {
+    let mut encoder = Deflate64Encoder::new(Vec::new());
+    for chunk in data.chunks(chunk) {
+        encoder.write_all(chunk).unwrap();
+    }
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn encode_to_write() {
+    let compressed = encode(BINARY_WAV_DATA, 10000);
+
+    let mut decoder = Deflate64Decoder::new(Cursor::new(compressed));
+    let mut uncompressed_data = vec![];
+    decoder.read_to_end(&mut uncompressed_data).unwrap();
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[test]
+fn encode_empty() {
+    let compressed = encode(&[], 1);
+
+    let mut decoder = Deflate64Decoder::new(Cursor::new(compressed));
+    let mut uncompressed_data = vec![];
+    decoder.read_to_end(&mut uncompressed_data).unwrap();
+
+    assert!(uncompressed_data.is_empty());
+}
+
+#[test]
+fn encode_flush() {
+    let data = &BINARY_WAV_DATA[..100000];
+    let mut encoder = Deflate64Encoder::new(Vec::new());
+    encoder.write_all(&data[..30000]).unwrap();
+    encoder.flush().unwrap();
+
+    // all data written so far is available in the inner writer
+    let flushed = encoder.get_ref().clone();
+    assert!(flushed.ends_with(&[0x00, 0x00, 0xFF, 0xFF]));
+    let mut decoder = Deflate64Decoder::new(Cursor::new(flushed));
+    let mut uncompressed_data = vec![0u8; 30000];
+    decoder.read_exact(&mut uncompressed_data).unwrap();
+    assert_eq!(&uncompressed_data[..], &data[..30000]);
+
+    encoder.write_all(&data[30000..]).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let mut decoder = Deflate64Decoder::new(Cursor::new(compressed));
+    let mut uncompressed_data = vec![];
+    decoder.read_to_end(&mut uncompressed_data).unwrap();
+    assert_eq!(&uncompressed_data[..], data);
+}
+
+#[test]
+fn decode_to_write() {
+    let mut decoder = Deflate64DecoderWriter::new(Vec::new());
+    for chunk in source_stream().chunks(1000) {
+        decoder.write_all(chunk).unwrap();
+    }
+    let uncompressed_data = decoder.finish().unwrap();
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[test]
+fn decode_to_write_trailing_data() {
+    let mut compressed = source_stream().to_vec();
+    compressed.extend_from_slice(b"trailing");
+
+    let mut decoder = Deflate64DecoderWriter::new(Vec::new());
+    let mut input = &compressed[..];
+    let error = loop {
+        match decoder.write(input) {
+            Ok(consumed) => input = &input[consumed..],
+            Err(error) => break error,
+        }
+    };
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert_eq!(input, b"trailing");
+
+    let error = decoder.write_all(b"more").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    let uncompressed_data = decoder.finish().unwrap();
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[test]
+fn decode_to_write_output_before_error() {
+    let data = &BINARY_WAV_DATA[..50000];
+    let mut input = corrupt_after(data);
+    input.extend_from_slice(b"more");
+
+    let mut decoder = Deflate64DecoderWriter::new(Vec::new());
+    let error = decoder.write_all(&input).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert_eq!(decoder.get_ref()[..], data[..]);
+
+    let error = decoder.try_finish().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn decode_to_write_strict_and_limits() {
+    let mut compressed = source_stream().to_vec();
+    compressed.extend_from_slice(b"trailing");
+    let mut decoder = Deflate64DecoderWriter::new(Vec::new());
+    decoder.set_strict(true);
+    let error = decoder.write_all(&compressed).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    let inner = error.into_inner().unwrap();
+    let error = inner.downcast_ref::<deflate64::InflateError>().unwrap();
+    assert_eq!(error.kind(), InflateErrorKind::TrailingData);
+    assert_eq!(&decoder.get_ref()[..], BINARY_WAV_DATA);
+
+    let mut decoder = Deflate64DecoderWriter::new(Vec::new());
+    decoder.set_limits(InflateLimits::new().max_output(100000));
+    let error = decoder.write_all(source_stream()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert_eq!(decoder.get_ref().len(), 100000);
+    assert!(decoder.get_ref()[..] == BINARY_WAV_DATA[..100000]);
+}
+
+#[test]
+fn decode_to_write_truncated() {
+    let mut decoder = Deflate64DecoderWriter::new(Vec::new());
+    decoder
+        .write_all(&source_stream()[..source_stream().len() / 2])
+        .unwrap();
+    let error = decoder.try_finish().unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    assert!(decoder.get_ref().len() < BINARY_WAV_DATA.len());
+    assert_eq!(
+        decoder.get_ref()[..],
+        BINARY_WAV_DATA[..decoder.get_ref().len()]
+    );
+}
@@ -0,0 +1,139 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use common::compress;
+use deflate64::{Deflate64Decoder, InflateError, InflateErrorKind, InflaterManaged};
+use std::io::{Cursor, ErrorKind, Read};
+
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+/// Decompresses all of `input` in strict mode, returning the output and the error
+fn inflate_strict(input: &[u8], uncompressed_size: usize) -> (Vec<u8>, Option<InflateError>) {
+    let mut inflater = Box::new(InflaterManaged::with_uncompressed_size(uncompressed_size));
+    inflater.set_strict(true);
+    let mut output = vec![0u8; BINARY_WAV_DATA.len() * 2];
+    let mut written = 0;
+    let mut input = input;
+    loop {
+        let result = inflater.inflate(input, &mut output[written..]);
+        input = &input[result.bytes_consumed..];
+        written += result.bytes_written;
+        if result.error.is_some() || inflater.finished() {
+            output.truncate(written);
+            return (output, result.error);
+        }
+    }
+}
+
+#[test]
+fn exact_size() {
+    let compressed = compress(BINARY_WAV_DATA);
+    let (output, error) = inflate_strict(&compressed, BINARY_WAV_DATA.len());
+    assert_eq!(error, None);
+    assert!(output == BINARY_WAV_DATA);
+}
+
+#[test]
+fn output_too_long() {
+    let compressed = compress(BINARY_WAV_DATA);
+    let (output, error) = inflate_strict(&compressed, 1000);
+    assert_eq!(
+        error.map(|x| x.kind()),
+        Some(InflateErrorKind::OutputTooLong)
+    );
+    assert!(output == BINARY_WAV_DATA[..1000]);
+
+    // without strict mode, the output is silently truncated
+    let mut inflater = Box::new(InflaterManaged::with_uncompressed_size(1000));
+    let mut output = vec![0u8; BINARY_WAV_DATA.len()];
+    let result = inflater.inflate(&compressed, &mut output);
+    assert_eq!(result.error, None);
+    assert_eq!(result.bytes_written, 1000);
+}
+
+#[test]
+fn output_too_short() {
+    let compressed = compress(BINARY_WAV_DATA);
+    let (output, error) = inflate_strict(&compressed, BINARY_WAV_DATA.len() + 1);
+    assert_eq!(
+        error.map(|x| x.kind()),
+        Some(InflateErrorKind::OutputTooShort)
+    );
+    assert!(output == BINARY_WAV_DATA);
+}
+
+#[test]
+fn trailing_data() {
+    let compressed = compress(BINARY_WAV_DATA);
+    let mut input = compressed.clone();
+    input.extend_from_slice(b"trailing");
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    inflater.set_strict(true);
+    let mut output = vec![0u8; BINARY_WAV_DATA.len()];
+    let result = inflater.inflate(&input, &mut output);
+    let error = result.error.expect("expected an error");
+    assert_eq!(error.kind(), InflateErrorKind::TrailingData);
+    assert_eq!(
+        error.input_bit_offset().div_ceil(8),
+        compressed.len() as u64
+    );
+    assert_eq!(result.bytes_consumed, compressed.len());
+    assert_eq!(result.bytes_written, BINARY_WAV_DATA.len());
+}
+
+#[test]
+fn decoder_trailing_data() {
+    let mut input = compress(BINARY_WAV_DATA);
+    input.extend_from_slice(b"trailing");
+
+    let mut output = Vec::new();
+    Deflate64Decoder::new(Cursor::new(&input))
+        .read_to_end(&mut output)
+        .unwrap();
+    assert!(output == BINARY_WAV_DATA);
+
+    let mut decoder = Deflate64Decoder::new(Cursor::new(&input));
+    decoder.set_strict(true);
+    let error = decoder.read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    let error = error
+        .get_ref()
+        .and_then(|x| x.downcast_ref::<InflateError>())
+        .expect("expected InflateError");
+    assert_eq!(error.kind(), InflateErrorKind::TrailingData);
+}
+
+#[test]
+fn decoder_truncated() {
+    let compressed = compress(BINARY_WAV_DATA);
+    let truncated = &compressed[..compressed.len() / 2];
+
+    // without strict mode, the truncated stream ends silently
+    let mut output = Vec::new();
+    Deflate64Decoder::new(Cursor::new(truncated))
+        .read_to_end(&mut output)
+        .unwrap();
+    assert!(output.len() < BINARY_WAV_DATA.len());
+
+    let inflater = Box::new(InflaterManaged::with_uncompressed_size(
+        BINARY_WAV_DATA.len(),
+    ));
+    for mut decoder in [
+        Deflate64Decoder::with_buffer(Cursor::new(truncated)),
+        Deflate64Decoder::with_inflater(Cursor::new(truncated), inflater),
+    ] {
+        decoder.set_strict(true);
+        let mut output = Vec::new();
+        let error = decoder.read_to_end(&mut output).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        let error = error
+            .get_ref()
+            .and_then(|x| x.downcast_ref::<InflateError>())
+            .expect("expected InflateError");
+        assert_eq!(error.kind(), InflateErrorKind::UnexpectedEndOfInput);
+        assert_eq!(error.output_byte_offset(), output.len() as u64);
+        assert!(output == BINARY_WAV_DATA[..output.len()]);
+    }
+}
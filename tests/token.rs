@@ -0,0 +1,131 @@
+use deflate64::{InflaterManaged, Token};
+use std::io::Write;
+
+const BINARY_WAV_DATA_OFFSET: usize = 40;
+const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
+
+static ZIP_FILE_DATA: &[u8] = include_bytes!("../test-assets/deflate64.zip");
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+/// Rebuilds the output from tokens
+#[derive(Default)]
+struct Replay {
+    output: Vec<u8>,
+    bits: u64,
+    end_of_blocks: usize,
+}
+
+impl Replay {
+    fn token(&mut self, token: Token, bits: u32) {
+        match token {
+            Token::Literal(byte) => self.output.push(byte),
+            Token::Match { length, distance } => {
+                let start = self.output.len() - distance as usize;
+                for i in 0..length as usize {
+                    self.output.push(self.output[start + i]);
+                }
+            }
+            Token::EndOfBlock => self.end_of_blocks += 1,
+        }
+        self.bits += bits as u64;
+    }
+}
+
+fn flate2_compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn tokens_only() {
+    let compressed = &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE];
+    let mut replay = Replay::default();
+    let mut inflater = Box::new(InflaterManaged::new());
+    let result = inflater.inflate_tokens(compressed, &mut |token, bits| replay.token(token, bits));
+
+    assert!(!result.data_error());
+    assert!(inflater.finished());
+    assert_eq!(result.bytes_consumed, compressed.len());
+    assert_eq!(result.bytes_written, BINARY_WAV_DATA.len());
+    assert_eq!(replay.output, BINARY_WAV_DATA);
+    assert!(replay.bits < compressed.len() as u64 * 8);
+}
+
+#[test]
+fn tokens_with_output() {
+    let data = &BINARY_WAV_DATA[..300_000];
+    let compressed = flate2_compress(data);
+
+    let mut replay = Replay::default();
+    let mut inflater = Box::new(InflaterManaged::new_deflate());
+    let mut output = vec![];
+    let mut buffer = [0u8; 1000];
+    let mut input = &compressed[..];
+    while !inflater.finished() {
+        // small input to use slow path
+        let chunk = &input[..input.len().min(3)];
+        let result = inflater.inflate_with_tokens(chunk, &mut buffer, &mut |token, bits| {
+            replay.token(token, bits)
+        });
+        assert!(!result.data_error());
+        input = &input[result.bytes_consumed..];
+        output.extend_from_slice(&buffer[..result.bytes_written]);
+    }
+
+    assert_eq!(output, data);
+    assert_eq!(replay.output, data);
+    assert!(replay.end_of_blocks >= 1);
+}
+
+#[test]
+fn bit_costs() {
+    // single static block with literal 'a' and end of block
+    let compressed = flate2_compress(b"a");
+    let mut tokens = vec![];
+    let mut inflater = Box::new(InflaterManaged::new_deflate());
+    inflater.inflate_tokens(&compressed, &mut |token, bits| tokens.push((token, bits)));
+    assert_eq!(tokens, [(Token::Literal(b'a'), 8), (Token::EndOfBlock, 7)]);
+
+    // "abcabcabcabcabcabc" compressed by zlib with static block
+    let compressed = [0x4b, 0x4c, 0x4a, 0x4e, 0x44, 0x45, 0x00];
+    let mut tokens = vec![];
+    let mut inflater = Box::new(InflaterManaged::new_deflate());
+    inflater.inflate_tokens(&compressed, &mut |token, bits| tokens.push((token, bits)));
+    // length 14 is code 265 (7 bits) with 1 extra bit, and distance 3 is code 2 (5 bits)
+    assert_eq!(
+        tokens,
+        [
+            (Token::Literal(b'a'), 8),
+            (Token::Literal(b'b'), 8),
+            (Token::Literal(b'c'), 8),
+            (Token::Literal(b'a'), 8),
+            (
+                Token::Match {
+                    length: 14,
+                    distance: 3
+                },
+                7 + 1 + 5
+            ),
+            (Token::EndOfBlock, 7),
+        ]
+    );
+}
+
+#[test]
+fn stored_block() {
+    let mut compressed = vec![0x01, 0x03, 0x00, 0xFC, 0xFF];
+    compressed.extend_from_slice(b"abc");
+    let mut tokens = vec![];
+    let mut inflater = Box::new(InflaterManaged::new());
+    inflater.inflate_tokens(&compressed, &mut |token, bits| tokens.push((token, bits)));
+    assert_eq!(
+        tokens,
+        [
+            (Token::Literal(b'a'), 8),
+            (Token::Literal(b'b'), 8),
+            (Token::Literal(b'c'), 8),
+            (Token::EndOfBlock, 0),
+        ]
+    );
+}
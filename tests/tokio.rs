@@ -0,0 +1,73 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use common::corrupt_after;
+use deflate64::tokio::AsyncDeflate64Decoder;
+use deflate64::InflateError;
+use tokio::io::{AsyncReadExt, BufReader};
+
+const BINARY_WAV_DATA_OFFSET: usize = 40;
+const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
+
+static ZIP_FILE_DATA: &[u8] = include_bytes!("../test-assets/deflate64.zip");
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+fn source_stream() -> &'static [u8] {
+    &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE]
+}
+
+#[tokio::test]
+async fn decode_from_async_read() {
+    let mut decoder = AsyncDeflate64Decoder::new(source_stream());
+
+    let mut uncompressed_data = vec![];
+    decoder.read_to_end(&mut uncompressed_data).await.unwrap();
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[tokio::test]
+async fn decode_with_small_buffer() {
+    let inner = BufReader::with_capacity(7, source_stream());
+    let mut decoder = AsyncDeflate64Decoder::with_buffer(inner);
+
+    let mut uncompressed_data = vec![];
+    let mut buf = [0u8; 13];
+    loop {
+        let read = decoder.read(&mut buf).await.unwrap();
+        if read == 0 {
+            break;
+        }
+        uncompressed_data.extend_from_slice(&buf[..read]);
+    }
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+    assert!(decoder.get_ref().buffer().is_empty());
+}
+
+#[tokio::test]
+async fn decode_error() {
+    let mut decoder = AsyncDeflate64Decoder::with_buffer(&[0xFFu8; 16][..]);
+
+    let mut uncompressed_data = vec![];
+    let error = decoder
+        .read_to_end(&mut uncompressed_data)
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(error.into_inner().unwrap().is::<InflateError>());
+}
+
+#[tokio::test]
+async fn output_before_error() {
+    let data = &BINARY_WAV_DATA[..1000];
+    let stream = corrupt_after(data);
+    let mut decoder = AsyncDeflate64Decoder::with_buffer(&stream[..]);
+
+    let mut buf = vec![0u8; 2000];
+    let read = decoder.read(&mut buf).await.unwrap();
+    assert_eq!(&buf[..read], data);
+    let error = decoder.read(&mut buf).await.unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use common::compress;
+use deflate64::{Deflate64Decoder, InflaterManaged};
+use std::io::{Cursor, Read};
+
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+#[test]
+fn totals_match_results() {
+    let compressed = compress(BINARY_WAV_DATA);
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![0u8; 10000];
+    let mut input = &compressed[..];
+    let mut consumed = 0u64;
+    let mut written = 0u64;
+
+    while !inflater.finished() {
+        let result = inflater.inflate(&input[..input.len().min(3000)], &mut output);
+        assert_eq!(result.error, None);
+        input = &input[result.bytes_consumed..];
+        consumed += result.bytes_consumed as u64;
+        written += result.bytes_written as u64;
+
+        assert_eq!(inflater.total_in(), consumed);
+        assert_eq!(inflater.total_out(), written);
+        assert!(inflater.total_in_bits() <= consumed * 8);
+        assert!(inflater.total_in_bits() + 32 >= consumed * 8);
+    }
+
+    assert_eq!(inflater.total_in(), compressed.len() as u64);
+    assert_eq!(
+        inflater.total_in_bits().div_ceil(8),
+        compressed.len() as u64
+    );
+    assert_eq!(inflater.total_out(), BINARY_WAV_DATA.len() as u64);
+
+    inflater.reset();
+    assert_eq!(inflater.total_in(), 0);
+    assert_eq!(inflater.total_in_bits(), 0);
+    assert_eq!(inflater.total_out(), 0);
+}
+
+#[test]
+fn decoder_totals() {
+    let compressed = compress(BINARY_WAV_DATA);
+    let mut input = compressed.clone();
+    input.extend_from_slice(b"trailing");
+
+    let mut decoder = Deflate64Decoder::new(Cursor::new(input));
+    let mut output = Vec::new();
+    decoder.read_to_end(&mut output).unwrap();
+
+    assert_eq!(decoder.total_in(), compressed.len() as u64);
+    assert_eq!(decoder.total_in_bits().div_ceil(8), compressed.len() as u64);
+    assert_eq!(decoder.total_out(), BINARY_WAV_DATA.len() as u64);
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "alloc")]
+
+use deflate64::{
+    DeflateFlush, DeflaterManaged, InflateErrorKind, InflaterManaged, WindowStorage, WINDOW_SIZE,
+};
+
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+fn compress(data: &[u8], dictionary: &[u8]) -> Vec<u8> {
+    let mut deflater = DeflaterManaged::new();
+    deflater.set_dictionary(dictionary);
+    let mut compressed = vec![0u8; data.len() + 1024];
+    let result = deflater.deflate(data, &mut compressed, DeflateFlush::Finish);
+    assert!(deflater.finished());
+    compressed.truncate(result.bytes_written);
+    compressed
+}
+
+fn inflate<W: WindowStorage>(inflater: &mut InflaterManaged<W>, compressed: &[u8]) -> Vec<u8> {
+    let mut output = vec![0u8; BINARY_WAV_DATA.len() + 10];
+    let result = inflater.inflate(compressed, &mut output);
+    assert_eq!(result.error, None);
+    assert!(inflater.finished());
+    output.truncate(result.bytes_written);
+    output
+}
+
+#[test]
+fn borrowed_window() {
+    let first = &BINARY_WAV_DATA[..300000];
+    let second = &BINARY_WAV_DATA[300000..];
+    let mut window = Box::new([0u8; WINDOW_SIZE]);
+
+    // the window is reused for another stream
+    let mut inflater = InflaterManaged::with_window(&mut *window);
+    assert!(inflate(&mut inflater, &compress(first, &[])) == first);
+    let mut inflater = InflaterManaged::with_window(&mut *window);
+    assert!(inflate(&mut inflater, &compress(second, &[])) == second);
+}
+
+#[test]
+fn boxed_window() {
+    let window: Box<[u8; WINDOW_SIZE]> = vec![0u8; WINDOW_SIZE]
+        .into_boxed_slice()
+        .try_into()
+        .unwrap();
+    let mut inflater = InflaterManaged::with_window(window);
+    let output = inflate(&mut inflater, &compress(BINARY_WAV_DATA, &[]));
+    assert!(output == BINARY_WAV_DATA);
+
+    let window = inflater.into_window();
+    assert_eq!(window.len(), WINDOW_SIZE);
+}
+
+#[test]
+fn stale_window_is_not_output() {
+    let dictionary = &BINARY_WAV_DATA[..60000];
+    let data = &BINARY_WAV_DATA[10000..20000];
+    let compressed = compress(data, dictionary);
+
+    // the window has the data which the stream refers as the dictionary
+    let mut window = Box::new([0u8; WINDOW_SIZE]);
+    window[WINDOW_SIZE - dictionary.len()..].copy_from_slice(dictionary);
+
+    let mut inflater = InflaterManaged::with_window(&mut *window);
+    let mut output = vec![0u8; data.len()];
+    let result = inflater.inflate(&compressed, &mut output);
+    assert_eq!(
+        result.error.map(|x| x.kind()),
+        Some(InflateErrorKind::DistanceTooFar)
+    );
+
+    let mut inflater = InflaterManaged::with_window(&mut *window);
+    inflater.set_dictionary(dictionary);
+    assert!(inflate(&mut inflater, &compressed) == data);
+}
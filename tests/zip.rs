@@ -0,0 +1,283 @@
+#![cfg(feature = "zip")]
+
+use deflate64::zip::{CompressionMethod, ZipArchive};
+use deflate64::{DeflateFlush, DeflaterManaged};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::{Cursor, ErrorKind, Read, Write};
+
+static ZIP_FILE_DATA: &[u8] = include_bytes!("../test-assets/deflate64.zip");
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+static FIRST_TXT_DATA: &[u8] = include_bytes!("../test-assets/folder/first.txt");
+static SECOND_TXT_DATA: &[u8] = include_bytes!("../test-assets/folder/notempty/second.txt");
+
+fn read_entry<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>, name: &str) -> Vec<u8> {
+    let mut data = Vec::new();
+    archive
+        .by_name(name)
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    data
+}
+
+#[test]
+fn entries() {
+    let archive = ZipArchive::new(Cursor::new(ZIP_FILE_DATA)).unwrap();
+    let names = archive
+        .entries()
+        .iter()
+        .map(|entry| entry.name())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "binary.wmv",
+            "empty.file",
+            "first.txt",
+            "notempty/",
+            "notempty/second.txt"
+        ]
+    );
+
+    let binary_wav = &archive.entries()[0];
+    assert_eq!(
+        binary_wav.compression_method(),
+        CompressionMethod::Deflate64
+    );
+    assert_eq!(binary_wav.size(), BINARY_WAV_DATA.len() as u64);
+    assert!(!binary_wav.is_dir());
+    assert!(archive.entries()[3].is_dir());
+}
+
+#[test]
+fn extract_all() {
+    let mut archive = ZipArchive::new(Cursor::new(ZIP_FILE_DATA)).unwrap();
+    assert_eq!(read_entry(&mut archive, "binary.wmv"), BINARY_WAV_DATA);
+    assert_eq!(read_entry(&mut archive, "empty.file"), b"");
+    assert_eq!(read_entry(&mut archive, "first.txt"), FIRST_TXT_DATA);
+    assert_eq!(read_entry(&mut archive, "notempty/"), b"");
+    assert_eq!(
+        read_entry(&mut archive, "notempty/second.txt"),
+        SECOND_TXT_DATA
+    );
+}
+
+#[test]
+fn not_found() {
+    let mut archive = ZipArchive::new(Cursor::new(ZIP_FILE_DATA)).unwrap();
+    assert_eq!(
+        archive.by_name("no-such-file").err().unwrap().kind(),
+        ErrorKind::NotFound
+    );
+    assert_eq!(
+        archive.by_index(5).err().unwrap().kind(),
+        ErrorKind::NotFound
+    );
+}
+
+#[test]
+fn crc_mismatch() {
+    let mut zip = ZIP_FILE_DATA.to_vec();
+    let position = zip
+        .windows(FIRST_TXT_DATA.len())
+        .position(|window| window == FIRST_TXT_DATA)
+        .unwrap();
+    zip[position] ^= 1;
+
+    let mut archive = ZipArchive::new(Cursor::new(zip)).unwrap();
+    let mut data = Vec::new();
+    let error = archive
+        .by_name("first.txt")
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn not_a_zip() {
+    let error = ZipArchive::new(Cursor::new(BINARY_WAV_DATA)).err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
+
+struct ZipEntryData {
+    name: &'static str,
+    method: u16,
+    data: Vec<u8>,
+    compressed: Vec<u8>,
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Creates zip64 archive with data descriptors.
+/// All sizes and offsets in the central directory are stored in the zip64 extra field.
+fn create_zip64_with_data_descriptor(entries: &[ZipEntryData]) -> Vec<u8> {
+    let mut zip = Vec::new();
+    let mut offsets = Vec::new();
+
+    for entry in entries {
+        offsets.push(zip.len() as u64);
+        // local file header
+        zip.extend_from_slice(&[0x50, 0x4B, 0x03, 0x04]);
+        zip.extend_from_slice(&45u16.to_le_bytes()); // version needed to extract
+        zip.extend_from_slice(&(1u16 << 3).to_le_bytes()); // flags: data descriptor
+        zip.extend_from_slice(&entry.method.to_le_bytes());
+        zip.extend_from_slice(&[0; 4]); // last mod time / date
+        zip.extend_from_slice(&[0; 12]); // crc and sizes are in data descriptor
+        zip.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+        zip.extend_from_slice(entry.name.as_bytes());
+        zip.extend_from_slice(&entry.compressed);
+
+        // data descriptor
+        zip.extend_from_slice(&[0x50, 0x4B, 0x07, 0x08]);
+        zip.extend_from_slice(&crc32(&entry.data).to_le_bytes());
+        zip.extend_from_slice(&(entry.compressed.len() as u64).to_le_bytes());
+        zip.extend_from_slice(&(entry.data.len() as u64).to_le_bytes());
+    }
+
+    let central_directory_offset = zip.len() as u64;
+    for (entry, offset) in entries.iter().zip(offsets) {
+        zip.extend_from_slice(&[0x50, 0x4B, 0x01, 0x02]);
+        zip.extend_from_slice(&45u16.to_le_bytes()); // version made by
+        zip.extend_from_slice(&45u16.to_le_bytes()); // version needed to extract
+        zip.extend_from_slice(&(1u16 << 3).to_le_bytes()); // flags: data descriptor
+        zip.extend_from_slice(&entry.method.to_le_bytes());
+        zip.extend_from_slice(&[0; 4]); // last mod time / date
+        zip.extend_from_slice(&crc32(&entry.data).to_le_bytes());
+        zip.extend_from_slice(&[0xFF; 8]); // sizes are in zip64 extra field
+        zip.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&28u16.to_le_bytes()); // extra field length
+        zip.extend_from_slice(&[0; 6]); // comment length, disk, internal attributes
+        zip.extend_from_slice(&[0; 4]); // external attributes
+        zip.extend_from_slice(&[0xFF; 4]); // local header offset is in zip64 extra field
+        zip.extend_from_slice(entry.name.as_bytes());
+        // zip64 extra field
+        zip.extend_from_slice(&1u16.to_le_bytes());
+        zip.extend_from_slice(&24u16.to_le_bytes());
+        zip.extend_from_slice(&(entry.data.len() as u64).to_le_bytes());
+        zip.extend_from_slice(&(entry.compressed.len() as u64).to_le_bytes());
+        zip.extend_from_slice(&offset.to_le_bytes());
+    }
+    let central_directory_size = zip.len() as u64 - central_directory_offset;
+
+    // zip64 end of central directory
+    let zip64_end_of_central_directory_offset = zip.len() as u64;
+    zip.extend_from_slice(&[0x50, 0x4B, 0x06, 0x06]);
+    zip.extend_from_slice(&44u64.to_le_bytes()); // size of the record
+    zip.extend_from_slice(&45u16.to_le_bytes()); // version made by
+    zip.extend_from_slice(&45u16.to_le_bytes()); // version needed to extract
+    zip.extend_from_slice(&[0; 8]); // disk numbers
+    zip.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+    zip.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+    zip.extend_from_slice(&central_directory_size.to_le_bytes());
+    zip.extend_from_slice(&central_directory_offset.to_le_bytes());
+
+    // zip64 end of central directory locator
+    zip.extend_from_slice(&[0x50, 0x4B, 0x06, 0x07]);
+    zip.extend_from_slice(&0u32.to_le_bytes()); // disk number
+    zip.extend_from_slice(&zip64_end_of_central_directory_offset.to_le_bytes());
+    zip.extend_from_slice(&1u32.to_le_bytes()); // total number of disks
+
+    // end of central directory
+    zip.extend_from_slice(&[0x50, 0x4B, 0x05, 0x06]);
+    zip.extend_from_slice(&[0; 4]); // disk numbers
+    zip.extend_from_slice(&[0xFF; 4]); // entry counts
+    zip.extend_from_slice(&[0xFF; 8]); // central directory size and offset
+    zip.extend_from_slice(&0u16.to_le_bytes()); // comment length
+
+    zip
+}
+
+#[test]
+fn zip64_with_data_descriptor() {
+    let data = BINARY_WAV_DATA[..200000].to_vec();
+
+    let mut deflater = DeflaterManaged::new();
+    let mut deflate64 = vec![0u8; data.len() + 1024];
+    let result = deflater.deflate(&data, &mut deflate64, DeflateFlush::Finish);
+    deflate64.truncate(result.bytes_written);
+
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&data).unwrap();
+    let deflate = encoder.finish().unwrap();
+
+    let zip = create_zip64_with_data_descriptor(&[
+        ZipEntryData {
+            name: "deflate64.bin",
+            method: 9,
+            data: data.clone(),
+            compressed: deflate64,
+        },
+        ZipEntryData {
+            name: "deflate.bin",
+            method: 8,
+            data: data.clone(),
+            compressed: deflate,
+        },
+        ZipEntryData {
+            name: "stored.txt",
+            method: 0,
+            data: FIRST_TXT_DATA.to_vec(),
+            compressed: FIRST_TXT_DATA.to_vec(),
+        },
+    ]);
+
+    let mut archive = ZipArchive::new(Cursor::new(zip)).unwrap();
+    assert_eq!(archive.len(), 3);
+    assert_eq!(
+        archive.entries()[1].compression_method(),
+        CompressionMethod::Deflate
+    );
+    assert_eq!(read_entry(&mut archive, "deflate64.bin"), data);
+    assert_eq!(read_entry(&mut archive, "deflate.bin"), data);
+    assert_eq!(read_entry(&mut archive, "stored.txt"), FIRST_TXT_DATA);
+
+    // the inflater is reused for the next entry, also after reading a part of the entry
+    let mut partial = [0u8; 1000];
+    let mut entry = archive.by_name("deflate.bin").unwrap();
+    entry.read_exact(&mut partial).unwrap();
+    assert_eq!(partial, data[..1000]);
+    assert_eq!(read_entry(&mut archive, "deflate64.bin"), data);
+    assert_eq!(read_entry(&mut archive, "deflate.bin"), data);
+}
+
+#[test]
+fn trailing_data_in_entry() {
+    let data = FIRST_TXT_DATA.to_vec();
+    let mut deflater = DeflaterManaged::new();
+    let mut compressed = vec![0u8; data.len() + 1024];
+    let result = deflater.deflate(&data, &mut compressed, DeflateFlush::Finish);
+    compressed.truncate(result.bytes_written);
+    compressed.extend_from_slice(b"garbage");
+
+    let zip = create_zip64_with_data_descriptor(&[ZipEntryData {
+        name: "first.txt",
+        method: 9,
+        data,
+        compressed,
+    }]);
+
+    let mut archive = ZipArchive::new(Cursor::new(zip)).unwrap();
+    let mut data = Vec::new();
+    let error = archive
+        .by_name("first.txt")
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
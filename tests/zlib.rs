@@ -0,0 +1,184 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use common::corrupt_after;
+use deflate64::{DeflateFlush, DeflaterManaged, ZlibDeflate64Decoder, ZlibError};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{BufRead, Cursor, ErrorKind, Read, Write};
+
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+/// Creates zlib stream with deflate64. If dictionary is specified, the stream refers it.
+fn create_zlib_deflate64(data: &[u8], dictionary: Option<&[u8]>) -> Vec<u8> {
+    let mut deflater = DeflaterManaged::new();
+    let mut compressed = vec![0u8; data.len() + dictionary.map_or(0, <[u8]>::len) + 1024];
+
+    // blocks after the sync point of the dictionary can refer the dictionary as history
+    let mut start = 0;
+    if let Some(dictionary) = dictionary {
+        let result = deflater.deflate(dictionary, &mut compressed, DeflateFlush::Sync);
+        start = result.bytes_written;
+    }
+    let result = deflater.deflate(data, &mut compressed[start..], DeflateFlush::Finish);
+    assert!(deflater.finished());
+    let body = &compressed[start..][..result.bytes_written];
+
+    // CINFO = 8, CM = 8
+    let cmf = 0x88u8;
+    let mut flg = if dictionary.is_some() { 0x20 } else { 0 };
+    flg += 31 - ((cmf as u16 * 256 + flg as u16) % 31) as u8;
+
+    let mut zlib = vec![cmf, flg];
+    if let Some(dictionary) = dictionary {
+        zlib.extend_from_slice(&adler32(dictionary).to_be_bytes());
+    }
+    zlib.extend_from_slice(body);
+    zlib.extend_from_slice(&adler32(data).to_be_bytes());
+    zlib
+}
+
+fn decode(zlib: &[u8], dictionary: Option<&[u8]>) -> std::io::Result<Vec<u8>> {
+    let mut decoder = match dictionary {
+        Some(dictionary) => ZlibDeflate64Decoder::with_dictionary(Cursor::new(zlib), dictionary),
+        None => ZlibDeflate64Decoder::with_buffer(Cursor::new(zlib)),
+    };
+    let mut uncompressed = Vec::new();
+    decoder.read_to_end(&mut uncompressed)?;
+    Ok(uncompressed)
+}
+
+fn zlib_error(error: std::io::Error) -> ZlibError {
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    *error
+        .get_ref()
+        .unwrap()
+        .downcast_ref::<ZlibError>()
+        .unwrap()
+}
+
+#[test]
+fn deflate64() {
+    let data = &BINARY_WAV_DATA[..500000];
+    let zlib = create_zlib_deflate64(data, None);
+    assert_eq!(decode(&zlib, None).unwrap(), data);
+}
+
+#[test]
+fn plain_deflate() {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(BINARY_WAV_DATA).unwrap();
+    let zlib = encoder.finish().unwrap();
+    assert_eq!(zlib[0] >> 4, 7);
+
+    assert_eq!(decode(&zlib, None).unwrap(), BINARY_WAV_DATA);
+}
+
+#[test]
+fn dictionary() {
+    let dictionary = &BINARY_WAV_DATA[..100000];
+    let data = &BINARY_WAV_DATA[50000..100000];
+    let zlib = create_zlib_deflate64(data, Some(dictionary));
+    // data is encoded as matches into the dictionary
+    assert!(zlib.len() < 1000, "{}", zlib.len());
+
+    assert_eq!(decode(&zlib, Some(dictionary)).unwrap(), data);
+
+    let expected = adler32(dictionary);
+    assert_eq!(
+        zlib_error(decode(&zlib, None).unwrap_err()),
+        ZlibError::DictionaryRequired(expected)
+    );
+    assert_eq!(
+        zlib_error(decode(&zlib, Some(b"wrong")).unwrap_err()),
+        ZlibError::DictionaryMismatch {
+            expected,
+            actual: adler32(b"wrong")
+        }
+    );
+}
+
+#[test]
+fn checksum_mismatch() {
+    let data = &BINARY_WAV_DATA[..10000];
+    let mut zlib = create_zlib_deflate64(data, None);
+    let last = zlib.len() - 1;
+    zlib[last] ^= 1;
+
+    assert_eq!(
+        zlib_error(decode(&zlib, None).unwrap_err()),
+        ZlibError::ChecksumMismatch {
+            expected: adler32(data) ^ 1,
+            actual: adler32(data),
+        }
+    );
+}
+
+#[test]
+fn invalid_header() {
+    let zlib = create_zlib_deflate64(b"", None);
+
+    let mut invalid_check = zlib.clone();
+    invalid_check[1] ^= 1;
+    assert_eq!(
+        zlib_error(decode(&invalid_check, None).unwrap_err()),
+        ZlibError::InvalidHeaderCheck
+    );
+
+    assert_eq!(
+        zlib_error(decode(&[0x87, 0x00], None).unwrap_err()),
+        ZlibError::UnknownCompressionMethod(7)
+    );
+    assert_eq!(
+        zlib_error(decode(&[0x98, 0x00], None).unwrap_err()),
+        ZlibError::InvalidWindowSize(9)
+    );
+}
+
+#[test]
+fn truncated() {
+    let zlib = create_zlib_deflate64(&BINARY_WAV_DATA[..10000], None);
+    for len in [1, 10, zlib.len() - 2] {
+        let error = decode(&zlib[..len], None).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof, "len {len}");
+    }
+}
+
+#[test]
+fn exact_end_of_stream() {
+    // the stream must be read exactly to the end so the following data can be read
+    let mut zlib = create_zlib_deflate64(&BINARY_WAV_DATA[..10000], None);
+    zlib.extend_from_slice(b"following data");
+
+    let mut decoder = ZlibDeflate64Decoder::new(Cursor::new(&zlib));
+    let mut uncompressed = Vec::new();
+    decoder.read_to_end(&mut uncompressed).unwrap();
+    assert_eq!(uncompressed, &BINARY_WAV_DATA[..10000]);
+
+    let mut inner = decoder.into_inner();
+    assert_eq!(inner.fill_buf().unwrap(), b"following data");
+}
+
+#[test]
+fn output_before_error() {
+    let data = &BINARY_WAV_DATA[..1000];
+    let mut zlib = vec![0x78, 0x9C];
+    zlib.extend_from_slice(&corrupt_after(data));
+
+    let mut decoder = ZlibDeflate64Decoder::new(Cursor::new(&zlib));
+    let mut buf = vec![0u8; 2000];
+    let read = decoder.read(&mut buf).unwrap();
+    assert_eq!(&buf[..read], data);
+    let error = decoder.read(&mut buf).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}